@@ -30,7 +30,7 @@ fn main() -> Result<()> {
 
 fn get_git_hash() -> Result<String> {
     let output = Command::new("git")
-        .args(&["rev-parse", "--short", "HEAD"])
+        .args(["rev-parse", "--short", "HEAD"])
         .output()?;
 
     if !output.status.success() {
@@ -50,7 +50,7 @@ fn get_git_hash() -> Result<String> {
 fn get_rustc_version() -> Result<String> {
     let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
 
-    let output = Command::new(rustc).args(&["--version"]).output()?;
+    let output = Command::new(rustc).args(["--version"]).output()?;
 
     if !output.status.success() {
         eprintln!("{}", String::from_utf8_lossy(&output.stderr));
@@ -0,0 +1,255 @@
+//! Reconstructs the board of a kyoku by replaying mjai events.
+
+use crate::mjai::Event;
+use crate::Pai;
+
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+/// The board of a kyoku as seen from the log, where every hand is known.
+#[derive(Debug, Clone, Default)]
+pub struct Board {
+    /// Concealed pais of each player, including the ones in a pending tsumo.
+    pub tehais: [Vec<Pai>; 4],
+    /// Discarded pais that are still in each player's river, i.e. excluding
+    /// the ones called by others.
+    pub kawas: [Vec<Pai>; 4],
+    /// All pais in each player's fuuros, including ankans.
+    pub fuuros: [Vec<Pai>; 4],
+    /// Revealed dora indicators. The indicator of an ankan is revealed before
+    /// its rinshan tsumo, while that of a daiminkan or kakan is revealed only
+    /// with the discard after the rinshan tsumo, as ordered by the Dora
+    /// events of [`tenhou_to_mjai`](crate::tenhou_to_mjai).
+    pub dora_indicators: Vec<Pai>,
+}
+
+/// The pais of each kind a seat has not seen yet, which may still be drawn
+/// or be in the concealed hand of another player.
+///
+/// This is the one place that tells how many of a pai are left, shared by
+/// [`shanten::ukeire`](crate::shanten::ukeire) and the waits and suji in
+/// [`safety`](crate::safety), so they all agree. Aka count as normal 5s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiveTiles {
+    counts: [u8; 34],
+}
+
+/// Whose concealed pais a [`BoardSnapshot`] shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    /// Only the hero's, as seen at the table.
+    HeroOnly,
+    /// Everyone's, as seen from the log, for teaching.
+    Full,
+}
+
+impl Default for Visibility {
+    #[inline]
+    fn default() -> Self {
+        Self::HeroOnly
+    }
+}
+
+/// The board from the view of a seat, see [`Board::snapshot`].
+///
+/// A masked hand still has one `Pai::Unknown` per concealed pai, as the
+/// number of pais in a hand is public. Pais are serialized as mjai strings.
+#[serde_as]
+#[derive(Debug, Clone, Serialize)]
+pub struct BoardSnapshot {
+    pub hero_seat: u8,
+    pub visibility: Visibility,
+    #[serde_as(as = "[Vec<DisplayFromStr>; 4]")]
+    pub tehais: [Vec<Pai>; 4],
+    #[serde_as(as = "[Vec<DisplayFromStr>; 4]")]
+    pub kawas: [Vec<Pai>; 4],
+    #[serde_as(as = "[Vec<DisplayFromStr>; 4]")]
+    pub fuuros: [Vec<Pai>; 4],
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    pub dora_indicators: Vec<Pai>,
+}
+
+impl Board {
+    /// Replays `events` from the beginning, where `events` should start with
+    /// a StartKyoku.
+    pub fn replay(events: &[Event]) -> Self {
+        let mut board = Self::default();
+        events.iter().for_each(|ev| board.update(ev));
+        board
+    }
+
+    pub fn update(&mut self, event: &Event) {
+        match *event {
+            Event::StartKyoku {
+                dora_marker,
+                tehais,
+                ..
+            } => {
+                *self = Self::default();
+                for (tehai, haipai) in self.tehais.iter_mut().zip(&tehais) {
+                    tehai.extend_from_slice(haipai);
+                }
+                self.dora_indicators.push(dora_marker);
+            }
+
+            Event::Tsumo { actor, pai } => self.tehais[actor as usize].push(pai),
+
+            Event::Dahai { actor, pai, .. } => {
+                remove_pai(&mut self.tehais[actor as usize], pai);
+                self.kawas[actor as usize].push(pai);
+            }
+
+            Event::Chi {
+                actor,
+                target,
+                pai,
+                consumed,
+            }
+            | Event::Pon {
+                actor,
+                target,
+                pai,
+                consumed,
+            } => self.call(actor, target, pai, &consumed.as_array()),
+
+            Event::Daiminkan {
+                actor,
+                target,
+                pai,
+                consumed,
+            } => self.call(actor, target, pai, &consumed.as_array()),
+
+            Event::Kakan { actor, pai, .. } => {
+                remove_pai(&mut self.tehais[actor as usize], pai);
+                self.fuuros[actor as usize].push(pai);
+            }
+
+            Event::Ankan { actor, consumed } => {
+                for &pai in &consumed.as_array() {
+                    remove_pai(&mut self.tehais[actor as usize], pai);
+                }
+                self.fuuros[actor as usize].extend_from_slice(&consumed.as_array());
+            }
+
+            Event::Dora { dora_marker } => self.dora_indicators.push(dora_marker),
+
+            _ => (),
+        }
+    }
+
+    /// Takes a snapshot of the board from `hero_seat`, where the concealed
+    /// pais of the others are masked as `Pai::Unknown` unless `visibility` is
+    /// [`Visibility::Full`].
+    pub fn snapshot(&self, hero_seat: u8, visibility: Visibility) -> BoardSnapshot {
+        let mut tehais = self.tehais.clone();
+        if visibility == Visibility::HeroOnly {
+            for (seat, tehai) in tehais.iter_mut().enumerate() {
+                if seat != hero_seat as usize {
+                    tehai.iter_mut().for_each(|p| *p = Pai::Unknown);
+                }
+            }
+        }
+
+        BoardSnapshot {
+            hero_seat,
+            visibility,
+            tehais,
+            kawas: self.kawas.clone(),
+            fuuros: self.fuuros.clone(),
+            dora_indicators: self.dora_indicators.clone(),
+        }
+    }
+
+    /// Returns all pais visible to `hero_seat`, which are
+    ///
+    /// * the concealed pais of `hero_seat`,
+    /// * the rivers of all players,
+    /// * the fuuros of all players, including ankans and the called pais,
+    /// * the revealed dora indicators.
+    ///
+    /// The concealed pais of other players, the wall and the rest of the dead
+    /// wall, including ura dora indicators, are not visible.
+    pub fn visible_tiles(&self, hero_seat: u8) -> Vec<Pai> {
+        let mut ret = self.tehais[hero_seat as usize].clone();
+        self.kawas.iter().for_each(|k| ret.extend_from_slice(k));
+        self.fuuros.iter().for_each(|f| ret.extend_from_slice(f));
+        ret.extend_from_slice(&self.dora_indicators);
+        ret
+    }
+
+    /// Returns the [`LiveTiles`] of `hero_seat`, which are all the pais but
+    /// the [`visible_tiles`](Self::visible_tiles).
+    #[inline]
+    pub fn live_tiles(&self, hero_seat: u8) -> LiveTiles {
+        LiveTiles::from_visible(&self.visible_tiles(hero_seat))
+    }
+
+    fn call(&mut self, actor: u8, target: u8, pai: Pai, consumed: &[Pai]) {
+        for &p in consumed {
+            remove_pai(&mut self.tehais[actor as usize], p);
+        }
+
+        // the called pai leaves the river for the fuuro
+        let kawa = &mut self.kawas[target as usize];
+        if let Some(idx) = kawa.iter().rposition(|&p| p == pai) {
+            kawa.remove(idx);
+        }
+
+        let fuuro = &mut self.fuuros[actor as usize];
+        fuuro.push(pai);
+        fuuro.extend_from_slice(consumed);
+    }
+}
+
+/// Returns the visible pais to `hero_seat` after replaying the first `turn`
+/// events of `kyoku_events`. See [`Board::visible_tiles`] for what counts as
+/// visible.
+#[inline]
+pub fn visible_tiles(kyoku_events: &[Event], hero_seat: u8, turn: usize) -> Vec<Pai> {
+    let turn = turn.min(kyoku_events.len());
+    Board::replay(&kyoku_events[..turn]).visible_tiles(hero_seat)
+}
+
+impl LiveTiles {
+    /// Subtracts `visible` from the 4 pais of each kind. Unknown pais are
+    /// ignored, and a kind never goes below 0.
+    pub fn from_visible(visible: &[Pai]) -> Self {
+        let mut counts = [4_u8; 34];
+        for idx in visible.iter().filter_map(|p| p.as_index34()) {
+            counts[idx] = counts[idx].saturating_sub(1);
+        }
+        Self { counts }
+    }
+
+    /// Returns the number of live pais of `pai`, or 0 for an unknown pai.
+    #[inline]
+    pub fn count(&self, pai: Pai) -> u8 {
+        pai.as_index34().map_or(0, |idx| self.counts[idx])
+    }
+
+    /// Returns the number of live pais of the kind `idx`, as given by
+    /// [`Pai::as_index34`].
+    #[inline]
+    pub fn count34(&self, idx: usize) -> u8 {
+        self.counts[idx]
+    }
+
+    #[inline]
+    pub fn is_live(&self, pai: Pai) -> bool {
+        self.count(pai) > 0
+    }
+
+    /// Returns the number of live pais over `pais`, counting each kind as
+    /// many times as it is given.
+    #[inline]
+    pub fn sum(&self, pais: &[Pai]) -> usize {
+        pais.iter().map(|&p| self.count(p) as usize).sum()
+    }
+}
+
+fn remove_pai(pais: &mut Vec<Pai>, pai: Pai) {
+    if let Some(idx) = pais.iter().position(|&p| p == pai) {
+        pais.remove(idx);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::mjai;
 use crate::tenhou;
+use crate::yaku;
 use crate::Pai;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
@@ -47,6 +48,33 @@ pub enum ConvertError {
         honba: u8,
         actor: u8,
     },
+
+    #[error("unexpected mjai event: {0:?}")]
+    UnexpectedEvent(mjai::Event),
+
+    #[error("previous pon not found for kakan: {0:?}")]
+    PonNotFoundForKakan(mjai::Event),
+
+    #[error("missing start_game event")]
+    MissingStartGame,
+
+    #[error("invalid start_kyoku: bakaze {bakaze:?} kyoku {kyoku}")]
+    InvalidStartKyoku { bakaze: Pai, kyoku: u8 },
+
+    #[error("kita is not supported in mjai format: {0:?}")]
+    UnsupportedKita(String),
+
+    #[error(
+        "unresolvable tsumogiri: \
+        at kyoku {kyoku} honba {honba} for actor {seat} at turn {turn}"
+    )]
+    UnresolvableTsumogiri {
+        kyoku: u8,
+        honba: u8,
+        seat: u8,
+        /// Index of the take in "取" that the tsumogiri should discard.
+        turn: usize,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, ConvertError>;
@@ -56,8 +84,26 @@ struct BackTrack {
     use_the_first_branch: bool,
 }
 
+/// Options of [`tenhou_to_mjai_with_options`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConvertOptions {
+    /// Return [`ConvertError::UnresolvableTsumogiri`] when a tsumogiri in
+    /// "出" does not follow a draw in "取", instead of discarding
+    /// `Pai::Unknown`.
+    pub strict_tsumogiri: bool,
+}
+
 /// Transform a tenhou.net/6 format log into mjai format.
+#[inline]
 pub fn tenhou_to_mjai(log: &tenhou::Log) -> Result<Vec<mjai::Event>> {
+    tenhou_to_mjai_with_options(log, ConvertOptions::default())
+}
+
+/// Same as [`tenhou_to_mjai`], with options.
+pub fn tenhou_to_mjai_with_options(
+    log: &tenhou::Log,
+    options: ConvertOptions,
+) -> Result<Vec<mjai::Event>> {
     let mut events = vec![mjai::Event::StartGame {
         kyoku_first: log.game_length as u8,
         aka_flag: log.has_aka,
@@ -65,7 +111,7 @@ pub fn tenhou_to_mjai(log: &tenhou::Log) -> Result<Vec<mjai::Event>> {
     }];
 
     for kyoku in &log.kyokus {
-        let kyoku_events = tenhou_kyoku_to_mjai_events(kyoku)?;
+        let kyoku_events = tenhou_kyoku_to_mjai_events(kyoku, options)?;
         events.extend(kyoku_events);
     }
 
@@ -73,15 +119,29 @@ pub fn tenhou_to_mjai(log: &tenhou::Log) -> Result<Vec<mjai::Event>> {
     Ok(events)
 }
 
-fn tenhou_kyoku_to_mjai_events(kyoku: &tenhou::Kyoku) -> Result<Vec<mjai::Event>> {
+pub(crate) fn tenhou_kyoku_to_mjai_events(
+    kyoku: &tenhou::Kyoku,
+    options: ConvertOptions,
+) -> Result<Vec<mjai::Event>> {
     // First of all, transform all takes and discards to events.
     let (take_events, discard_events): (Vec<_>, Vec<_>) = (0..4)
         .map(|a| {
-            parse_takes_and_discards_to_mjai(
+            let (takes, discards, unresolved) = parse_takes_and_discards_to_mjai(
                 a,
                 &kyoku.action_tables[a as usize].takes,
                 &kyoku.action_tables[a as usize].discards,
-            )
+            )?;
+            match unresolved {
+                Some(turn) if options.strict_tsumogiri => {
+                    Err(ConvertError::UnresolvableTsumogiri {
+                        kyoku: kyoku.meta.kyoku_num,
+                        honba: kyoku.meta.honba,
+                        seat: a,
+                        turn,
+                    })
+                }
+                _ => Ok((takes, discards)),
+            }
         })
         .collect::<Result<Vec<_>>>()?
         .into_iter()
@@ -91,13 +151,8 @@ fn tenhou_kyoku_to_mjai_events(kyoku: &tenhou::Kyoku) -> Result<Vec<mjai::Event>
     let mut backtracks = HashMap::new();
 
     // Then emit the events in order.
-    let oya = kyoku.meta.kyoku_num % 4;
-    let bakaze = match kyoku.meta.kyoku_num / 4 {
-        0 => Pai::East,
-        1 => Pai::South,
-        2 => Pai::West,
-        _ => Pai::North,
-    };
+    let oya = kyoku.oya();
+    let bakaze = kyoku.round_wind();
 
     let attempt = |backtracks: &mut HashMap<Pai, BackTrack>| -> Result<Vec<mjai::Event>> {
         let mut events = vec![];
@@ -151,7 +206,7 @@ fn tenhou_kyoku_to_mjai_events(kyoku: &tenhou::Kyoku) -> Result<Vec<mjai::Event>
         loop {
             // Start to process a take event.
             let take =
-                &*take_events[actor]
+                take_events[actor]
                     .get(take_i[actor])
                     .ok_or(ConvertError::InsufficientTakes {
                         kyoku: kyoku.meta.kyoku_num,
@@ -402,7 +457,7 @@ fn tenhou_kyoku_to_mjai_events(kyoku: &tenhou::Kyoku) -> Result<Vec<mjai::Event>
                         }
                         Entry::Occupied(mut o) => {
                             // This is where the backtrack happens.
-                            let mut bc = o.get_mut();
+                            let bc = o.get_mut();
                             if bc.use_the_first_branch {
                                 // When this branch is reached, it is likely the
                                 // first branch has failed, that is, the real naki
@@ -442,23 +497,29 @@ fn tenhou_kyoku_to_mjai_events(kyoku: &tenhou::Kyoku) -> Result<Vec<mjai::Event>
     }
 }
 
+/// Also returns the turn of the first tsumogiri that can not be resolved, see
+/// [`finalize_discards`].
 fn parse_takes_and_discards_to_mjai(
     actor: u8,
     takes: &[tenhou::ActionItem],
     discards: &[tenhou::ActionItem],
-) -> Result<(Vec<mjai::Event>, Vec<mjai::Event>)> {
+) -> Result<(Vec<mjai::Event>, Vec<mjai::Event>, Option<usize>)> {
     let mjai_takes = take_action_to_events(actor, takes)?;
     let mut mjai_discards = discard_action_to_events(actor, discards)?;
-    finalize_discards(&mjai_takes, &mut mjai_discards);
+    let unresolved = finalize_discards(&mjai_takes, &mut mjai_discards);
 
-    Ok((mjai_takes, mjai_discards))
+    Ok((mjai_takes, mjai_discards, unresolved))
 }
 
 /// 1. fill in possible tsumogiri pais
 /// 2. skip discards of daiminkans
-fn finalize_discards(takes: &[mjai::Event], discards: &mut Vec<mjai::Event>) {
+///
+/// Returns the index in `takes` of the first tsumogiri that does not follow a
+/// tsumo, whose pai is left as `Pai::Unknown`.
+fn finalize_discards(takes: &[mjai::Event], discards: &mut Vec<mjai::Event>) -> Option<usize> {
+    let mut unresolved = None;
     let mut di = 0;
-    for take in takes {
+    for (ti, take) in takes.iter().enumerate() {
         if di >= discards.len() {
             break;
         }
@@ -480,6 +541,8 @@ fn finalize_discards(takes: &[mjai::Event], discards: &mut Vec<mjai::Event>) {
                         tsumogiri,
                         actor,
                     }
+                } else {
+                    unresolved = unresolved.or(Some(ti));
                 }
             } else if pai == Pai::Unknown {
                 // `take` is daiminkan, skip one discard and immediately consume
@@ -491,6 +554,22 @@ fn finalize_discards(takes: &[mjai::Event], discards: &mut Vec<mjai::Event>) {
 
         di += 1;
     }
+
+    // tsumogiris left without any take
+    let has_more_tsumogiri = discards[di.min(discards.len())..].iter().any(|d| {
+        matches!(
+            d,
+            mjai::Event::Dahai {
+                tsumogiri: true,
+                ..
+            }
+        )
+    });
+    if has_more_tsumogiri {
+        unresolved = unresolved.or(Some(takes.len()));
+    }
+
+    unresolved
 }
 
 fn take_action_to_events(actor: u8, takes: &[tenhou::ActionItem]) -> Result<Vec<mjai::Event>> {
@@ -658,8 +737,11 @@ fn discard_action_to_events(
             tenhou::ActionItem::Naki(naki_string) => {
                 let naki = naki_string.as_bytes();
 
-                // only ankan, kakan and reach are possible
-                if let Some(idx) = naki_string.find('k') {
+                // only ankan, kakan and reach are possible, kita only
+                // happens in sanma which mjai does not support
+                if naki_string.starts_with('f') {
+                    return Err(ConvertError::UnsupportedKita(naki_string.clone()));
+                } else if let Some(idx) = naki_string.find('k') {
                     // kakan
 
                     if naki_string.len() != 9 {
@@ -735,7 +817,7 @@ fn discard_action_to_events(
                     // reach
                     // e.g. "r35" => discard 5s to reach
 
-                    if naki_string.len() != 3 {
+                    if naki_string.len() != 3 || naki[0] != b'r' {
                         return Err(ConvertError::InvalidNaki(naki_string.clone()));
                     }
 
@@ -770,7 +852,7 @@ fn end_kyoku(events: &mut Vec<mjai::Event>, kyoku: &tenhou::Kyoku) {
             }));
         }
 
-        tenhou::kyoku::EndStatus::Ryukyoku { score_deltas } => {
+        tenhou::kyoku::EndStatus::Ryukyoku { score_deltas, .. } => {
             events.push(mjai::Event::Ryukyoku {
                 deltas: Some(*score_deltas),
             });
@@ -780,6 +862,45 @@ fn end_kyoku(events: &mut Vec<mjai::Event>, kyoku: &tenhou::Kyoku) {
     events.push(mjai::Event::EndKyoku);
 }
 
+/// Tells 九種九牌 and 四風連打 from the kyoku so far and the event before
+/// the ryukyoku, which mjai does not record, or returns Unknown.
+///
+/// 九種九牌 is declared right after a tsumo, while any other ryukyoku comes
+/// after a discard. 四風連打 is the only one after exactly one discard of
+/// each player without any naki, all of the same wind.
+fn guess_first_turn_abort(
+    kyoku: &tenhou::Kyoku,
+    last_event: &mjai::Event,
+) -> tenhou::kyoku::RyukyokuReason {
+    use tenhou::kyoku::RyukyokuReason;
+
+    if matches!(last_event, mjai::Event::Tsumo { .. }) {
+        return RyukyokuReason::KyuushuKyuuhai;
+    }
+
+    let first_discards: Vec<_> = kyoku
+        .action_tables
+        .iter()
+        .filter(|t| t.takes.len() == 1 && t.discards.len() == 1)
+        .filter_map(|t| match (&t.takes[0], &t.discards[0]) {
+            (tenhou::ActionItem::Pai(_), tenhou::ActionItem::Pai(pai)) => Some(*pai),
+            (tenhou::ActionItem::Pai(pai), tenhou::ActionItem::Tsumogiri(_)) => Some(*pai),
+            _ => None,
+        })
+        .collect();
+    let is_suufuu_renda = first_discards.len() == 4
+        && matches!(
+            first_discards[0],
+            Pai::East | Pai::South | Pai::West | Pai::North
+        )
+        && first_discards.iter().all(|&p| p == first_discards[0]);
+    if is_suufuu_renda {
+        RyukyokuReason::SuufuuRenda
+    } else {
+        RyukyokuReason::Unknown
+    }
+}
+
 fn pai_from_bytes(b: &[u8]) -> Result<Pai> {
     let s = String::from_utf8_lossy(b);
     let id: u8 = s
@@ -788,3 +909,274 @@ fn pai_from_bytes(b: &[u8]) -> Result<Pai> {
 
     Pai::try_from(id).map_err(|_| ConvertError::InvalidPai(s.clone().into_owned()))
 }
+
+/// Transform a mjai format log back into tenhou.net/6 format.
+///
+/// This is the inverse of `tenhou_to_mjai`, which allows logs from other
+/// sources to go through the same path as tenhou logs. Information that does
+/// not exist in mjai, such as the reason of a ryukyoku, is left unknown.
+pub fn mjai_to_tenhou(events: &[mjai::Event]) -> Result<tenhou::Log> {
+    let (game_length, has_aka, names) = match events.first() {
+        Some(mjai::Event::StartGame {
+            kyoku_first,
+            aka_flag,
+            names,
+        }) => {
+            let game_length = if *kyoku_first == tenhou::GameLength::Tonpuu as u8 {
+                tenhou::GameLength::Tonpuu
+            } else {
+                tenhou::GameLength::Hanchan
+            };
+            (game_length, *aka_flag, names.clone())
+        }
+        Some(event) => return Err(ConvertError::UnexpectedEvent(event.clone())),
+        None => return Err(ConvertError::MissingStartGame),
+    };
+
+    let mut kyokus = vec![];
+    let mut kyoku: Option<tenhou::Kyoku> = None;
+    // Pons of each actor, recorded for looking up the direction of kakans.
+    let mut pons: [Vec<(u8, Pai, mjai::Consumed2)>; 4] = Default::default();
+    let mut reach_flags = [false; 4];
+
+    for (i, event) in events.iter().enumerate().skip(1) {
+        if let mjai::Event::StartKyoku {
+            bakaze,
+            dora_marker,
+            kyoku: kk,
+            honba,
+            kyotaku,
+            scores,
+            tehais,
+            ..
+        } = *event
+        {
+            if kyoku.is_some() {
+                return Err(ConvertError::UnexpectedEvent(event.clone()));
+            }
+            let is_wind = matches!(bakaze, Pai::East | Pai::South | Pai::West | Pai::North);
+            if !is_wind || !(1..=4).contains(&kk) {
+                return Err(ConvertError::InvalidStartKyoku { bakaze, kyoku: kk });
+            }
+
+            let action_table = |actor: usize| tenhou::ActionTable {
+                haipai: tehais[actor],
+                takes: vec![],
+                discards: vec![],
+            };
+            kyoku = Some(tenhou::Kyoku {
+                meta: tenhou::kyoku::Meta {
+                    kyoku_num: (bakaze.as_u8() - Pai::East.as_u8()) * 4 + kk - 1,
+                    honba,
+                    kyotaku,
+                },
+                scoreboard: scores,
+                dora_indicators: vec![dora_marker],
+                ura_indicators: vec![],
+                action_tables: [
+                    action_table(0),
+                    action_table(1),
+                    action_table(2),
+                    action_table(3),
+                ],
+                end_status: tenhou::kyoku::EndStatus::Ryukyoku {
+                    reason: tenhou::kyoku::RyukyokuReason::Unknown,
+                    score_deltas: [0; 4],
+                    tenpai_seats: [false; 4],
+                },
+            });
+            pons = Default::default();
+            reach_flags = [false; 4];
+            continue;
+        }
+
+        match *event {
+            mjai::Event::EndGame => break,
+            mjai::Event::None | mjai::Event::ReachAccepted { .. } => continue,
+            _ => (),
+        }
+
+        let current = kyoku
+            .as_mut()
+            .ok_or_else(|| ConvertError::UnexpectedEvent(event.clone()))?;
+
+        match *event {
+            mjai::Event::Tsumo { actor, pai } => {
+                current.action_tables[actor as usize]
+                    .takes
+                    .push(tenhou::ActionItem::Pai(pai));
+            }
+
+            mjai::Event::Dahai {
+                actor,
+                pai,
+                tsumogiri,
+            } => {
+                let item = if reach_flags[actor as usize] {
+                    reach_flags[actor as usize] = false;
+                    let pai_string = if tsumogiri {
+                        "60".to_owned()
+                    } else {
+                        pai.as_u8().to_string()
+                    };
+                    tenhou::ActionItem::Naki(format!("r{}", pai_string))
+                } else if tsumogiri {
+                    tenhou::ActionItem::Tsumogiri(60)
+                } else {
+                    tenhou::ActionItem::Pai(pai)
+                };
+
+                current.action_tables[actor as usize].discards.push(item);
+            }
+
+            mjai::Event::Reach { actor } => reach_flags[actor as usize] = true,
+
+            mjai::Event::Chi {
+                actor,
+                pai,
+                consumed,
+                ..
+            } => {
+                let [c0, c1] = consumed.as_array();
+                let naki = format!("c{}{}{}", pai.as_u8(), c0.as_u8(), c1.as_u8());
+
+                current.action_tables[actor as usize]
+                    .takes
+                    .push(tenhou::ActionItem::Naki(naki));
+            }
+
+            mjai::Event::Pon {
+                actor,
+                target,
+                pai,
+                consumed,
+            } => {
+                let [c0, c1] = consumed.as_array();
+                let (p, c0, c1) = (pai.as_u8(), c0.as_u8(), c1.as_u8());
+                let naki = match (target + 4 - actor) % 4 {
+                    3 => format!("p{}{}{}", p, c0, c1),
+                    2 => format!("{}p{}{}", c0, p, c1),
+                    _ => format!("{}{}p{}", c0, c1, p),
+                };
+
+                pons[actor as usize].push((target, pai, consumed));
+                current.action_tables[actor as usize]
+                    .takes
+                    .push(tenhou::ActionItem::Naki(naki));
+            }
+
+            mjai::Event::Daiminkan {
+                actor,
+                target,
+                pai,
+                consumed,
+            } => {
+                let [c0, c1, c2] = consumed.as_array();
+                let (p, c0, c1, c2) = (pai.as_u8(), c0.as_u8(), c1.as_u8(), c2.as_u8());
+                let naki = match (target + 4 - actor) % 4 {
+                    3 => format!("m{}{}{}{}", p, c0, c1, c2),
+                    2 => format!("{}m{}{}{}", c0, p, c1, c2),
+                    _ => format!("{}{}{}m{}", c0, c1, c2, p),
+                };
+
+                // Daiminkan takes a placeholder in the discard table.
+                let action_table = &mut current.action_tables[actor as usize];
+                action_table.takes.push(tenhou::ActionItem::Naki(naki));
+                action_table
+                    .discards
+                    .push(tenhou::ActionItem::Pai(Pai::Unknown));
+            }
+
+            mjai::Event::Kakan {
+                actor,
+                pai,
+                consumed,
+            } => {
+                let (target, pon_pai, pon_consumed) = pons[actor as usize]
+                    .iter()
+                    .copied()
+                    .find(|&(_, pon_pai, pon_consumed)| {
+                        let [c0, c1] = pon_consumed.as_array();
+                        mjai::Consumed3::from([pon_pai, c0, c1]) == consumed
+                    })
+                    .ok_or_else(|| ConvertError::PonNotFoundForKakan(event.clone()))?;
+
+                let [c0, c1] = pon_consumed.as_array();
+                let (a, p, c0, c1) = (pai.as_u8(), pon_pai.as_u8(), c0.as_u8(), c1.as_u8());
+                let naki = match (target + 4 - actor) % 4 {
+                    3 => format!("k{}{}{}{}", a, p, c0, c1),
+                    2 => format!("{}k{}{}{}", c0, a, p, c1),
+                    _ => format!("{}{}k{}{}", c0, c1, a, p),
+                };
+
+                current.action_tables[actor as usize]
+                    .discards
+                    .push(tenhou::ActionItem::Naki(naki));
+            }
+
+            mjai::Event::Ankan { actor, consumed } => {
+                let [c0, c1, c2, c3] = consumed.as_array();
+                let naki = format!("{}{}{}a{}", c0.as_u8(), c1.as_u8(), c2.as_u8(), c3.as_u8(),);
+
+                current.action_tables[actor as usize]
+                    .discards
+                    .push(tenhou::ActionItem::Naki(naki));
+            }
+
+            mjai::Event::Dora { dora_marker } => current.dora_indicators.push(dora_marker),
+
+            mjai::Event::Hora {
+                actor,
+                target,
+                deltas,
+                ref ura_markers,
+            } => {
+                let detail = tenhou::kyoku::HoraDetail {
+                    who: actor,
+                    target,
+                    score_deltas: deltas.unwrap_or_default(),
+                    pao: yaku::pao_seat(events, i).filter(|&p| p != target),
+                };
+
+                if let Some(ura_markers) = ura_markers {
+                    current.ura_indicators = ura_markers.clone();
+                }
+                match &mut current.end_status {
+                    tenhou::kyoku::EndStatus::Hora { details } => details.push(detail),
+                    status => {
+                        *status = tenhou::kyoku::EndStatus::Hora {
+                            details: vec![detail],
+                        }
+                    }
+                };
+            }
+
+            mjai::Event::Ryukyoku { deltas } => {
+                // mjai does not tell the reason, guess tenpai from the deltas
+                // as if it is an exhaustive draw
+                let score_deltas = deltas.unwrap_or_default();
+                current.end_status = tenhou::kyoku::EndStatus::Ryukyoku {
+                    reason: guess_first_turn_abort(current, &events[i - 1]),
+                    score_deltas,
+                    tenpai_seats: tenhou::kyoku::tenpai_seats_from("流局", &score_deltas),
+                };
+            }
+
+            mjai::Event::EndKyoku => {
+                // `current` is always Some here.
+                kyokus.extend(kyoku.take());
+            }
+
+            _ => return Err(ConvertError::UnexpectedEvent(event.clone())),
+        };
+    }
+
+    Ok(tenhou::Log {
+        names,
+        game_length,
+        game_kind: tenhou::GameKind::Yonma, // mjai does not support sanma
+        has_aka,
+        kyokus,
+        warnings: vec![],
+    })
+}
@@ -0,0 +1,106 @@
+//! A stable identifier of a decision, shared by the report, annotations and
+//! anything else that needs to refer to a single decision.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use thiserror::Error;
+
+const BAKAZE: [char; 4] = ['E', 'S', 'W', 'N'];
+
+/// Identifies the decision of `seat` at `turn` in a kyoku, formatted as
+/// "E1-0-s2-t7", which is E1 honba 0, seat 2, turn 7.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, SerializeDisplay, DeserializeFromStr,
+)]
+pub struct DecisionId {
+    pub kyoku: u8, // in tenhou.net/6 format, counts from 0
+    pub honba: u8,
+    pub seat: u8,
+    pub turn: u8, // same as junme
+}
+
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("expected 4 parts separated by \"-\", got {0}")]
+    WrongPartCount(usize),
+
+    #[error(r#"invalid kyoku: {0:?} (expected "E1" through "N4")"#)]
+    InvalidKyoku(String),
+    #[error("invalid honba: {0:?}")]
+    InvalidHonba(String),
+    #[error(r#"invalid seat: {0:?} (expected "s0" through "s3")"#)]
+    InvalidSeat(String),
+    #[error(r#"invalid turn: {0:?} (expected e.g. "t7")"#)]
+    InvalidTurn(String),
+}
+
+impl DecisionId {
+    #[inline]
+    pub const fn new(kyoku: u8, honba: u8, seat: u8, turn: u8) -> Self {
+        Self {
+            kyoku,
+            honba,
+            seat,
+            turn,
+        }
+    }
+}
+
+impl fmt::Display for DecisionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}-{}-s{}-t{}",
+            BAKAZE[self.kyoku as usize / 4 % 4],
+            self.kyoku % 4 + 1,
+            self.honba,
+            self.seat,
+            self.turn,
+        )
+    }
+}
+
+impl FromStr for DecisionId {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split('-').collect();
+        let (kyoku, honba, seat, turn) = match parts[..] {
+            [kyoku, honba, seat, turn] => (kyoku, honba, seat, turn),
+            _ => return Err(ParseError::WrongPartCount(parts.len())),
+        };
+
+        let invalid_kyoku = || ParseError::InvalidKyoku(kyoku.to_owned());
+        let mut chars = kyoku.chars();
+        let bakaze = chars
+            .next()
+            .and_then(|c| BAKAZE.iter().position(|&b| b == c.to_ascii_uppercase()))
+            .ok_or_else(invalid_kyoku)?;
+        let kyoku_num = match (chars.next().and_then(|c| c.to_digit(10)), chars.next()) {
+            (Some(n @ 1..=4), None) => n,
+            _ => return Err(invalid_kyoku()),
+        };
+
+        let honba = honba
+            .parse()
+            .map_err(|_| ParseError::InvalidHonba(honba.to_owned()))?;
+        let seat = seat
+            .strip_prefix('s')
+            .and_then(|n| n.parse().ok())
+            .filter(|&n| n < 4)
+            .ok_or_else(|| ParseError::InvalidSeat(seat.to_owned()))?;
+        let turn = turn
+            .strip_prefix('t')
+            .and_then(|n| n.parse().ok())
+            .ok_or_else(|| ParseError::InvalidTurn(turn.to_owned()))?;
+
+        Ok(Self {
+            kyoku: (bakaze * 4) as u8 + kyoku_num as u8 - 1,
+            honba,
+            seat,
+            turn,
+        })
+    }
+}
@@ -0,0 +1,61 @@
+//! Exporters of [`Log`] into formats consumed by other tools.
+//!
+//! To add a format, implement [`ExternalFormat`] for a new unit struct in
+//! this module and re-export it from here. An exporter should only depend
+//! on the public API of [`Log`], and report any problem through
+//! [`ExportError`], adding a variant if none fits.
+
+use crate::conv::{tenhou_to_mjai, ConvertError};
+use crate::tenhou::Log;
+use std::io::{self, Write};
+
+use serde_json as json;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("failed to convert log: {0}")]
+    Convert(#[from] ConvertError),
+
+    #[error("failed to serialize: {0}")]
+    Serialize(#[from] json::Error),
+
+    #[error("failed to write: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// A format that a [`Log`] can be exported into.
+pub trait ExternalFormat {
+    /// A short name to show to users, such as "mjai".
+    fn name(&self) -> &'static str;
+
+    /// The file extension of exported files, without the leading dot.
+    fn extension(&self) -> &'static str;
+
+    /// Writes `log` in this format into `w`.
+    fn export(&self, log: &Log, w: &mut dyn Write) -> Result<(), ExportError>;
+}
+
+/// mjai format, one JSON event per line.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mjai;
+
+impl ExternalFormat for Mjai {
+    #[inline]
+    fn name(&self) -> &'static str {
+        "mjai"
+    }
+
+    #[inline]
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn export(&self, log: &Log, w: &mut dyn Write) -> Result<(), ExportError> {
+        for event in tenhou_to_mjai(log)? {
+            json::to_writer(&mut *w, &event)?;
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+//! Tracks how the hand of a player develops over a kyoku, from the direction
+//! its haipai leans toward to its value turn by turn.
+
+use crate::board::Board;
+use crate::mjai::Event;
+use crate::shanten;
+use crate::yaku::dora_han;
+use crate::Pai;
+use std::cmp::Reverse;
+
+/// The state of the hero's hand right after one of their discards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandValue {
+    /// Index of the Dahai event in the kyoku events.
+    pub turn: usize,
+    /// The junme of the discard as counted by the review, one for each tsumo,
+    /// chi and pon of the hero, so a daiminkan and its rinshan tsumo are one.
+    pub junme: u8,
+    pub shanten: i8,
+    /// Kinds of pai, indexed by [`Pai::as_index34`], that complete the hand.
+    /// Empty unless tenpai.
+    pub waits: Vec<usize>,
+    /// The estimated han of the hand if it wins, only when tenpai.
+    pub han: Option<HanRange>,
+}
+
+/// The range of han a tenpai hand may win with over all of its waits.
+///
+/// `min` is the cheapest wait won by ron without riichi, while `max` is the
+/// most valuable wait, with riichi and menzen tsumo added for a closed hand.
+/// Neither counts ura dora, ippatsu or yaku that depend on how the hand is
+/// split into mentsu, such as pinfu, iipeikou or ittsuu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HanRange {
+    pub min: u8,
+    pub max: u8,
+}
+
+/// Returns the [`HandValue`] of `hero_seat` after each of their discards in
+/// `kyoku_events`, which should start with a StartKyoku.
+///
+/// `has_aka` should come from [`Log::has_aka`](crate::tenhou::Log::has_aka).
+/// The han estimate only counts dora, aka, tanyao, yakuhai, chiitoitsu,
+/// honitsu and chinitsu, which can be told from the pais alone.
+pub fn progression(kyoku_events: &[Event], hero_seat: u8, has_aka: bool) -> Vec<HandValue> {
+    let mut board = Board::default();
+    let mut yakuhai = vec![];
+    let mut fuuro_count = 0;
+    let mut is_closed = true;
+    let mut junme = 0;
+    let mut ret = vec![];
+
+    for (turn, event) in kyoku_events.iter().enumerate() {
+        board.update(event);
+        match *event {
+            Event::StartKyoku { bakaze, oya, .. } => {
+                let seat_wind =
+                    Pai::from_index34(27 + (hero_seat + 4 - oya) as usize % 4).unwrap_or_default();
+                yakuhai = vec![bakaze, seat_wind, Pai::Haku, Pai::Hatsu, Pai::Chun];
+                fuuro_count = 0;
+                is_closed = true;
+                junme = 0;
+            }
+            Event::Tsumo { actor, .. } if actor == hero_seat => junme += 1,
+            Event::Chi { actor, .. } | Event::Pon { actor, .. } if actor == hero_seat => {
+                fuuro_count += 1;
+                is_closed = false;
+                junme += 1;
+            }
+            Event::Daiminkan { actor, .. } if actor == hero_seat => {
+                fuuro_count += 1;
+                is_closed = false;
+            }
+            Event::Ankan { actor, .. } if actor == hero_seat => fuuro_count += 1,
+            Event::Dahai { actor, .. } if actor == hero_seat => {
+                let tehai = &board.tehais[hero_seat as usize];
+                let tehai34 = counts34(tehai);
+
+                let shanten = shanten::calc(&tehai34, fuuro_count);
+                let waits = if shanten == 0 {
+                    shanten::waits(&tehai34, fuuro_count)
+                } else {
+                    vec![]
+                };
+                let han = waits
+                    .iter()
+                    .filter_map(|&kind| Pai::from_index34(kind))
+                    .map(|agari_pai| {
+                        let mut pais = tehai.clone();
+                        pais.push(agari_pai);
+                        pais.extend_from_slice(&board.fuuros[hero_seat as usize]);
+                        dora_han(&pais, &board.dora_indicators, has_aka)
+                            + shape_han(&pais, &yakuhai, is_closed)
+                    })
+                    .fold(None, |range: Option<HanRange>, han| {
+                        Some(range.map_or(HanRange { min: han, max: han }, |r| HanRange {
+                            min: r.min.min(han),
+                            max: r.max.max(han),
+                        }))
+                    })
+                    .map(|r| HanRange {
+                        max: if is_closed { r.max + 2 } else { r.max },
+                        ..r
+                    });
+
+                ret.push(HandValue {
+                    turn,
+                    junme,
+                    shanten,
+                    waits,
+                    han,
+                });
+            }
+            _ => (),
+        }
+    }
+
+    ret
+}
+
+/// Returns the han of tanyao, yakuhai, chiitoitsu, honitsu and chinitsu in
+/// `pais`, all the pais of a winning hand including fuuros.
+fn shape_han(pais: &[Pai], yakuhai: &[Pai], is_closed: bool) -> u8 {
+    let counts = counts34(pais);
+    let kuisagari = u8::from(!is_closed);
+    let mut han = 0;
+
+    let is_simple = |idx: usize| idx < 27 && !matches!(idx % 9, 0 | 8);
+    if (0..34).all(|idx| counts[idx] == 0 || is_simple(idx)) {
+        han += 1;
+    }
+
+    // a wind may be both the round wind and the seat wind
+    han += yakuhai
+        .iter()
+        .filter_map(|p| p.as_index34())
+        .filter(|&idx| counts[idx] >= 3)
+        .count() as u8;
+
+    // only a closed hand of 14 pais can be chiitoitsu, and a kan would make
+    // 4 of a kind, which is never 2 pairs
+    if is_closed
+        && pais.len() == 14
+        && counts.iter().all(|&c| c == 0 || c == 2)
+        && shanten::calc_normal(&counts, 0) != -1
+    {
+        han += 2;
+    }
+
+    let suits: Vec<_> = (0..3)
+        .filter(|s| counts[s * 9..s * 9 + 9].iter().any(|&c| c > 0))
+        .collect();
+    let has_jihai = counts[27..].iter().any(|&c| c > 0);
+    if suits.len() == 1 {
+        han += if has_jihai { 3 } else { 6 } - kuisagari;
+    }
+
+    han
+}
+
+/// A yaku the starting hand leans toward, see [`HandDirection::hint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Chinitsu,
+    Honitsu,
+    Junchan,
+    Chanta,
+}
+
+/// How much a haipai concentrates on one suit or on terminals and jihai, as
+/// a hint for the direction of the hand in the early turns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandDirection {
+    /// The suit with the most pais, 0 for manzu, 1 for pinzu and 2 for souzu,
+    /// preferring the former on a tie.
+    pub main_suit: usize,
+    /// Number of pais in `main_suit`, toward chinitsu and honitsu.
+    pub main_suit_count: u8,
+    pub jihai_count: u8,
+    /// Number of suited pais from 1 to 3 or 7 to 9, which can all form
+    /// mentsu with a terminal, toward junchan and chanta.
+    pub outside_count: u8,
+    /// Number of terminals and jihai.
+    pub yaochuu_count: u8,
+}
+
+impl HandDirection {
+    /// Counts the pais of `haipai`, which is usually 13 pais. Unknown pais
+    /// are ignored.
+    pub fn from_haipai(haipai: &[Pai]) -> Self {
+        let mut suit_counts = [0_u8; 3];
+        let mut jihai_count = 0;
+        let mut outside_count = 0;
+        let mut yaochuu_count = 0;
+
+        for idx in haipai.iter().filter_map(|p| p.as_index34()) {
+            if idx >= 27 {
+                jihai_count += 1;
+                yaochuu_count += 1;
+                continue;
+            }
+            suit_counts[idx / 9] += 1;
+            let num = idx % 9;
+            if !(3..6).contains(&num) {
+                outside_count += 1;
+            }
+            if matches!(num, 0 | 8) {
+                yaochuu_count += 1;
+            }
+        }
+
+        // max_by_key returns the last maximum, so search from the back
+        let main_suit = (0..3).rev().max_by_key(|&s| suit_counts[s]).unwrap_or(0);
+
+        Self {
+            main_suit,
+            main_suit_count: suit_counts[main_suit],
+            jihai_count,
+            outside_count,
+            yaochuu_count,
+        }
+    }
+
+    /// Returns the yaku the hand clearly leans toward, if any, preferring
+    /// the flushes to the terminal ones.
+    ///
+    /// The thresholds are for a haipai of 13 pais: 10 pais of one suit for
+    /// chinitsu, 10 of one suit and jihai for honitsu, and 11 outside pais
+    /// and jihai with at least 3 yaochuu for junchan, or chanta if there is
+    /// any jihai.
+    pub fn hint(&self) -> Option<Direction> {
+        if self.main_suit_count >= 10 {
+            Some(Direction::Chinitsu)
+        } else if self.main_suit_count + self.jihai_count >= 10 {
+            Some(Direction::Honitsu)
+        } else if self.outside_count + self.jihai_count >= 11 && self.yaochuu_count >= 3 {
+            if self.jihai_count == 0 {
+                Some(Direction::Junchan)
+            } else {
+                Some(Direction::Chanta)
+            }
+        } else {
+            None
+        }
+    }
+}
+
+/// A discard of the hero compared with the most efficient ones of the same
+/// hand, by shanten and then by [`ukeire`](shanten::ukeire).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EfficiencyCheck {
+    /// Index of the Dahai event in the kyoku events.
+    pub turn: usize,
+    pub discarded: Pai,
+    pub shanten: i8,
+    pub ukeire: u8,
+    pub best_shanten: i8,
+    pub best_ukeire: u8,
+    /// The kinds of pai, as normal pais, whose discard gives the best
+    /// shanten and ukeire.
+    pub best: Vec<Pai>,
+}
+
+impl EfficiencyCheck {
+    /// Returns true if another discard would have kept a lower shanten, or
+    /// the same shanten with more ukeire.
+    #[inline]
+    pub fn is_mistake(&self) -> bool {
+        (self.shanten, Reverse(self.ukeire)) > (self.best_shanten, Reverse(self.best_ukeire))
+    }
+}
+
+/// Checks the efficiency of each discard by `hero_seat` in `kyoku_events`,
+/// which should start with a StartKyoku, without akochan.
+///
+/// Only the pais are considered, so a discard for value, for safety or to
+/// avoid kuikae may be flagged as a mistake. Discards after the hero's
+/// riichi are forced and skipped. Ukeire counts the pais not visible to the
+/// hero, see [`Board::live_tiles`].
+pub fn efficiency(kyoku_events: &[Event], hero_seat: u8) -> Vec<EfficiencyCheck> {
+    let mut board = Board::default();
+    let mut fuuro_count = 0;
+    let mut is_reached = false;
+    let mut ret = vec![];
+
+    for (turn, event) in kyoku_events.iter().enumerate() {
+        match *event {
+            Event::StartKyoku { .. } => {
+                fuuro_count = 0;
+                is_reached = false;
+            }
+            Event::Chi { actor, .. }
+            | Event::Pon { actor, .. }
+            | Event::Daiminkan { actor, .. }
+            | Event::Ankan { actor, .. }
+                if actor == hero_seat =>
+            {
+                fuuro_count += 1;
+            }
+            Event::ReachAccepted { actor } if actor == hero_seat => is_reached = true,
+            Event::Dahai { actor, pai, .. } if actor == hero_seat && !is_reached => {
+                let tehai34 = counts34(&board.tehais[hero_seat as usize]);
+                let live = board.live_tiles(hero_seat);
+                let after_discard = |kind: usize| {
+                    let mut after = tehai34;
+                    after[kind] -= 1;
+                    (
+                        shanten::calc(&after, fuuro_count),
+                        shanten::ukeire(&after, fuuro_count, &live),
+                    )
+                };
+
+                if let Some(kind) = pai.as_index34().filter(|&k| tehai34[k] > 0) {
+                    let (shanten, ukeire) = after_discard(kind);
+                    let candidates: Vec<_> = (0..34)
+                        .filter(|&k| tehai34[k] > 0)
+                        .map(|k| (k, after_discard(k)))
+                        .collect();
+                    // there is at least the discarded kind
+                    let &(best_shanten, best_ukeire) = candidates
+                        .iter()
+                        .map(|(_, v)| v)
+                        .min_by_key(|&&(s, u)| (s, Reverse(u)))
+                        .unwrap();
+                    let best = candidates
+                        .iter()
+                        .filter(|(_, v)| *v == (best_shanten, best_ukeire))
+                        .filter_map(|&(k, _)| Pai::from_index34(k))
+                        .collect();
+
+                    ret.push(EfficiencyCheck {
+                        turn,
+                        discarded: pai,
+                        shanten,
+                        ukeire,
+                        best_shanten,
+                        best_ukeire,
+                        best,
+                    });
+                }
+            }
+            _ => (),
+        }
+        board.update(event);
+    }
+
+    ret
+}
+
+fn counts34(pais: &[Pai]) -> [u8; 34] {
+    let mut counts = [0; 34];
+    pais.iter()
+        .filter_map(|p| p.as_index34())
+        .for_each(|idx| counts[idx] += 1);
+    counts
+}
@@ -81,6 +81,6 @@ impl KyokuFilter {
             return false;
         };
 
-        self.whitelist[kyoku as usize].iter().any(|&h| h == honba)
+        self.whitelist[kyoku as usize].contains(&honba)
     }
 }
@@ -1,13 +1,27 @@
 //! Provides methods to transform mahjong logs from tenhou.net/6 format into
-//! mjai format.
+//! mjai format, and vice versa.
 
+pub mod board;
 mod conv;
+pub mod decision_id;
+pub mod export;
+pub mod hand_value;
 mod kyoku_filter;
+pub mod log_date;
 pub mod mjai;
 pub mod pai;
+pub mod safety;
+pub mod score;
+pub mod shanten;
 pub mod tenhou;
+pub mod yaku;
 
+pub use conv::mjai_to_tenhou;
 pub use conv::tenhou_to_mjai;
+pub use conv::tenhou_to_mjai_with_options;
 pub use conv::ConvertError;
+pub use conv::ConvertOptions;
+pub use decision_id::DecisionId;
 pub use kyoku_filter::KyokuFilter;
+pub use log_date::LogDate;
 pub use pai::Pai;
@@ -0,0 +1,48 @@
+//! Dates of games, as encoded at the start of the log IDs of tenhou and
+//! mahjong soul.
+
+use std::fmt;
+
+/// The date a game was played on, in the timezone of the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LogDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl LogDate {
+    /// Parses the date out of `log_id`, such as "2019062701gm-00a9-0000-0a1b2c3d"
+    /// for tenhou, which starts with YYYYMMDDHH, or "190627-0a1b2c3d-..." for
+    /// mahjong soul, which starts with YYMMDD.
+    ///
+    /// Returns None if `log_id` is of neither form, such as a log loaded from
+    /// a file.
+    pub fn from_log_id(log_id: &str) -> Option<Self> {
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+
+        let (year, rest) = match (log_id.get(..10), log_id.get(10..13), log_id.get(6..7)) {
+            (Some(head), Some("gm-"), _) if is_digits(head) => {
+                (log_id[..4].parse().ok()?, &log_id[4..8])
+            }
+            (_, _, Some("-")) if is_digits(&log_id[..6]) => {
+                (2000 + log_id[..2].parse::<u16>().ok()?, &log_id[2..6])
+            }
+            _ => return None,
+        };
+        let month = rest[..2].parse().ok()?;
+        let day = rest[2..].parse().ok()?;
+
+        if (1..=12).contains(&month) && (1..=31).contains(&day) {
+            Some(Self { year, month, day })
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for LogDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
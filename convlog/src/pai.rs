@@ -1,3 +1,4 @@
+use crate::tenhou::GameKind;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
@@ -5,13 +6,18 @@ use std::str::FromStr;
 
 use num_enum::TryFromPrimitive;
 use once_cell::sync::Lazy;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::Serializer;
 use serde_repr::Deserialize_repr as DeserializeRepr;
 use serde_repr::Serialize_repr as SerializeRepr;
+use serde_with::{DeserializeAs, SerializeAs};
 use thiserror::Error;
 
 /// Describes a pai in tenhou.net/6 format.
 ///
-/// It de/serializes as an `u8` in tenhou.net/6 format.
+/// It de/serializes as an `u8` in tenhou.net/6 format. Use
+/// `serde_with::DisplayFromStr` for mjai notation, or [`Mpsz`] for mpsz
+/// notation instead.
 #[derive(Debug, Clone, Copy, PartialEq, Hash, SerializeRepr, DeserializeRepr, TryFromPrimitive)]
 #[repr(u8)]
 pub enum Pai {
@@ -71,6 +77,15 @@ const MJAI_PAI_STRINGS: &[&str] = &[
     "?", "5mr", "5pr", "5sr", // 50~53
 ];
 
+const MPSZ_PAI_STRINGS: &[&str] = &[
+    "?", "?", "?", "?", "?", "?", "?", "?", "?", "?", // 0~9
+    "?", "1m", "2m", "3m", "4m", "5m", "6m", "7m", "8m", "9m", // 10~19
+    "?", "1p", "2p", "3p", "4p", "5p", "6p", "7p", "8p", "9p", // 20~29
+    "?", "1s", "2s", "3s", "4s", "5s", "6s", "7s", "8s", "9s", // 30~39
+    "?", "1z", "2z", "3z", "4z", "5z", "6z", "7z", "?", "?", // 40~49
+    "?", "0m", "0p", "0s", // 50~53
+];
+
 static MJAI_PAI_STRINGS_MAP: Lazy<HashMap<String, Pai>> = Lazy::new(|| {
     let mut m = HashMap::new();
 
@@ -84,6 +99,19 @@ static MJAI_PAI_STRINGS_MAP: Lazy<HashMap<String, Pai>> = Lazy::new(|| {
     m
 });
 
+static MPSZ_PAI_STRINGS_MAP: Lazy<HashMap<String, Pai>> = Lazy::new(|| {
+    let mut m = HashMap::new();
+
+    for (i, &v) in MPSZ_PAI_STRINGS.iter().enumerate() {
+        if let Ok(pai) = Pai::try_from(i as u8) {
+            m.insert(v.to_owned(), pai);
+        }
+    }
+    assert_eq!(m.len(), 1 + 9 * 3 + 7 + 3);
+
+    m
+});
+
 #[derive(Debug, Error)]
 pub enum ParseError {
     #[error("invalid pai string {0:?}")]
@@ -122,6 +150,23 @@ impl Default for Pai {
 }
 
 impl Pai {
+    /// Parses a pai in mpsz notation, such as "3m", "0p" and "7z".
+    #[inline]
+    pub fn from_mpsz(s: &str) -> Result<Self, ParseError> {
+        if let Some(&pai) = MPSZ_PAI_STRINGS_MAP.get(s) {
+            Ok(pai)
+        } else {
+            Err(ParseError::InvalidPaiString(s.to_owned()))
+        }
+    }
+
+    /// Returns the pai in mpsz notation, where aka pai are "0m", "0p" and
+    /// "0s", and jihai are "1z" to "7z".
+    #[inline]
+    pub fn as_mpsz(self) -> &'static str {
+        MPSZ_PAI_STRINGS[self.as_usize() % MPSZ_PAI_STRINGS.len()]
+    }
+
     #[inline]
     pub const fn as_u8(self) -> u8 {
         self as u8
@@ -131,6 +176,77 @@ impl Pai {
         self as usize
     }
 
+    /// Returns the pai with aka turned into its normal counterpart.
+    #[inline]
+    pub const fn deaka(self) -> Self {
+        match self {
+            Self::AkaMan5 => Self::Man5,
+            Self::AkaPin5 => Self::Pin5,
+            Self::AkaSou5 => Self::Sou5,
+            _ => self,
+        }
+    }
+
+    #[inline]
+    pub const fn is_aka(self) -> bool {
+        matches!(self, Self::AkaMan5 | Self::AkaPin5 | Self::AkaSou5)
+    }
+
+    /// Returns the dora indicated by the pai as a dora indicator, where aka
+    /// indicates 6. Returns `Pai::Unknown` for `Pai::Unknown`.
+    #[inline]
+    pub fn next_dora(self) -> Self {
+        let id = self.deaka().as_u8();
+        let next = match id {
+            19 | 29 | 39 => id - 8,
+            44 => 41,
+            47 => 45,
+            0 => 0,
+            _ => id + 1,
+        };
+        // every arm above yields a valid id
+        Self::try_from(next).unwrap()
+    }
+
+    /// Returns how far apart the numbers of two suited pais of the same suit
+    /// are, where aka are the same as normal 5s, such as 3 for 2m and 5m.
+    ///
+    /// Returns None across suits, or if either is a jihai or `Pai::Unknown`.
+    #[inline]
+    pub fn distance(self, other: Self) -> Option<u8> {
+        let (a, b) = (self.as_index34()?, other.as_index34()?);
+        if a < 27 && b < 27 && a / 9 == b / 9 {
+            Some((a % 9).abs_diff(b % 9) as u8)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the index of the pai in the 34 kinds, in the order of manzu,
+    /// pinzu, souzu and jihai, where aka are the same as normal 5s.
+    ///
+    /// Returns None for `Pai::Unknown`.
+    #[inline]
+    pub const fn as_index34(self) -> Option<usize> {
+        let id = self.deaka().as_usize();
+        match id {
+            11..=19 | 21..=29 | 31..=39 | 41..=47 => Some((id / 10 - 1) * 9 + id % 10 - 1),
+            _ => None,
+        }
+    }
+
+    /// The inverse of [`as_index34`](Self::as_index34), which never returns
+    /// an aka. Returns None if `index` is not less than 34.
+    #[inline]
+    pub fn from_index34(index: usize) -> Option<Self> {
+        if index < 34 {
+            // every index below 34 maps to a valid id
+            Some(Self::try_from((index / 9 * 10 + index % 9 + 11) as u8).unwrap())
+        } else {
+            None
+        }
+    }
+
     #[inline]
     pub fn as_ord(self) -> impl Ord {
         match self {
@@ -152,4 +268,164 @@ impl Pai {
             }
         }
     }
+
+    /// Returns all the pais in a wall of `kind`, with normal 5s replaced by
+    /// the aka in `aka`, sorted by [`as_ord`](Self::as_ord).
+    ///
+    /// There are 136 pais for yonma and 108 for sanma. Sanma has no 2m to 8m,
+    /// so `aka.man` is ignored there.
+    pub fn full_set(kind: GameKind, aka: &AkaConfig) -> Vec<Self> {
+        let mut pais = Vec::with_capacity(136);
+        for id in (11..=19).chain(21..=29).chain(31..=39).chain(41..=47) {
+            if kind == GameKind::Sanma && (12..=18).contains(&id) {
+                continue;
+            }
+
+            // every id above is valid
+            let pai = Self::try_from(id).unwrap();
+            let (aka_pai, aka_count) = match pai {
+                Self::Man5 => (Self::AkaMan5, aka.man),
+                Self::Pin5 => (Self::AkaPin5, aka.pin),
+                Self::Sou5 => (Self::AkaSou5, aka.sou),
+                _ => (pai, 0),
+            };
+            let aka_count = aka_count.min(4) as usize;
+            pais.resize(pais.len() + 4 - aka_count, pai);
+            pais.resize(pais.len() + aka_count, aka_pai);
+        }
+
+        pais
+    }
+}
+
+/// Parses pais in compact mpsz notation, such as "123m456p789s11z", where
+/// the digits before each suit letter are pais of that suit, "0" is aka and
+/// "1z" to "7z" are jihai.
+pub fn pais_from_mpsz(s: &str) -> Result<Vec<Pai>, ParseError> {
+    let mut pais = vec![];
+    let mut nums = vec![];
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            nums.push(c);
+        } else if nums.is_empty() {
+            return Err(ParseError::InvalidPaiString(s.to_owned()));
+        } else {
+            for n in nums.drain(..) {
+                pais.push(Pai::from_mpsz(&format!("{}{}", n, c))?);
+            }
+        }
+    }
+
+    if nums.is_empty() {
+        Ok(pais)
+    } else {
+        // digits without a suit letter
+        Err(ParseError::InvalidPaiString(s.to_owned()))
+    }
+}
+
+/// The inverse of [`pais_from_mpsz`], which keeps the order of `pais` and
+/// writes the suit letter once for each run of the same suit.
+///
+/// `Pai::Unknown` is written as "?", which does not parse back.
+pub fn pais_to_mpsz(pais: &[Pai]) -> String {
+    let mut ret = String::with_capacity(pais.len() * 2);
+    let mut pending_suit = None;
+    for pai in pais {
+        let mpsz = pai.as_mpsz();
+        let (num, suit) = mpsz.split_at(mpsz.len() - 1);
+        if num.is_empty() {
+            ret.extend(pending_suit.take());
+            ret.push_str(suit);
+            continue;
+        }
+
+        if pending_suit.is_some_and(|s| s != suit) {
+            ret.extend(pending_suit);
+        }
+        ret.push_str(num);
+        pending_suit = Some(suit);
+    }
+    ret.extend(pending_suit);
+    ret
+}
+
+/// A hand in compact mpsz notation, such as "123m456p789s11z 5m" with the
+/// drawn pai after a space, or "123m456p789s11z" with none.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Hand {
+    pub tehai: Vec<Pai>,
+    pub tsumo: Option<Pai>,
+}
+
+impl FromStr for Hand {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let tehai = pais_from_mpsz(parts.next().unwrap_or_default())?;
+        let tsumo = match parts.next() {
+            Some(part) => Some(Pai::from_mpsz(part)?),
+            None => None,
+        };
+        if parts.next().is_some() {
+            return Err(ParseError::InvalidPaiString(s.to_owned()));
+        }
+
+        Ok(Self { tehai, tsumo })
+    }
+}
+
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", pais_to_mpsz(&self.tehai))?;
+        if let Some(tsumo) = self.tsumo {
+            write!(f, " {}", tsumo.as_mpsz())?;
+        }
+        Ok(())
+    }
+}
+
+/// The number of aka of each suit in a wall, each replacing a normal 5.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AkaConfig {
+    pub man: u8,
+    pub pin: u8,
+    pub sou: u8,
+}
+
+impl AkaConfig {
+    /// One aka of each suit, as in most rulesets with aka.
+    pub const STANDARD: Self = Self {
+        man: 1,
+        pin: 1,
+        sou: 1,
+    };
+}
+
+/// De/serializes a [`Pai`] as a string in mpsz notation, to be used with
+/// `#[serde_as(as = "Mpsz")]`.
+///
+/// This is meant for human readable output, tenhou.net/6 logs must keep using
+/// the default `u8` encoding.
+pub struct Mpsz;
+
+impl SerializeAs<Pai> for Mpsz {
+    #[inline]
+    fn serialize_as<S>(source: &Pai, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(source.as_mpsz())
+    }
+}
+
+impl<'de> DeserializeAs<'de, Pai> for Mpsz {
+    fn deserialize_as<D>(deserializer: D) -> Result<Pai, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Pai::from_mpsz(&s).map_err(de::Error::custom)
+    }
 }
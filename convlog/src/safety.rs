@@ -0,0 +1,331 @@
+//! Judgement of how safe a discard is against a player from the rivers, of
+//! how to fold the rest of a kyoku, and of how dangerous a deal-in actually
+//! was in hindsight.
+
+use crate::board::{Board, LiveTiles};
+use crate::mjai::Event;
+use crate::shanten;
+use crate::Pai;
+
+/// How safe a pai is against a player, judged from the rivers only. The
+/// variants are in order from the safest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SafetyClass {
+    /// Can not deal in by ron, since the player would be furiten.
+    Genbutsu,
+    /// A number pai whose suji are all genbutsu, e.g. 4p against a river of
+    /// 1p and 7p.
+    Suji,
+    /// A jihai that is not genbutsu.
+    Jihai,
+    /// A number pai that is neither genbutsu nor suji.
+    Musuji,
+}
+
+/// Which suji make a number pai [`SafetyClass::Suji`] or half of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SujiKind {
+    /// A 1, 2, 3, 7, 8 or 9 whose only suji is genbutsu, e.g. 1p against 4p.
+    Suji,
+    /// A 4, 5 or 6 whose two suji are both genbutsu, e.g. 4p against 1p and
+    /// 7p.
+    NakaSuji,
+    /// A 4, 5 or 6 with only one of its suji genbutsu, still open to a
+    /// ryanmen from the other side, e.g. 4p against 1p alone.
+    Katasuji,
+}
+
+/// The waits other than ryanmen that can still catch a suji pai, judged from
+/// the pais visible to the discarder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SujiRisk {
+    pub kind: SujiKind,
+    /// A kanchan on the pai is possible, as neither neighbor is fully
+    /// visible.
+    pub kanchan: bool,
+    /// A penchan on the 3 or 7 is possible, as neither of the 2 pais below or
+    /// above it is fully visible.
+    pub penchan: bool,
+    /// A shanpon on the pai is possible, as at most 2 of it are visible.
+    pub shanpon: bool,
+}
+
+impl SujiRisk {
+    /// Returns true if the pai is suji but still easy to deal in with, by a
+    /// ryanmen for katasuji, or by kanchan, penchan or shanpon. Tanki is
+    /// always possible and not counted.
+    pub fn is_trap(&self) -> bool {
+        self.kind == SujiKind::Katasuji || self.kanchan || self.penchan || self.shanpon
+    }
+}
+
+/// A way to fold the rest of a kyoku against the players in riichi, see
+/// [`fold_path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldPath {
+    /// Every pai of the hand from the safest to discard first, each with its
+    /// class against the most dangerous of the threats. Equally safe pais
+    /// keep the order of the hand.
+    pub order: Vec<(Pai, SafetyClass)>,
+    /// How many discards in a row can be genbutsu against every threat.
+    ///
+    /// It only counts the pais in the hand now, while the discards of the
+    /// others after a riichi become genbutsu as well, so the actual number
+    /// may be larger.
+    pub safe_turns: usize,
+    /// How many more discards the hand has to make before the wall runs out.
+    pub turns_left: usize,
+}
+
+impl FoldPath {
+    /// Returns true if the hand can fold with genbutsu alone until the
+    /// exhaustive draw.
+    #[inline]
+    pub fn survives(&self) -> bool {
+        self.safe_turns >= self.turns_left
+    }
+}
+
+/// Orders the pais of `tehai` by how safe they are against the threats with
+/// `genbutsus`, one list of [`genbutsu`] for each of them, where
+/// `turns_left` discards are still to be made.
+pub fn fold_path(tehai: &[Pai], genbutsus: &[Vec<Pai>], turns_left: usize) -> FoldPath {
+    let mut order: Vec<_> = tehai
+        .iter()
+        .map(|&pai| {
+            let class = genbutsus
+                .iter()
+                .map(|genbutsu| classify(pai, genbutsu))
+                .max()
+                .unwrap_or(SafetyClass::Genbutsu);
+            (pai, class)
+        })
+        .collect();
+    // stable, so that equally safe pais keep their order
+    order.sort_by_key(|&(_, class)| class);
+
+    let safe_turns = order
+        .iter()
+        .take_while(|&&(_, class)| class == SafetyClass::Genbutsu)
+        .count();
+    FoldPath {
+        order,
+        safe_turns,
+        turns_left,
+    }
+}
+
+/// Returns the [`FoldPath`] of `seat` after `events`, which are the events of
+/// a kyoku so far, against every other player in riichi. Returns None if no
+/// one else is in riichi.
+///
+/// The discards left are counted as if no one calls from now on.
+pub fn fold_path_at(events: &[Event], seat: u8) -> Option<FoldPath> {
+    // mjai logs are always yonma
+    const LIVE_WALL: usize = 136 - 14 - 13 * 4;
+
+    let start = events
+        .iter()
+        .rposition(|ev| matches!(ev, Event::StartKyoku { .. }))
+        .unwrap_or(0);
+    let events = &events[start..];
+
+    let mut threats = vec![];
+    let mut draws = 0;
+    let mut next_drawer = 0;
+    for event in events {
+        match *event {
+            Event::ReachAccepted { actor } if actor != seat && !threats.contains(&actor) => {
+                threats.push(actor)
+            }
+            // a rinshan also takes one from the live wall, which refills
+            // the dead wall
+            Event::Tsumo { actor, .. } => {
+                draws += 1;
+                next_drawer = (actor + 1) % 4;
+            }
+            Event::Dahai { actor, .. } | Event::Chi { actor, .. } | Event::Pon { actor, .. } => {
+                next_drawer = (actor + 1) % 4;
+            }
+            _ => (),
+        }
+    }
+    if threats.is_empty() {
+        return None;
+    }
+
+    let board = Board::replay(events);
+    let tehai = &board.tehais[seat as usize];
+    let genbutsus: Vec<_> = threats.iter().map(|&t| genbutsu(events, t)).collect();
+
+    // the pending discard of a hand that has just drawn or called
+    let pending = (tehai.len() % 3 == 2) as usize;
+    let draws_left = (0..LIVE_WALL.saturating_sub(draws))
+        .filter(|i| (next_drawer as usize + i) % 4 == seat as usize)
+        .count();
+    Some(fold_path(tehai, &genbutsus, pending + draws_left))
+}
+
+/// The danger of a deal-in judged in hindsight, with the winner's hand known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DealInDanger {
+    pub pai: Pai,
+    pub winner: u8,
+    /// The class of `pai` against the winner at the time of the discard.
+    pub class: SafetyClass,
+    /// The kinds of pai the winner was waiting on, in the order of
+    /// [`Pai::as_index34`].
+    pub waits: Vec<Pai>,
+    /// The number of pais of `waits` that were not visible to the houjuu
+    /// player at the time of the discard.
+    pub live_waits: usize,
+    /// What could still catch `pai` if it was suji or katasuji, as seen by
+    /// the houjuu player at the time of the discard.
+    pub suji_risk: Option<SujiRisk>,
+}
+
+/// Classifies `pai` against a player with `genbutsu`, which can be obtained
+/// by [`genbutsu`].
+pub fn classify(pai: Pai, genbutsu: &[Pai]) -> SafetyClass {
+    let is_genbutsu = |kind: usize| genbutsu.iter().any(|p| p.as_index34() == Some(kind));
+
+    let kind = match pai.as_index34() {
+        Some(kind) => kind,
+        None => return SafetyClass::Musuji,
+    };
+    if is_genbutsu(kind) {
+        return SafetyClass::Genbutsu;
+    }
+    if kind >= 27 {
+        return SafetyClass::Jihai;
+    }
+
+    let is_suji = match kind % 9 {
+        0..=2 => is_genbutsu(kind + 3),
+        6..=8 => is_genbutsu(kind - 3),
+        _ => is_genbutsu(kind - 3) && is_genbutsu(kind + 3),
+    };
+    if is_suji {
+        SafetyClass::Suji
+    } else {
+        SafetyClass::Musuji
+    }
+}
+
+/// Returns the [`SujiKind`] of `pai` against a player with `genbutsu`, or
+/// None if it is genbutsu, a jihai, or has no genbutsu suji at all.
+pub fn suji_kind(pai: Pai, genbutsu: &[Pai]) -> Option<SujiKind> {
+    let is_genbutsu = |kind: usize| genbutsu.iter().any(|p| p.as_index34() == Some(kind));
+
+    let kind = pai.as_index34().filter(|&k| k < 27 && !is_genbutsu(k))?;
+    match kind % 9 {
+        0..=2 if is_genbutsu(kind + 3) => Some(SujiKind::Suji),
+        6..=8 if is_genbutsu(kind - 3) => Some(SujiKind::Suji),
+        3..=5 => match (is_genbutsu(kind - 3), is_genbutsu(kind + 3)) {
+            (true, true) => Some(SujiKind::NakaSuji),
+            (true, false) | (false, true) => Some(SujiKind::Katasuji),
+            (false, false) => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the [`SujiRisk`] of `pai` against a player with `genbutsu`, with
+/// `live` being the [`LiveTiles`] of the discarder, which can be obtained by
+/// [`Board::live_tiles`]. Returns None if `pai` is not suji, see
+/// [`suji_kind`].
+pub fn suji_risk(pai: Pai, genbutsu: &[Pai], live: &LiveTiles) -> Option<SujiRisk> {
+    let kind = suji_kind(pai, genbutsu)?;
+    // suji_kind only returns Some for a number pai
+    let idx = pai.as_index34()?;
+    let num = idx % 9;
+    let is_live = |idx: usize| live.count34(idx) > 0;
+
+    Some(SujiRisk {
+        kind,
+        kanchan: (1..=7).contains(&num) && is_live(idx - 1) && is_live(idx + 1),
+        penchan: match num {
+            2 => is_live(idx - 2) && is_live(idx - 1),
+            6 => is_live(idx + 1) && is_live(idx + 2),
+            _ => false,
+        },
+        shanpon: live.count34(idx) >= 2,
+    })
+}
+
+/// Returns the pais that `seat` can not ron after `events`, which are the
+/// events of a kyoku so far.
+///
+/// They are the discards of `seat`, including the called ones, and every
+/// discard after the riichi of `seat`.
+pub fn genbutsu(events: &[Event], seat: u8) -> Vec<Pai> {
+    let mut ret = vec![];
+    let mut is_reached = false;
+
+    for event in events {
+        match *event {
+            Event::StartKyoku { .. } => {
+                ret.clear();
+                is_reached = false;
+            }
+            Event::ReachAccepted { actor } if actor == seat => is_reached = true,
+            Event::Dahai { actor, pai, .. } if actor == seat || is_reached => ret.push(pai),
+            _ => (),
+        }
+    }
+
+    ret
+}
+
+/// Returns the danger of the deal-in of the ron at `events[hora_index]`,
+/// where `events` are the events of a kyoku. Returns None if it is not a
+/// ron.
+///
+/// The deal-in is the last discard of the houjuu player before the hora, or
+/// the kakan robbed by chankan.
+pub fn deal_in_danger(events: &[Event], hora_index: usize) -> Option<DealInDanger> {
+    let (winner, target) = match events.get(hora_index) {
+        Some(&Event::Hora { actor, target, .. }) if actor != target => (actor, target),
+        _ => return None,
+    };
+
+    let (discard_index, pai) =
+        events[..hora_index]
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, ev)| match *ev {
+                Event::Dahai { actor, pai, .. } | Event::Kakan { actor, pai, .. }
+                    if actor == target =>
+                {
+                    Some((i, pai))
+                }
+                _ => None,
+            })?;
+    let before = &events[..discard_index];
+    let board = Board::replay(before);
+
+    let winner_tehai = &board.tehais[winner as usize];
+    let mut tehai = [0; 34];
+    for kind in winner_tehai.iter().filter_map(|p| p.as_index34()) {
+        tehai[kind] += 1;
+    }
+    let fuuro_count = ((14 - winner_tehai.len()) / 3) as u8;
+    let waits: Vec<_> = shanten::waits(&tehai, fuuro_count)
+        .into_iter()
+        .filter_map(Pai::from_index34)
+        .collect();
+
+    let live = board.live_tiles(target);
+    let live_waits = live.sum(&waits);
+
+    let genbutsu = genbutsu(before, winner);
+    Some(DealInDanger {
+        pai,
+        winner,
+        class: classify(pai, &genbutsu),
+        waits,
+        live_waits,
+        suji_risk: suji_risk(pai, &genbutsu, &live),
+    })
+}
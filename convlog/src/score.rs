@@ -0,0 +1,315 @@
+//! Standard scoring of a hora from its han and fu.
+
+use crate::tenhou::kyoku::{EndStatus, Kyoku, RyukyokuReason};
+use crate::tenhou::GameKind;
+
+/// The payments of a hora, before honba and kyotaku.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScorePayment {
+    /// Paid by the houjuu player alone.
+    Ron(i32),
+    /// Paid by each of the other players to a dealer.
+    DealerTsumo(i32),
+    /// Paid to a non-dealer by the dealer and by each of the other
+    /// non-dealers respectively.
+    Tsumo { dealer: i32, non_dealer: i32 },
+}
+
+/// Scoring options that differ between rulesets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoreRule {
+    /// Rounds 30fu 4han and 60fu 3han up to mangan.
+    pub kiriage_mangan: bool,
+    /// Points paid to the winner for each honba, split evenly among the
+    /// payers on tsumo. 300 by default.
+    pub honba_bonus: i32,
+    /// Whether the dealer keeps the seat when tenpai at an exhaustive draw.
+    /// True by default.
+    pub renchan_on_tenpai: bool,
+    /// Sanma has one payer less on tsumo, whose share is simply lost (tsumo
+    /// loss). Yonma by default.
+    pub game_kind: GameKind,
+    /// Points paid in total by the noten players to the tenpai players at an
+    /// exhaustive draw. 3000 by default.
+    pub noten_penalty: i32,
+}
+
+/// What `hero` would pay or receive if the kyoku drew at a turn, see
+/// [`ScoreRule::noten_exposure`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotenExposure {
+    pub hero_tenpai: bool,
+    pub others_tenpai: u8,
+    /// The delta of the hero if tenpai.
+    pub if_tenpai: i32,
+    /// The delta of the hero if noten.
+    pub if_noten: i32,
+}
+
+impl NotenExposure {
+    /// The delta of the hero as the hand is.
+    #[inline]
+    pub fn delta(&self) -> i32 {
+        if self.hero_tenpai {
+            self.if_tenpai
+        } else {
+            self.if_noten
+        }
+    }
+}
+
+impl Default for ScoreRule {
+    fn default() -> Self {
+        Self {
+            kiriage_mangan: false,
+            honba_bonus: 300,
+            renchan_on_tenpai: true,
+            game_kind: GameKind::Yonma,
+            noten_penalty: 3000,
+        }
+    }
+}
+
+impl ScorePayment {
+    /// Returns the total points the winner receives in yonma.
+    #[inline]
+    pub fn total(self) -> i32 {
+        self.total_in(GameKind::Yonma)
+    }
+
+    /// Returns the total points the winner receives in a game of `kind`.
+    pub fn total_in(self, kind: GameKind) -> i32 {
+        let payers = kind.num_players() as i32 - 1;
+        match self {
+            Self::Ron(points) => points,
+            Self::DealerTsumo(points) => points * payers,
+            Self::Tsumo { dealer, non_dealer } => dealer + non_dealer * (payers - 1),
+        }
+    }
+
+    /// Returns the score deltas of each seat in yonma in the same form as
+    /// [`HoraDetail::score_deltas`](crate::tenhou::kyoku::HoraDetail::score_deltas),
+    /// except that honba and kyotaku are not included.
+    #[inline]
+    pub fn deltas(self, who: u8, target: u8, oya: u8) -> [i32; 4] {
+        self.deltas_in(GameKind::Yonma, who, target, oya)
+    }
+
+    /// Returns the score deltas like [`deltas`](Self::deltas) in a game of
+    /// `kind`. The empty seat 3 of sanma pays nothing.
+    pub fn deltas_in(self, kind: GameKind, who: u8, target: u8, oya: u8) -> [i32; 4] {
+        let mut deltas = [0; 4];
+        let players = &mut deltas[..kind.num_players()];
+        match self {
+            Self::Ron(points) => {
+                players[target as usize] -= points;
+            }
+            Self::DealerTsumo(points) => {
+                for (seat, delta) in players.iter_mut().enumerate() {
+                    if seat != who as usize {
+                        *delta -= points;
+                    }
+                }
+            }
+            Self::Tsumo { dealer, non_dealer } => {
+                for (seat, delta) in players.iter_mut().enumerate() {
+                    if seat == oya as usize {
+                        *delta -= dealer;
+                    } else if seat != who as usize {
+                        *delta -= non_dealer;
+                    }
+                }
+            }
+        }
+        deltas[who as usize] = self.total_in(kind);
+        deltas
+    }
+}
+
+impl ScoreRule {
+    /// Returns the payments of a hora with `han` and `fu` under this rule.
+    ///
+    /// `fu` must already be rounded up, with the exception of 25fu for
+    /// chiitoitsu. Hands of 13 han or more are counted as yakuman.
+    pub fn score(self, han: u8, fu: u8, is_dealer: bool, is_tsumo: bool) -> ScorePayment {
+        let base = self.base_points(han, fu);
+
+        match (is_dealer, is_tsumo) {
+            (true, false) => ScorePayment::Ron(round_up(base * 6)),
+            (false, false) => ScorePayment::Ron(round_up(base * 4)),
+            (true, true) => ScorePayment::DealerTsumo(round_up(base * 2)),
+            (false, true) => ScorePayment::Tsumo {
+                dealer: round_up(base * 2),
+                non_dealer: round_up(base),
+            },
+        }
+    }
+
+    /// Returns the score deltas of each seat for the payments of a hora,
+    /// including the honba bonus but not kyotaku.
+    pub fn deltas(
+        self,
+        payment: ScorePayment,
+        who: u8,
+        target: u8,
+        oya: u8,
+        honba: u8,
+    ) -> [i32; 4] {
+        let mut deltas = payment.deltas_in(self.game_kind, who, target, oya);
+        let bonus = self.honba_bonus * honba as i32;
+
+        if let ScorePayment::Ron(_) = payment {
+            deltas[target as usize] -= bonus;
+        } else {
+            let players = self.game_kind.num_players();
+            for (seat, delta) in deltas[..players].iter_mut().enumerate() {
+                if seat != who as usize {
+                    *delta -= bonus / (players as i32 - 1);
+                }
+            }
+        }
+        deltas[who as usize] += bonus;
+
+        deltas
+    }
+
+    /// Returns the score deltas of each seat for the yakuman payments of a
+    /// hora that `pao` is liable for, including the honba bonus but not
+    /// kyotaku.
+    ///
+    /// On tsumo the liable player pays the full amount and the honba alone.
+    /// On ron the liable player and the houjuu player split the amount, and
+    /// the houjuu player pays the honba.
+    pub fn deltas_with_pao(
+        self,
+        payment: ScorePayment,
+        who: u8,
+        target: u8,
+        honba: u8,
+        pao: u8,
+    ) -> [i32; 4] {
+        let total = payment.total_in(self.game_kind);
+        let bonus = self.honba_bonus * honba as i32;
+
+        let mut deltas = [0; 4];
+        match payment {
+            ScorePayment::Ron(_) if target != pao => {
+                deltas[target as usize] -= total / 2 + bonus;
+                deltas[pao as usize] -= total / 2;
+            }
+            _ => deltas[pao as usize] -= total + bonus,
+        }
+        deltas[who as usize] = total + bonus;
+
+        deltas
+    }
+
+    /// Returns the score deltas of each seat at an exhaustive draw, where the
+    /// noten players split the penalty to pay and the tenpai players split
+    /// it to receive. Nobody pays if everyone or no one is tenpai.
+    pub fn noten_deltas(self, tenpai_seats: [bool; 4]) -> [i32; 4] {
+        let players = self.game_kind.num_players();
+        let tenpai = tenpai_seats[..players].iter().filter(|&&t| t).count() as i32;
+
+        let mut deltas = [0; 4];
+        if tenpai == 0 || tenpai == players as i32 {
+            return deltas;
+        }
+        for (delta, &is_tenpai) in deltas[..players].iter_mut().zip(&tenpai_seats) {
+            *delta = if is_tenpai {
+                self.noten_penalty / tenpai
+            } else {
+                -self.noten_penalty / (players as i32 - tenpai)
+            };
+        }
+        deltas
+    }
+
+    /// Returns what `hero` would pay or receive if `kyoku` drew right after
+    /// the `turn`-th discard of each seat, counting from 0, both for the hand
+    /// of the hero as it is and for the other way around.
+    ///
+    /// The tenpai of the others is taken from the log as a matter of fact,
+    /// not as estimated by the hero at the table.
+    pub fn noten_exposure(self, kyoku: &Kyoku, hero: u8, turn: usize) -> NotenExposure {
+        let mut tenpai_seats = kyoku.tenpai_seats_at(turn);
+        let hero_tenpai = tenpai_seats[hero as usize];
+        let others_tenpai = tenpai_seats[..self.game_kind.num_players()]
+            .iter()
+            .enumerate()
+            .filter(|&(seat, &t)| t && seat != hero as usize)
+            .count() as u8;
+
+        tenpai_seats[hero as usize] = true;
+        let if_tenpai = self.noten_deltas(tenpai_seats)[hero as usize];
+        tenpai_seats[hero as usize] = false;
+        let if_noten = self.noten_deltas(tenpai_seats)[hero as usize];
+
+        NotenExposure {
+            hero_tenpai,
+            others_tenpai,
+            if_tenpai,
+            if_noten,
+        }
+    }
+
+    /// Returns true if the dealer of `kyoku` keeps the seat for the next
+    /// kyoku, which happens when the dealer wins, is tenpai at an exhaustive
+    /// draw if the rule allows, or the kyoku is aborted.
+    ///
+    /// Tenpai is judged from the hand after the last discard, a kakan or kita
+    /// at the very end is not taken into account.
+    pub fn is_renchan(self, kyoku: &Kyoku) -> bool {
+        let oya = kyoku.oya();
+        match &kyoku.end_status {
+            EndStatus::Hora { .. } => kyoku.winners().contains(&oya),
+            EndStatus::Ryukyoku { reason, .. } => match reason {
+                RyukyokuReason::Exhaustive | RyukyokuReason::NagashiMangan => {
+                    let is_tenpai = kyoku.action_tables[oya as usize]
+                        .shanten_by_turn()
+                        .into_iter()
+                        .rev()
+                        .flatten()
+                        .next()
+                        .map(|s| s <= 0)
+                        .unwrap_or(false);
+                    self.renchan_on_tenpai && is_tenpai
+                }
+                _ => true,
+            },
+        }
+    }
+
+    fn base_points(self, han: u8, fu: u8) -> i32 {
+        match han {
+            13..=u8::MAX => 8000,
+            11..=12 => 6000,
+            8..=10 => 4000,
+            6..=7 => 3000,
+            5 => 2000,
+            _ => {
+                let base = (fu as i32) << (han + 2);
+                if base >= 2000 || self.kiriage_mangan && base == 1920 {
+                    2000
+                } else {
+                    base
+                }
+            }
+        }
+    }
+}
+
+/// Returns the payments of a hora with `han` and `fu` under the default
+/// rule, which has no kiriage mangan.
+///
+/// See [`ScoreRule::score`] for details.
+#[inline]
+pub fn score(han: u8, fu: u8, is_dealer: bool, is_tsumo: bool) -> ScorePayment {
+    ScoreRule::default().score(han, fu, is_dealer, is_tsumo)
+}
+
+/// Rounds up to a multiple of 100.
+#[inline]
+fn round_up(points: i32) -> i32 {
+    (points + 99) / 100 * 100
+}
@@ -0,0 +1,184 @@
+//! Shanten calculation over the 34 kinds of pai, indexed by
+//! [`Pai::as_index34`](crate::Pai::as_index34).
+
+use crate::board::LiveTiles;
+
+/// Returns the shanten number of a hand, where 0 means tenpai and -1 means
+/// agari.
+///
+/// `tehai` counts the pais of each kind in the closed part of the hand, and
+/// `fuuro_count` is the number of fuuros, including ankans. Chiitoitsu and
+/// kokushi musou are only considered for a hand without fuuro.
+pub fn calc(tehai: &[u8; 34], fuuro_count: u8) -> i8 {
+    let normal = calc_normal(tehai, fuuro_count);
+    if fuuro_count > 0 {
+        normal
+    } else {
+        normal.min(calc_chiitoi(tehai)).min(calc_kokushi(tehai))
+    }
+}
+
+/// Returns the kinds of pai that complete a tenpai hand into agari, or an
+/// empty list if the hand is not tenpai.
+///
+/// The arguments are the same as [`calc`]. Kinds of which the hand already
+/// holds all 4 pais are not waits.
+pub fn waits(tehai: &[u8; 34], fuuro_count: u8) -> Vec<usize> {
+    let mut tehai = *tehai;
+    (0..34)
+        .filter(|&kind| {
+            if tehai[kind] >= 4 {
+                return false;
+            }
+            tehai[kind] += 1;
+            let is_agari = calc(&tehai, fuuro_count) == -1;
+            tehai[kind] -= 1;
+            is_agari
+        })
+        .collect()
+}
+
+/// Returns the number of unseen pais that lower the shanten of `tehai`, a
+/// hand waiting for a tsumo. `live` should come from the view of the owner
+/// of `tehai`, so that the pais in `tehai` are not live.
+///
+/// The other arguments are the same as [`calc`].
+pub fn ukeire(tehai: &[u8; 34], fuuro_count: u8, live: &LiveTiles) -> u8 {
+    let shanten = calc(tehai, fuuro_count);
+    let mut tehai = *tehai;
+    (0..34)
+        .filter(|&kind| live.count34(kind) > 0)
+        .map(|kind| {
+            tehai[kind] += 1;
+            let is_improving = calc(&tehai, fuuro_count) < shanten;
+            tehai[kind] -= 1;
+            if is_improving {
+                live.count34(kind)
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+/// Returns true if discarding one pai of `kind` from `tehai`, a closed hand
+/// right after tsumo, is only a routine cleanup of a bad haipai.
+///
+/// This is deliberately conservative. `kind` must be a single yaochuu pai,
+/// without any pai of the same suit within 2 if it is a terminal. The hand
+/// after the discard must also stay at `starting_shanten`, the shanten of the
+/// haipai, for the form of 4 mentsu and 1 jantou. Hands closer to chiitoitsu
+/// or kokushi musou, where the same pai may matter, are never trivial.
+pub fn is_trivial_discard(tehai: &[u8; 34], kind: usize, starting_shanten: i8) -> bool {
+    let is_jihai = kind >= 27;
+    if kind >= 34 || tehai[kind] != 1 || !(is_jihai || matches!(kind % 9, 0 | 8)) {
+        return false;
+    }
+    if !is_jihai {
+        let suit = kind / 9 * 9..kind / 9 * 9 + 9;
+        let near = kind.saturating_sub(2).max(suit.start)..(kind + 3).min(suit.end);
+        if near.filter(|&k| k != kind).any(|k| tehai[k] > 0) {
+            return false;
+        }
+    }
+
+    let mut after = *tehai;
+    after[kind] -= 1;
+    let normal = calc_normal(&after, 0);
+    normal == starting_shanten && normal < calc_chiitoi(&after) && normal < calc_kokushi(&after)
+}
+
+/// Returns the shanten number for the form of 4 mentsu and 1 jantou.
+pub fn calc_normal(tehai: &[u8; 34], fuuro_count: u8) -> i8 {
+    let mut tehai = *tehai;
+    let mut best = 8;
+    search(&mut tehai, 0, fuuro_count as i8, 0, false, &mut best);
+    best
+}
+
+/// Returns the shanten number for chiitoitsu.
+pub fn calc_chiitoi(tehai: &[u8; 34]) -> i8 {
+    let pairs = tehai.iter().filter(|&&c| c >= 2).count() as i8;
+    let kinds = tehai.iter().filter(|&&c| c >= 1).count() as i8;
+    6 - pairs + (7 - kinds).max(0)
+}
+
+/// Returns the shanten number for kokushi musou.
+pub fn calc_kokushi(tehai: &[u8; 34]) -> i8 {
+    const YAOCHU: [usize; 13] = [0, 8, 9, 17, 18, 26, 27, 28, 29, 30, 31, 32, 33];
+
+    let kinds = YAOCHU.iter().filter(|&&i| tehai[i] >= 1).count() as i8;
+    let has_pair = YAOCHU.iter().any(|&i| tehai[i] >= 2);
+    13 - kinds - has_pair as i8
+}
+
+fn search(
+    tehai: &mut [u8; 34],
+    mut idx: usize,
+    mentsu: i8,
+    taatsu: i8,
+    has_jantou: bool,
+    best: &mut i8,
+) {
+    while idx < 34 && tehai[idx] == 0 {
+        idx += 1;
+    }
+
+    if idx == 34 {
+        let taatsu = taatsu.min(4 - mentsu);
+        let shanten = 8 - 2 * mentsu - taatsu - has_jantou as i8;
+        *best = (*best).min(shanten);
+        return;
+    }
+
+    let is_suuhai = idx < 27;
+    let pos = idx % 9;
+
+    // mentsu
+    if tehai[idx] >= 3 {
+        tehai[idx] -= 3;
+        search(tehai, idx, mentsu + 1, taatsu, has_jantou, best);
+        tehai[idx] += 3;
+    }
+    if is_suuhai && pos <= 6 && tehai[idx + 1] > 0 && tehai[idx + 2] > 0 {
+        tehai[idx] -= 1;
+        tehai[idx + 1] -= 1;
+        tehai[idx + 2] -= 1;
+        search(tehai, idx, mentsu + 1, taatsu, has_jantou, best);
+        tehai[idx] += 1;
+        tehai[idx + 1] += 1;
+        tehai[idx + 2] += 1;
+    }
+
+    // jantou and taatsu, which are useless once there are already 4 blocks
+    if mentsu + taatsu < 4 || !has_jantou {
+        if tehai[idx] >= 2 {
+            tehai[idx] -= 2;
+            if !has_jantou {
+                search(tehai, idx, mentsu, taatsu, true, best);
+            }
+            search(tehai, idx, mentsu, taatsu + 1, has_jantou, best);
+            tehai[idx] += 2;
+        }
+        if is_suuhai && pos <= 7 && tehai[idx + 1] > 0 {
+            tehai[idx] -= 1;
+            tehai[idx + 1] -= 1;
+            search(tehai, idx, mentsu, taatsu + 1, has_jantou, best);
+            tehai[idx] += 1;
+            tehai[idx + 1] += 1;
+        }
+        if is_suuhai && pos <= 6 && tehai[idx + 2] > 0 {
+            tehai[idx] -= 1;
+            tehai[idx + 2] -= 1;
+            search(tehai, idx, mentsu, taatsu + 1, has_jantou, best);
+            tehai[idx] += 1;
+            tehai[idx + 2] += 1;
+        }
+    }
+
+    // isolated
+    let count = tehai[idx];
+    tehai[idx] = 0;
+    search(tehai, idx + 1, mentsu, taatsu, has_jantou, best);
+    tehai[idx] = count;
+}
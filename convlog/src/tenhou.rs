@@ -1,27 +1,102 @@
-use crate::{KyokuFilter, Pai};
+use crate::board::{Board, BoardSnapshot, Visibility};
+use crate::conv::{self, ConvertOptions};
+use crate::mjai::Event;
+use crate::yaku::dora_han;
+use crate::{shanten, ConvertError, KyokuFilter, Pai};
+use std::convert::TryFrom;
 
 use std::fmt;
+use std::io::{self, BufRead};
 
 use serde::{Deserialize, Serialize};
 use serde_json as json;
 use serde_json::{Result, Value};
 use serde_tuple::{Deserialize_tuple as DeserializeTuple, Serialize_tuple as SerializeTuple};
+use thiserror::Error;
 
 /// The overview structure of log in tenhou.net/6 format.
 #[derive(Debug, Clone)]
 pub struct Log {
     pub names: [String; 4],
     pub game_length: GameLength,
+    pub game_kind: GameKind,
     pub has_aka: bool,
     pub kyokus: Vec<Kyoku>,
+    /// Problems found when converting from [`RawLog`], which did not stop the
+    /// conversion but may make the result inaccurate.
+    pub warnings: Vec<Warning>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Warning {
+    /// The log has no "rule". The game length is assumed to be hanchan, the
+    /// game is assumed to be sanma iff seat 3 starts with 0 points, and aka is
+    /// assumed to be used iff any aka pai shows up in the log.
+    MissingRule,
+    /// The game length given to [`Log::force_game_length`] does not fit the
+    /// kyokus of the log, where `last_kyoku` is the largest `kyoku_num`.
+    GameLengthMismatch { forced: GameLength, last_kyoku: u8 },
+    /// A discard such as "o22" is marked by an unknown letter `marker`
+    /// instead of the "r" of riichi, which is assumed to be meant.
+    UnknownRiichiNotation {
+        kyoku: u8,
+        honba: u8,
+        seat: u8,
+        marker: char,
+    },
+}
+
+impl fmt::Display for Warning {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::MissingRule => write!(
+                f,
+                "the log has no rule, assuming hanchan and guessing aka from pais",
+            ),
+            Warning::GameLengthMismatch { forced, last_kyoku } => write!(
+                f,
+                "the game length is forced to be {}, which does not fit kyoku #{} of the log",
+                forced, last_kyoku,
+            ),
+            Warning::UnknownRiichiNotation {
+                kyoku,
+                honba,
+                seat,
+                marker,
+            } => write!(
+                f,
+                "unknown riichi notation {:?} of seat {} at kyoku #{} honba {}, read as a riichi",
+                marker, seat, kyoku, honba,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameLength {
     Hanchan = 0,
     Tonpuu = 4,
 }
 
+/// Whether a game is played by four or three players.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameKind {
+    Yonma,
+    Sanma,
+}
+
+impl GameKind {
+    /// Returns 4 for yonma and 3 for sanma, where seat 3 is empty.
+    #[inline]
+    pub const fn num_players(self) -> usize {
+        match self {
+            Self::Yonma => 4,
+            Self::Sanma => 3,
+        }
+    }
+}
+
 impl fmt::Display for GameLength {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -35,6 +110,8 @@ impl fmt::Display for GameLength {
 pub mod kyoku {
     use super::*;
 
+    const WINDS: [Pai; 4] = [Pai::East, Pai::South, Pai::West, Pai::North];
+
     /// Contains infomation about a kyoku.
     #[derive(Debug, Clone)]
     pub struct Kyoku {
@@ -55,8 +132,77 @@ pub mod kyoku {
 
     #[derive(Debug, Clone)]
     pub enum EndStatus {
-        Hora { details: Vec<HoraDetail> },
-        Ryukyoku { score_deltas: [i32; 4] },
+        Hora {
+            details: Vec<HoraDetail>,
+        },
+        Ryukyoku {
+            reason: RyukyokuReason,
+            score_deltas: [i32; 4],
+            /// Whether each seat was tenpai at an exhaustive draw, as told by
+            /// the status text and the score deltas. All false for other
+            /// reasons. The tenpai hands themselves can be rebuilt from
+            /// `Kyoku::action_tables`, as tenhou.net/6 logs record every hand.
+            tenpai_seats: [bool; 4],
+        },
+    }
+
+    /// Returns the tenpai seats of a ryukyoku with `status` in "結果".
+    pub(crate) fn tenpai_seats_from(status: &str, score_deltas: &[i32; 4]) -> [bool; 4] {
+        match status {
+            "全員聴牌" => [true; 4],
+            "流局" => {
+                let mut tenpai = [false; 4];
+                for (t, &d) in tenpai.iter_mut().zip(score_deltas) {
+                    *t = d > 0;
+                }
+                tenpai
+            }
+            _ => [false; 4],
+        }
+    }
+
+    /// The reason of a ryukyoku, parsed from the status text of "結果".
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum RyukyokuReason {
+        /// 流局, 全員聴牌 or 全員不聴.
+        Exhaustive,
+        /// 流し満貫
+        NagashiMangan,
+        /// 九種九牌
+        KyuushuKyuuhai,
+        /// 四風連打
+        SuufuuRenda,
+        /// 四家立直
+        SuuchaRiichi,
+        /// 四槓散了
+        SuukanSanra,
+        /// 三家和了
+        SanchaHou,
+        /// The status text is not recognized.
+        Unknown,
+    }
+
+    impl RyukyokuReason {
+        pub fn from_status(status: &str) -> Self {
+            match status {
+                "流局" | "全員聴牌" | "全員不聴" => Self::Exhaustive,
+                "流し満貫" => Self::NagashiMangan,
+                "九種九牌" => Self::KyuushuKyuuhai,
+                "四風連打" => Self::SuufuuRenda,
+                "四家立直" => Self::SuuchaRiichi,
+                "四槓散了" => Self::SuukanSanra,
+                "三家和了" => Self::SanchaHou,
+                _ => Self::Unknown,
+            }
+        }
+
+        /// Returns true if the kyoku is aborted before the first go-around
+        /// completes, namely 九種九牌 and 四風連打, where there is nothing
+        /// meaningful to review.
+        #[inline]
+        pub fn is_first_turn_abort(self) -> bool {
+            matches!(self, Self::KyuushuKyuuhai | Self::SuufuuRenda)
+        }
     }
 
     #[derive(Debug, Clone, Default)]
@@ -64,6 +210,540 @@ pub mod kyoku {
         pub who: u8,
         pub target: u8,
         pub score_deltas: [i32; 4],
+        /// The seat liable for the hora under pao, see
+        /// [`yaku::pao_seat`](crate::yaku::pao_seat). `None` when nobody is
+        /// liable or the liable player is the houjuu player, as the latter
+        /// makes no difference to the payments.
+        pub pao: Option<u8>,
+    }
+
+    impl Kyoku {
+        /// Returns true if the kyoku ended in 九種九牌 or 四風連打.
+        #[inline]
+        pub fn is_first_turn_abort(&self) -> bool {
+            matches!(
+                self.end_status,
+                EndStatus::Ryukyoku { reason, .. } if reason.is_first_turn_abort()
+            )
+        }
+
+        /// Returns the sum of all players' scores and the kyotaku on the
+        /// table at the beginning of the kyoku.
+        #[inline]
+        pub fn points_on_table(&self) -> i32 {
+            self.scoreboard.iter().sum::<i32>() + self.meta.kyotaku as i32 * 1000
+        }
+
+        /// Returns the number of riichi declared in the kyoku, including the
+        /// ones dealt in on the declaring tile.
+        pub fn riichi_count(&self) -> u8 {
+            self.action_tables
+                .iter()
+                .flat_map(|t| &t.discards)
+                .filter(|a| matches!(a, ActionItem::Naki(s) if s.starts_with('r')))
+                .count() as u8
+        }
+
+        /// Returns the kyotaku carried into the next kyoku, which is 0 if
+        /// anyone won.
+        pub fn leaving_kyotaku(&self) -> u8 {
+            match self.end_status {
+                EndStatus::Hora { .. } => 0,
+                EndStatus::Ryukyoku { .. } => self.meta.kyotaku + self.riichi_count(),
+            }
+        }
+
+        /// Returns true if `seat` declared riichi and paid 1000 points for
+        /// it, which is not the case when the declaring discard is dealt in.
+        pub fn paid_riichi(&self, seat: u8) -> bool {
+            let table = &self.action_tables[seat as usize];
+            match table.riichi_turn() {
+                Some(turn) => turn + 1 < table.discards.len() || self.deal_in_seat() != Some(seat),
+                None => false,
+            }
+        }
+
+        /// Returns the scores of each player at the end of the kyoku, after
+        /// paying for riichi and applying the score deltas.
+        pub fn leaving_scores(&self) -> [i32; 4] {
+            let mut scores = self.scoreboard;
+            for (seat, score) in (0..4).zip(scores.iter_mut()) {
+                if self.paid_riichi(seat) {
+                    *score -= 1000;
+                }
+            }
+            for (score, delta) in scores.iter_mut().zip(self.score_deltas()) {
+                *score += delta;
+            }
+
+            scores
+        }
+
+        /// Returns the sum of the score deltas of every hora, or those of the
+        /// ryukyoku, without the riichi sticks.
+        pub fn score_deltas(&self) -> [i32; 4] {
+            let mut sum = [0; 4];
+            let mut apply = |deltas: &[i32; 4]| {
+                for (s, delta) in sum.iter_mut().zip(deltas) {
+                    *s += delta;
+                }
+            };
+            match &self.end_status {
+                EndStatus::Hora { details } => {
+                    details.iter().for_each(|d| apply(&d.score_deltas));
+                }
+                EndStatus::Ryukyoku { score_deltas, .. } => apply(score_deltas),
+            }
+
+            sum
+        }
+
+        /// Returns the number of turns the kyoku lasted, which is the most
+        /// discards made by a single player.
+        #[inline]
+        pub fn turn_count(&self) -> usize {
+            self.action_tables
+                .iter()
+                .map(|t| t.discards.len())
+                .max()
+                .unwrap_or(0)
+        }
+
+        /// Returns the number of kita of each player.
+        pub fn kita_counts(&self) -> [usize; 4] {
+            let mut ret = [0; 4];
+            for (count, table) in ret.iter_mut().zip(&self.action_tables) {
+                *count = table.kita_count();
+            }
+            ret
+        }
+
+        /// Returns how many more kans can be made after the first `up_to_turn`
+        /// turns of every player, out of the 4 kans a kyoku allows. A kakan
+        /// robbed by chankan still counts as declared.
+        pub fn kans_remaining(&self, up_to_turn: usize) -> u8 {
+            let kans = self
+                .action_tables
+                .iter()
+                .flat_map(|t| {
+                    let takes = t.takes.iter().take(up_to_turn);
+                    let discards = t.discards.iter().take(up_to_turn);
+                    takes.chain(discards)
+                })
+                .filter(|a| a.is_kan())
+                .count();
+            4_usize.saturating_sub(kans) as u8
+        }
+
+        /// Checks that the kyoku has one dora indicator, plus one for each
+        /// kan made in it.
+        ///
+        /// When the kyoku ends in a hora, the kans a player makes in a row at
+        /// the very end of their discards may have no indicator, as a kakan
+        /// robbed by chankan never completes, and the indicator of the kakan
+        /// before it is only revealed with a discard after the rinshan tsumo.
+        pub fn verify_dora_count(&self) -> std::result::Result<(), DoraCountError> {
+            let kans = 4 - self.kans_remaining(usize::MAX) as usize;
+            let actual = self.dora_indicators.len();
+            let unrevealed = if matches!(self.end_status, EndStatus::Hora { .. }) {
+                self.action_tables
+                    .iter()
+                    .map(|t| t.discards.iter().rev().take_while(|a| a.is_kan()).count())
+                    .max()
+                    .unwrap_or(0)
+            } else {
+                0
+            };
+
+            if (1 + kans - unrevealed..=1 + kans).contains(&actual) {
+                Ok(())
+            } else {
+                Err(DoraCountError { kans, actual })
+            }
+        }
+
+        /// Checks that no pai shows up more times than it exists in the wall,
+        /// counting haipai, draws and dora and ura indicators, each of which
+        /// reveals a new pai from the wall.
+        ///
+        /// Returns the first pai found over-counted. Aka are counted both as
+        /// themselves, at most 1 of each, and as normal 5s.
+        pub fn check_tile_conservation(&self) -> std::result::Result<(), TileConflict> {
+            let mut counts = [0_u8; 34];
+            let mut aka_counts = [0_u8; 3];
+
+            let draws = self.action_tables.iter().flat_map(|t| {
+                let takes = t.takes.iter().filter_map(|item| match *item {
+                    ActionItem::Pai(pai) => Some(pai),
+                    _ => None,
+                });
+                t.haipai.iter().copied().chain(takes)
+            });
+            let indicators = self.dora_indicators.iter().chain(&self.ura_indicators);
+
+            for pai in draws.chain(indicators.copied()) {
+                let index = match pai.as_index34() {
+                    Some(index) => index,
+                    None => continue,
+                };
+                counts[index] += 1;
+                if counts[index] > 4 {
+                    return Err(TileConflict {
+                        pai: pai.deaka(),
+                        count: counts[index],
+                    });
+                }
+
+                let aka_index = match pai {
+                    Pai::AkaMan5 => 0,
+                    Pai::AkaPin5 => 1,
+                    Pai::AkaSou5 => 2,
+                    _ => continue,
+                };
+                aka_counts[aka_index] += 1;
+                if aka_counts[aka_index] > 1 {
+                    return Err(TileConflict {
+                        pai,
+                        count: aka_counts[aka_index],
+                    });
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Replays the kyoku and checks that every action is possible, that
+        /// is, every discard and kan is made from the hand, every call takes
+        /// the last discard of its target with pais from the hand, and no one
+        /// draws after the wall is exhausted.
+        ///
+        /// This is stricter than [`check_tile_conservation`], which only
+        /// counts pais. Returns the first impossible action found.
+        ///
+        /// The size of the wall depends on `game_kind`, which should come
+        /// from [`Log::game_kind`].
+        ///
+        /// [`check_tile_conservation`]: Self::check_tile_conservation
+        pub fn validate_actions(
+            &self,
+            game_kind: GameKind,
+        ) -> std::result::Result<(), ActionError> {
+            let options = ConvertOptions {
+                strict_tsumogiri: true,
+            };
+            let events = conv::tenhou_kyoku_to_mjai_events(self, options)?;
+
+            let live_wall = match game_kind {
+                GameKind::Yonma => 136 - 14 - 13 * 4,
+                GameKind::Sanma => 108 - 14 - 13 * 3,
+            };
+
+            let mut tehais: [Vec<Pai>; 4] = Default::default();
+            let mut takes = [0_usize; 4];
+            let mut draws = 0;
+            let mut last_dahai = None;
+
+            for event in &events {
+                let actor = match event.actor() {
+                    Some(actor) => actor,
+                    None => {
+                        if let Event::StartKyoku {
+                            tehais: haipais, ..
+                        } = event
+                        {
+                            for (tehai, haipai) in tehais.iter_mut().zip(haipais) {
+                                tehai.extend_from_slice(haipai);
+                            }
+                        }
+                        continue;
+                    }
+                };
+                let seat = actor as usize;
+                let tehai = &mut tehais[seat];
+                let error = |turn, kind| ActionError::Impossible {
+                    seat: actor,
+                    turn,
+                    kind,
+                };
+                let following_turn = takes[seat].saturating_sub(1);
+
+                match *event {
+                    Event::Tsumo { pai, .. } => {
+                        if draws == live_wall {
+                            return Err(error(takes[seat], ImpossibleAction::WallExhausted));
+                        }
+                        draws += 1;
+                        takes[seat] += 1;
+                        tehai.push(pai);
+                    }
+
+                    Event::Dahai { pai, .. } => {
+                        take_from_hand(tehai, &[pai])
+                            .map_err(|kind| error(following_turn, kind))?;
+                        last_dahai = Some((actor, pai));
+                    }
+
+                    Event::Chi {
+                        target,
+                        pai,
+                        consumed,
+                        ..
+                    }
+                    | Event::Pon {
+                        target,
+                        pai,
+                        consumed,
+                        ..
+                    } => {
+                        if last_dahai != Some((target, pai)) {
+                            return Err(error(takes[seat], ImpossibleAction::NotDiscarded(pai)));
+                        }
+                        take_from_hand(tehai, &consumed.as_array())
+                            .map_err(|kind| error(takes[seat], kind))?;
+                        takes[seat] += 1;
+                    }
+
+                    Event::Daiminkan {
+                        target,
+                        pai,
+                        consumed,
+                        ..
+                    } => {
+                        if last_dahai != Some((target, pai)) {
+                            return Err(error(takes[seat], ImpossibleAction::NotDiscarded(pai)));
+                        }
+                        take_from_hand(tehai, &consumed.as_array())
+                            .map_err(|kind| error(takes[seat], kind))?;
+                        takes[seat] += 1;
+                    }
+
+                    Event::Kakan { pai, .. } => {
+                        take_from_hand(tehai, &[pai])
+                            .map_err(|kind| error(following_turn, kind))?;
+                    }
+
+                    Event::Ankan { consumed, .. } => {
+                        take_from_hand(tehai, &consumed.as_array())
+                            .map_err(|kind| error(following_turn, kind))?;
+                    }
+
+                    _ => (),
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Replays the kyoku and takes a [`BoardSnapshot`] from `hero_seat`
+        /// after each of its mjai events, in chronological order, from the
+        /// StartKyoku to the end of the kyoku.
+        ///
+        /// The concealed pais of the others are masked, see
+        /// [`Visibility::HeroOnly`](crate::board::Visibility::HeroOnly).
+        pub fn replay_frames(
+            &self,
+            hero_seat: u8,
+        ) -> std::result::Result<Vec<BoardSnapshot>, ConvertError> {
+            let events = conv::tenhou_kyoku_to_mjai_events(self, ConvertOptions::default())?;
+            let mut board = Board::default();
+            let frames = events
+                .iter()
+                .map(|event| {
+                    board.update(event);
+                    board.snapshot(hero_seat, Visibility::HeroOnly)
+                })
+                .collect();
+            Ok(frames)
+        }
+
+        /// Returns whether each seat is tenpai after its `turn`-th discard,
+        /// counting from 0, or after its last discard if it has fewer. A seat
+        /// without any discard yet is not tenpai.
+        pub fn tenpai_seats_at(&self, turn: usize) -> [bool; 4] {
+            let mut ret = [false; 4];
+            for (tenpai, table) in ret.iter_mut().zip(&self.action_tables) {
+                let shanten_by_turn = table.shanten_by_turn();
+                let end = shanten_by_turn.len().min(turn + 1);
+                *tenpai = shanten_by_turn[..end]
+                    .iter()
+                    .rev()
+                    .flatten()
+                    .next()
+                    .is_some_and(|&s| s <= 0);
+            }
+            ret
+        }
+
+        /// Returns the seat of the dealer. It only depends on `kyoku_num`,
+        /// which stays the same over a renchan while `honba` goes up, as
+        /// tenhou always starts with seat 0 as the dealer.
+        #[inline]
+        pub fn oya(&self) -> u8 {
+            self.meta.kyoku_num % 4
+        }
+
+        /// Returns the round wind (場風), East for E1 to E4 and so on.
+        #[inline]
+        pub fn round_wind(&self) -> Pai {
+            WINDS[(self.meta.kyoku_num / 4).min(3) as usize]
+        }
+
+        /// Returns the seat wind (自風) of `seat`, East for the dealer and
+        /// the next winds for the seats after it in turn order.
+        ///
+        /// In sanma the winds go around the three seats only and nobody is
+        /// North. `game_kind` should come from [`Log::game_kind`], as a
+        /// yonma kyoku may end before seat 3 ever draws.
+        pub fn seat_wind(&self, seat: u8, game_kind: GameKind) -> Pai {
+            let players = game_kind.num_players() as u8;
+            WINDS[((seat + players - self.oya()) % players) as usize]
+        }
+
+        /// Returns the seats of all winners of the kyoku, in the order of
+        /// "結果". Empty if the kyoku ended in ryukyoku.
+        pub fn winners(&self) -> Vec<u8> {
+            match &self.end_status {
+                EndStatus::Hora { details } => details.iter().map(|d| d.who).collect(),
+                EndStatus::Ryukyoku { .. } => vec![],
+            }
+        }
+
+        /// Returns the seat of the player who dealt in, or None if the kyoku
+        /// ended in tsumo or ryukyoku.
+        pub fn deal_in_seat(&self) -> Option<u8> {
+            match &self.end_status {
+                EndStatus::Hora { details } => details
+                    .first()
+                    .filter(|d| d.who != d.target)
+                    .map(|d| d.target),
+                EndStatus::Ryukyoku { .. } => None,
+            }
+        }
+
+        /// Returns whether `seat` was tenpai after any of its discards, or won
+        /// the kyoku, which also covers a winning haipai without discards.
+        pub fn reached_tenpai(&self, seat: u8) -> bool {
+            self.action_tables[seat as usize].tenpai_turn().is_some()
+                || self.winners().contains(&seat)
+        }
+
+        /// Returns the number of ura dora in the winning hand of `seat`,
+        /// including fuuros, or None unless `seat` won the kyoku with riichi.
+        ///
+        /// The hand is rebuilt by replaying the kyoku, with the pai dealt in
+        /// added for a ron. Aka are counted as normal 5s.
+        pub fn ura_dora_count(&self, seat: u8) -> std::result::Result<Option<u8>, ConvertError> {
+            if self.action_tables[seat as usize].riichi_turn().is_none()
+                || !self.winners().contains(&seat)
+            {
+                return Ok(None);
+            }
+
+            let events = conv::tenhou_kyoku_to_mjai_events(self, ConvertOptions::default())?;
+            let mut board = Board::default();
+            let mut last_discard = None;
+            for event in &events {
+                match *event {
+                    Event::Dahai { pai, .. } | Event::Kakan { pai, .. } => {
+                        last_discard = Some(pai);
+                    }
+                    Event::Hora { actor, target, .. } if actor == seat => {
+                        let mut pais = board.tehais[seat as usize].clone();
+                        if actor != target {
+                            pais.extend(last_discard);
+                        }
+                        pais.extend_from_slice(&board.fuuros[seat as usize]);
+                        return Ok(Some(dora_han(&pais, &self.ura_indicators, false)));
+                    }
+                    _ => (),
+                }
+                board.update(event);
+            }
+
+            Ok(None)
+        }
+
+        /// Returns a view of the kyoku from `hero_seat`, see [`KyokuPov`].
+        #[inline]
+        pub fn pov(&self, hero_seat: u8) -> KyokuPov<'_> {
+            KyokuPov {
+                kyoku: self,
+                hero_seat: hero_seat % 4,
+            }
+        }
+    }
+
+    /// Names of the relative seats from 0 to 3 in a [`KyokuPov`].
+    pub const RELATIVE_SEAT_NAMES: [&str; 4] = ["自家", "下家", "対面", "上家"];
+
+    /// A kyoku seen from `hero_seat`, where the relative seat 0 is the hero,
+    /// 1 is 下家 (shimocha), 2 is 対面 (toimen) and 3 is 上家 (kamicha).
+    ///
+    /// Everything per seat is rotated on access, the kyoku is only borrowed.
+    /// In sanma the empty seat rotates along with the others.
+    #[derive(Debug, Clone, Copy)]
+    pub struct KyokuPov<'a> {
+        pub kyoku: &'a Kyoku,
+        pub hero_seat: u8,
+    }
+
+    impl KyokuPov<'_> {
+        /// Returns the absolute seat of the relative seat `relative`.
+        #[inline]
+        pub fn seat(&self, relative: u8) -> u8 {
+            (self.hero_seat + relative) % 4
+        }
+
+        /// Returns the relative seat of the absolute seat `seat`.
+        #[inline]
+        pub fn relative(&self, seat: u8) -> u8 {
+            (seat + 4 - self.hero_seat) % 4
+        }
+
+        /// Reorders `per_seat`, indexed by absolute seats, by relative seats.
+        pub fn rotate<T: Copy>(&self, per_seat: [T; 4]) -> [T; 4] {
+            let mut ret = per_seat;
+            for (relative, item) in ret.iter_mut().enumerate() {
+                *item = per_seat[self.seat(relative as u8) as usize];
+            }
+            ret
+        }
+
+        /// The inverse of [`rotate`](Self::rotate).
+        pub fn unrotate<T: Copy>(&self, per_relative: [T; 4]) -> [T; 4] {
+            let mut ret = per_relative;
+            for (seat, item) in ret.iter_mut().enumerate() {
+                *item = per_relative[self.relative(seat as u8) as usize];
+            }
+            ret
+        }
+
+        /// Returns the relative seat of the dealer.
+        #[inline]
+        pub fn oya(&self) -> u8 {
+            self.relative(self.kyoku.oya())
+        }
+
+        #[inline]
+        pub fn action_table(&self, relative: u8) -> &ActionTable {
+            &self.kyoku.action_tables[self.seat(relative) as usize]
+        }
+
+        #[inline]
+        pub fn scoreboard(&self) -> [i32; 4] {
+            self.rotate(self.kyoku.scoreboard)
+        }
+
+        #[inline]
+        pub fn leaving_scores(&self) -> [i32; 4] {
+            self.rotate(self.kyoku.leaving_scores())
+        }
+
+        /// Same as [`Kyoku::score_deltas`], by relative seats.
+        #[inline]
+        pub fn score_deltas(&self) -> [i32; 4] {
+            self.rotate(self.kyoku.score_deltas())
+        }
     }
 }
 
@@ -78,6 +758,171 @@ pub struct ActionTable {
     pub discards: Vec<ActionItem>,
 }
 
+impl ActionTable {
+    /// Returns the shanten number of the hand right after each discard, in
+    /// the same order as `discards`.
+    ///
+    /// The items are None for kans in `discards`, after which the hand waits
+    /// for a rinshan instead of a discard.
+    pub fn shanten_by_turn(&self) -> Vec<Option<i8>> {
+        let mut tehai = [0; 34];
+        let mut fuuro_count = 0;
+        self.haipai
+            .iter()
+            .for_each(|&pai| add_pai(&mut tehai, pai, 1));
+
+        let mut ret = Vec::with_capacity(self.discards.len());
+        let mut last_take = Pai::Unknown;
+        for (take, discard) in self.takes.iter().zip(&self.discards) {
+            match take {
+                &ActionItem::Pai(pai) => {
+                    add_pai(&mut tehai, pai, 1);
+                    last_take = pai;
+                }
+                ActionItem::Naki(naki) => {
+                    // chi, pon or daiminkan, only the consumed pais are from
+                    // the hand
+                    let (_, consumed) = naki_pais(naki);
+                    consumed.iter().for_each(|&p| add_pai(&mut tehai, p, -1));
+                    fuuro_count += 1;
+                }
+                ActionItem::Tsumogiri(_) => (),
+            };
+
+            let is_dahai = match discard {
+                &ActionItem::Pai(Pai::Unknown) => false, // after daiminkan
+                &ActionItem::Pai(pai) => {
+                    add_pai(&mut tehai, pai, -1);
+                    true
+                }
+                ActionItem::Tsumogiri(_) => {
+                    add_pai(&mut tehai, last_take, -1);
+                    true
+                }
+                ActionItem::Naki(naki) => match naki.as_bytes().first() {
+                    Some(b'r') => {
+                        let pai = match naki_pais(naki) {
+                            (Some(pai), _) => pai,
+                            (None, _) => last_take, // "r60"
+                        };
+                        add_pai(&mut tehai, pai, -1);
+                        true
+                    }
+                    _ => {
+                        let (marked, others) = naki_pais(naki);
+                        if naki.contains('a') {
+                            // ankan, all 4 pais are from the hand
+                            others.iter().for_each(|&p| add_pai(&mut tehai, p, -1));
+                            fuuro_count += 1;
+                        }
+                        // for kakan, only the added pai is from the hand, so
+                        // is the north for kita
+                        if let Some(pai) = marked {
+                            add_pai(&mut tehai, pai, -1);
+                        }
+                        false
+                    }
+                },
+            };
+
+            ret.push(if is_dahai {
+                Some(shanten::calc(&tehai, fuuro_count))
+            } else {
+                None
+            });
+        }
+
+        ret
+    }
+
+    /// Returns the number of norths extracted as kita, each of which counts as
+    /// a dora in sanma.
+    pub fn kita_count(&self) -> usize {
+        self.discards.iter().filter(|a| a.is_kita()).count()
+    }
+
+    /// Returns the index in `discards` of the first discard after which the
+    /// hand is tenpai.
+    pub fn tenpai_turn(&self) -> Option<usize> {
+        self.shanten_by_turn()
+            .into_iter()
+            .position(|s| matches!(s, Some(s) if s <= 0))
+    }
+
+    /// Returns the turns, as indexes of "出", whose discard broke a tenpai
+    /// that the previous discard had left, such as when folding.
+    ///
+    /// Turns without a discard, such as kans, are skipped over, and changing
+    /// the waits while staying tenpai is not a break.
+    pub fn tenpai_breaks(&self) -> Vec<usize> {
+        let mut last_shanten = None;
+        self.shanten_by_turn()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(turn, shanten)| {
+                let shanten = shanten?;
+                let was_tenpai = matches!(last_shanten.replace(shanten), Some(s) if s <= 0);
+                (was_tenpai && shanten > 0).then_some(turn)
+            })
+            .collect()
+    }
+
+    /// Returns the index in `discards` of the riichi declaration.
+    pub fn riichi_turn(&self) -> Option<usize> {
+        self.discards
+            .iter()
+            .position(|a| matches!(a, ActionItem::Naki(s) if s.starts_with('r')))
+    }
+
+    /// Returns how many turns riichi was declared after the hand first
+    /// became tenpai.
+    pub fn riichi_delay(&self) -> Option<usize> {
+        let riichi_turn = self.riichi_turn()?;
+        let tenpai_turn = self.tenpai_turn()?;
+        Some(riichi_turn.saturating_sub(tenpai_turn))
+    }
+}
+
+fn add_pai(tehai: &mut [u8; 34], pai: Pai, n: i8) {
+    if let Some(idx) = pai.as_index34() {
+        tehai[idx] = (tehai[idx] as i8 + n).max(0) as u8;
+    }
+}
+
+/// Splits the pais in a naki string into the one marked by a letter like 'p'
+/// and 'k', and the others.
+fn naki_pais(naki: &str) -> (Option<Pai>, Vec<Pai>) {
+    let mut marked = None;
+    let mut others = vec![];
+
+    let bytes = naki.as_bytes();
+    let mut i = 0;
+    let mut is_marked = false;
+    while i < bytes.len() {
+        if bytes[i].is_ascii_alphabetic() {
+            is_marked = true;
+            i += 1;
+            continue;
+        }
+
+        let pai = naki
+            .get(i..i + 2)
+            .and_then(|s| s.parse::<u8>().ok())
+            .and_then(|id| Pai::try_from(id).ok());
+        if let Some(pai) = pai {
+            if is_marked {
+                marked = Some(pai);
+            } else {
+                others.push(pai);
+            }
+        }
+        is_marked = false;
+        i += 2;
+    }
+
+    (marked, others)
+}
+
 /// An item corresponding to each elements in "配牌", "取" and "出".
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -87,10 +932,24 @@ pub enum ActionItem {
     Naki(String),
 }
 
+impl ActionItem {
+    /// Returns true if it is a daiminkan, ankan or kakan.
+    #[inline]
+    pub fn is_kan(&self) -> bool {
+        matches!(self, ActionItem::Naki(naki) if naki.contains(&['m', 'a', 'k'][..]))
+    }
+
+    /// Returns true if it is a kita (抜きドラ) in sanma, e.g. "f44".
+    #[inline]
+    pub fn is_kita(&self) -> bool {
+        matches!(self, ActionItem::Naki(naki) if naki.starts_with('f'))
+    }
+}
+
 mod json_scheme {
     use super::*;
 
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Serialize)]
     #[serde(untagged)]
     pub(super) enum ResultItem {
         Status(String),
@@ -98,6 +957,49 @@ mod json_scheme {
         HoraDetail(Vec<Value>),
     }
 
+    impl<'de> Deserialize<'de> for ResultItem {
+        /// Score deltas may also come as numeric strings like "-1000" from
+        /// some tools. An array of 4 numbers or strings is taken as score
+        /// deltas, as a hora detail always has the winner, the target, the
+        /// pao seat, the points and at least one yaku.
+        fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            use serde::de::Error;
+
+            let items = match Value::deserialize(deserializer)? {
+                Value::String(status) => return Ok(Self::Status(status)),
+                Value::Array(items) => items,
+                other => {
+                    return Err(D::Error::custom(format!(
+                        "invalid result item {}, expected a string or an array",
+                        other,
+                    )))
+                }
+            };
+
+            let is_score_deltas =
+                items.len() == 4 && items.iter().all(|v| v.is_number() || v.is_string());
+            if !is_score_deltas {
+                return Ok(Self::HoraDetail(items));
+            }
+
+            let mut deltas = [0; 4];
+            for (delta, item) in deltas.iter_mut().zip(&items) {
+                let parsed = match item {
+                    Value::Number(n) => n.as_i64().and_then(|n| i32::try_from(n).ok()),
+                    Value::String(s) => s.trim().parse().ok(),
+                    _ => None,
+                };
+                *delta = parsed.ok_or_else(|| {
+                    D::Error::custom(format!("invalid score delta {}, expected an integer", item))
+                })?;
+            }
+            Ok(Self::ScoreDeltas(deltas))
+        }
+    }
+
     #[derive(Debug, Clone, SerializeTuple, DeserializeTuple)]
     pub(super) struct Kyoku {
         pub(super) meta: kyoku::Meta,
@@ -124,6 +1026,98 @@ mod json_scheme {
         pub(super) results: Vec<ResultItem>,
     }
 
+    impl Kyoku {
+        /// Returns true if any aka pai shows up in haipai, takes, discards or
+        /// dora indicators.
+        pub(super) fn has_aka_pai(&self) -> bool {
+            let is_aka = |pai: &Pai| pai.is_aka();
+            let is_aka_item = |item: &ActionItem| match item {
+                ActionItem::Pai(pai) => is_aka(pai),
+                ActionItem::Naki(naki) => {
+                    let (marked, others) = naki_pais(naki);
+                    marked.iter().chain(&others).any(is_aka)
+                }
+                ActionItem::Tsumogiri(_) => false,
+            };
+
+            let haipais = [
+                &self.haipai_0,
+                &self.haipai_1,
+                &self.haipai_2,
+                &self.haipai_3,
+            ];
+            let items = [
+                &self.takes_0,
+                &self.discards_0,
+                &self.takes_1,
+                &self.discards_1,
+                &self.takes_2,
+                &self.discards_2,
+                &self.takes_3,
+                &self.discards_3,
+            ];
+
+            haipais.iter().any(|h| h.iter().any(is_aka))
+                || items.iter().any(|i| i.iter().any(is_aka_item))
+                || self.dora_indicators.iter().any(is_aka)
+                || self.ura_indicators.iter().any(is_aka)
+        }
+
+        /// Same as [`Kyoku::reached_tenpai`](super::Kyoku::reached_tenpai).
+        pub(super) fn reached_tenpai(&self, seat: u8) -> bool {
+            let (haipai, takes, discards) = match seat {
+                0 => (&self.haipai_0, &self.takes_0, &self.discards_0),
+                1 => (&self.haipai_1, &self.takes_1, &self.discards_1),
+                2 => (&self.haipai_2, &self.takes_2, &self.discards_2),
+                _ => (&self.haipai_3, &self.takes_3, &self.discards_3),
+            };
+            let table = ActionTable {
+                haipai: *haipai,
+                takes: takes.clone(),
+                discards: discards.clone(),
+            };
+            if table.tenpai_turn().is_some() {
+                return true;
+            }
+
+            // the first item of each "和了" detail is the winner
+            self.results.iter().any(|item| match item {
+                ResultItem::HoraDetail(detail) => {
+                    detail.first().and_then(Value::as_u64) == Some(seat as u64)
+                }
+                _ => false,
+            })
+        }
+
+        /// Same as [`Kyoku::deal_in_seat`](super::Kyoku::deal_in_seat).
+        pub(super) fn deal_in_seat(&self) -> Option<u8> {
+            match self.results.as_slice() {
+                [ResultItem::Status(status_text), _, ResultItem::HoraDetail(detail), ..]
+                    if status_text == "和了" =>
+                {
+                    let who = detail.first()?.as_u64()?;
+                    let target = detail.get(1)?.as_u64()?;
+                    if who != target {
+                        Some(target as u8)
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            }
+        }
+
+        /// Returns None if the kyoku did not end in ryukyoku.
+        pub(super) fn ryukyoku_reason(&self) -> Option<kyoku::RyukyokuReason> {
+            match self.results.first() {
+                Some(ResultItem::Status(status_text)) if status_text != "和了" => {
+                    Some(kyoku::RyukyokuReason::from_status(status_text))
+                }
+                _ => None,
+            }
+        }
+    }
+
     #[derive(Debug, Clone, Default, Serialize, Deserialize)]
     #[serde(default)]
     pub(super) struct Rule {
@@ -140,8 +1134,15 @@ mod json_scheme {
         pub(super) logs: Vec<Kyoku>,
         #[serde(rename = "name")]
         pub(super) names: [String; 4],
-        pub(super) rule: Rule,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub(super) rule: Option<Rule>,
 
+        /// The tenhou log id of the game, given by tenhou.net/5 and /6.
+        #[serde(rename = "ref")]
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub(super) reference: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         pub(super) ratingc: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -152,15 +1153,87 @@ mod json_scheme {
         pub(super) rate: Option<Vec<f64>>,
         #[serde(skip_serializing_if = "Option::is_none")]
         pub(super) sx: Option<Vec<String>>,
+
+        /// The final scores and the resulting pt of each seat alternately,
+        /// given by tenhou.net/6 for complete games.
+        ///
+        /// Despite the name it says nothing about seating: tenhou always
+        /// starts a game with seat 0 as the dealer (起家), which
+        /// [`Kyoku::oya`] relies on. Kept as given, since the pt may or may
+        /// not be written with a fraction.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub(super) sc: Option<Vec<Value>>,
     }
 
-    #[derive(Debug, Serialize)]
+    /// Some kyokus of a [`Log`], which serializes as a complete log with the
+    /// metadata of the parent but without the other kyokus, borrowing
+    /// everything.
+    #[derive(Debug, Clone, Copy)]
     pub struct PartialLog<'a> {
-        #[serde(flatten)]
         pub(super) parent: &'a Log,
+        pub(super) logs: &'a [Kyoku],
+    }
 
+    /// The borrowed form of [`Log`] to serialize a [`PartialLog`] with.
+    #[derive(Serialize)]
+    struct LogRef<'a> {
         #[serde(rename = "log")]
-        pub(super) logs: &'a [Kyoku],
+        logs: &'a [Kyoku],
+        #[serde(rename = "name")]
+        names: &'a [String; 4],
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rule: Option<&'a Rule>,
+
+        #[serde(rename = "ref")]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reference: Option<&'a String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ratingc: Option<&'a String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        lobby: Option<i32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        dan: Option<&'a Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rate: Option<&'a Vec<f64>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sx: Option<&'a Vec<String>>,
+    }
+
+    impl Serialize for PartialLog<'_> {
+        fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            // exhaustive, so that a new field of Log can not be missed here
+            //
+            // "sc" is left out as it is the result of the whole game, not of
+            // the kyokus kept.
+            let Log {
+                logs: _,
+                names,
+                rule,
+                reference,
+                ratingc,
+                lobby,
+                dan,
+                rate,
+                sx,
+                sc: _,
+            } = self.parent;
+
+            LogRef {
+                logs: self.logs,
+                names,
+                rule: rule.as_ref(),
+                reference: reference.as_ref(),
+                ratingc: ratingc.as_ref(),
+                lobby: *lobby,
+                dan: dan.as_ref(),
+                rate: rate.as_ref(),
+                sx: sx.as_ref(),
+            }
+            .serialize(serializer)
+        }
     }
 }
 
@@ -171,16 +1244,62 @@ impl RawLog {
         &self.names
     }
 
+    /// Returns the [`GameInfo`] of the log, with the log id from its "ref".
+    #[inline]
+    pub fn game_info(&self) -> GameInfo {
+        GameInfo {
+            log_id: self.reference.clone(),
+        }
+    }
+
     #[inline]
     pub fn hide_names(&mut self) {
-        self.names
-            .iter_mut()
-            .zip('A'..='D')
-            .for_each(|(name, alias)| {
-                name.clear();
-                name.push(alias);
-                name.push_str("さん");
-            });
+        hide_names(&mut self.names);
+    }
+
+    /// Returns the final scores from "sc", or None if the log does not have
+    /// it or it is malformed.
+    pub fn final_scores(&self) -> Option<[i32; 4]> {
+        let sc = self.sc.as_ref()?;
+        if sc.len() != 8 {
+            return None;
+        }
+        let mut scores = [0; 4];
+        for (score, value) in scores.iter_mut().zip(sc.iter().step_by(2)) {
+            *score = i32::try_from(value.as_i64()?).ok()?;
+        }
+        Some(scores)
+    }
+
+    /// Renames the player at `seat`, leaving the others untouched.
+    pub fn rename_player(
+        &mut self,
+        seat: u8,
+        name: &str,
+    ) -> std::result::Result<(), SeatOutOfRange> {
+        let players = self.game_kind().num_players();
+        if seat as usize >= players {
+            return Err(SeatOutOfRange { seat, players });
+        }
+
+        let slot = &mut self.names[seat as usize];
+        slot.clear();
+        slot.push_str(name);
+        Ok(())
+    }
+
+    /// Sanma if "三" is in the rule, or if seat 3 starts with 0 points in a
+    /// log without the rule.
+    pub fn game_kind(&self) -> GameKind {
+        let is_sanma = match &self.rule {
+            Some(rule) => rule.disp.contains('三'),
+            None => self.logs.first().is_some_and(|k| k.scoreboard[3] == 0),
+        };
+        if is_sanma {
+            GameKind::Sanma
+        } else {
+            GameKind::Yonma
+        }
     }
 
     #[inline]
@@ -189,6 +1308,29 @@ impl RawLog {
             .retain(|l| kyoku_filter.test(l.meta.kyoku_num, l.meta.honba))
     }
 
+    /// Keeps only kyokus where `seat` dealt in.
+    #[inline]
+    pub fn retain_deal_ins(&mut self, seat: u8) {
+        self.logs.retain(|l| l.deal_in_seat() == Some(seat));
+    }
+
+    /// Keeps only kyokus where `seat` reached tenpai, see
+    /// [`Kyoku::reached_tenpai`].
+    #[inline]
+    pub fn retain_tenpai(&mut self, seat: u8) {
+        self.logs.retain(|l| l.reached_tenpai(seat));
+    }
+
+    /// Remove kyokus that ended in 九種九牌 or 四風連打.
+    #[inline]
+    pub fn skip_first_turn_aborts(&mut self) {
+        self.logs.retain(|l| {
+            !l.ryukyoku_reason()
+                .map(|reason| reason.is_first_turn_abort())
+                .unwrap_or(false)
+        })
+    }
+
     /// Split one raw tenhou.net/6 log into many by kyokus.
     pub fn split_by_kyoku(&self) -> Vec<RawPartialLog<'_>> {
         let mut ret = vec![];
@@ -205,6 +1347,16 @@ impl RawLog {
         ret
     }
 
+    /// Returns an owned log of only the kyoku at `index`, which is a complete
+    /// log on its own that the tenhou.net/6 viewer can open.
+    ///
+    /// Returns None if `index` is out of range.
+    pub fn to_standalone_log(&self, index: usize) -> Option<RawLog> {
+        let logs = self.logs.get(index..=index)?;
+        let partial_log = RawPartialLog { parent: self, logs };
+        Some(RawLog::from(partial_log))
+    }
+
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.logs.is_empty()
@@ -218,9 +1370,31 @@ impl RawLog {
 
 impl From<RawPartialLog<'_>> for RawLog {
     fn from(partial_log: RawPartialLog) -> Self {
+        // only the metadata is cloned, not the kyokus of the parent
+        let RawLog {
+            logs: _,
+            names,
+            rule,
+            reference,
+            ratingc,
+            lobby,
+            dan,
+            rate,
+            sx,
+            sc: _,
+        } = partial_log.parent;
+
         RawLog {
             logs: partial_log.logs.to_vec(),
-            ..partial_log.parent.clone()
+            names: names.clone(),
+            rule: rule.clone(),
+            reference: reference.clone(),
+            ratingc: ratingc.clone(),
+            lobby: *lobby,
+            dan: dan.clone(),
+            rate: rate.clone(),
+            sx: sx.clone(),
+            sc: None,
         }
     }
 }
@@ -233,25 +1407,672 @@ impl Log {
         Ok(Self::from(raw_log))
     }
 
+    /// Parse tenhou.net/6 logs from `reader` with one JSON object per line,
+    /// lazily one line at a time. Blank lines are skipped.
+    ///
+    /// A malformed line turns into an `Err` of the iterator, after which the
+    /// rest of the lines are still read.
+    #[inline]
+    pub fn from_json_lines<R: BufRead>(reader: R) -> JsonLines<R> {
+        JsonLines {
+            lines: reader.lines(),
+            line_num: 0,
+        }
+    }
+
+    #[inline]
+    pub fn hide_names(&mut self) {
+        hide_names(&mut self.names);
+    }
+
+    /// Turns every aka into its normal 5, in haipai, takes, discards, nakis
+    /// and dora indicators, and marks the log as without aka.
+    ///
+    /// This changes the values of hands as seen by anything evaluating them
+    /// from the pais. The recorded results and scores are kept as they are.
+    pub fn deaka(&mut self) {
+        let deaka_item = |item: &mut ActionItem| match item {
+            ActionItem::Pai(pai) => *pai = pai.deaka(),
+            ActionItem::Naki(naki) => *naki = deaka_naki(naki),
+            ActionItem::Tsumogiri(_) => (),
+        };
+
+        for kyoku in &mut self.kyokus {
+            for pai in kyoku
+                .dora_indicators
+                .iter_mut()
+                .chain(&mut kyoku.ura_indicators)
+            {
+                *pai = pai.deaka();
+            }
+            for table in &mut kyoku.action_tables {
+                for pai in &mut table.haipai {
+                    *pai = pai.deaka();
+                }
+                table.takes.iter_mut().for_each(deaka_item);
+                table.discards.iter_mut().for_each(deaka_item);
+            }
+        }
+        self.has_aka = false;
+    }
+
     #[inline]
     pub fn filter_kyokus(&mut self, kyoku_filter: &KyokuFilter) {
         self.kyokus
             .retain(|l| kyoku_filter.test(l.meta.kyoku_num, l.meta.honba))
     }
+
+    /// Overrides the game length told by the rule of the log, for a log whose
+    /// rule is missing or wrong.
+    ///
+    /// Pushes [`Warning::GameLengthMismatch`] if the kyokus do not fit the
+    /// forced length, that is, a tonpuu reaching the west round, or a hanchan
+    /// not leaving the east round without ending early by tobi.
+    pub fn force_game_length(&mut self, game_length: GameLength) {
+        self.game_length = game_length;
+
+        let last_kyoku = match self.kyokus.iter().map(|k| k.meta.kyoku_num).max() {
+            Some(k) => k,
+            None => return,
+        };
+        let fits = match game_length {
+            GameLength::Tonpuu => last_kyoku < 8,
+            GameLength::Hanchan => last_kyoku >= 4 || self.is_ended_by_tobi(),
+        };
+        if !fits {
+            self.warnings.push(Warning::GameLengthMismatch {
+                forced: game_length,
+                last_kyoku,
+            });
+        }
+    }
+
+    /// Returns true if the game ended early because some player's score went
+    /// below zero (tobi) in the last kyoku of the log.
+    ///
+    /// The log does not say how many kyokus the game was meant to have, so
+    /// the last kyoku is simply taken as the end of the game. This is not
+    /// meaningful on a filtered log.
+    pub fn is_ended_by_tobi(&self) -> bool {
+        self.kyokus
+            .last()
+            .map(|k| k.leaving_scores().iter().any(|&s| s < 0))
+            .unwrap_or(false)
+    }
+
+    /// Returns the final placement of each seat, from 1 to 4, by the scores
+    /// leaving the last kyoku of the log. In sanma they are from 1 to 3, and
+    /// the empty seat 3 gets 0.
+    ///
+    /// Ties are broken by seat order, where seat 0 is the starting dealer.
+    pub fn placements(&self) -> [u8; 4] {
+        let scores = self
+            .kyokus
+            .last()
+            .map(|k| k.leaving_scores())
+            .unwrap_or_default();
+        rank_scores(&scores, self.game_kind)
+    }
+
+    /// Returns the [`PlacementTarget`] of each player entering the last kyoku
+    /// of the log, in seat order, or None unless that kyoku is the oorasu,
+    /// i.e. the last kyoku of `game_length` or an extension after it.
+    ///
+    /// Placements and ties are as in [`Log::placements`], so the log should
+    /// not be filtered.
+    pub fn oorasu_targets(&self) -> Option<Vec<PlacementTarget>> {
+        let kyoku = self.kyokus.last()?;
+        let num_players = self.game_kind.num_players();
+        let winds = match self.game_length {
+            GameLength::Tonpuu => 1,
+            GameLength::Hanchan => 2,
+        };
+        // tenhou.net/6 counts 4 kyokus per wind in sanma as well
+        let oorasu = winds * 4 - 1 - (4 - num_players as u8);
+        if kyoku.meta.kyoku_num < oorasu {
+            return None;
+        }
+
+        let scores = kyoku.scoreboard;
+        let placements = rank_scores(&scores, self.game_kind);
+        let seat_at =
+            |placement: u8| (0..num_players as u8).find(|&s| placements[s as usize] == placement);
+        let honba = kyoku.meta.honba as i32 * 300;
+        let kyotaku = kyoku.meta.kyotaku as i32 * 1000;
+
+        let targets = (0..num_players as u8)
+            .map(|seat| {
+                let placement = placements[seat as usize];
+                let above = placement.checked_sub(1).and_then(seat_at);
+                let below = seat_at(placement + 1);
+                let gap = above.map(|a| scores[a as usize] - scores[seat as usize]);
+                // the earlier seat wins a tie
+                let wins_tie = above.is_some_and(|a| seat < a);
+
+                PlacementTarget {
+                    seat,
+                    placement,
+                    behind: gap,
+                    ahead: below.map(|b| scores[seat as usize] - scores[b as usize]),
+                    direct_hit: gap.map(|g| min_ron_value(g - 2 * honba - kyotaku, 2, wins_tie)),
+                    ron: gap.map(|g| min_ron_value(g - honba - kyotaku, 1, wins_tie)),
+                }
+            })
+            .collect();
+        Some(targets)
+    }
+
+    /// Returns how each kyoku moved `seat` in placement, valued by `pt`, the
+    /// placement points from 1st to 4th, in the order of the kyokus.
+    ///
+    /// The placements entering and leaving a kyoku are those the game would
+    /// end with right there, ranked as in [`Log::placements`]. Returns an
+    /// empty Vec if `seat` has no player, such as seat 3 in sanma.
+    pub fn placement_swings(&self, seat: u8, pt: [i32; 4]) -> Vec<PlacementSwing> {
+        if seat as usize >= self.game_kind.num_players() {
+            return vec![];
+        }
+
+        self.kyokus
+            .iter()
+            .map(|kyoku| {
+                let leaving_scores = kyoku.leaving_scores();
+                let before = rank_scores(&kyoku.scoreboard, self.game_kind)[seat as usize];
+                let after = rank_scores(&leaving_scores, self.game_kind)[seat as usize];
+                PlacementSwing {
+                    kyoku: kyoku.meta.kyoku_num,
+                    honba: kyoku.meta.honba,
+                    score_delta: leaving_scores[seat as usize] - kyoku.scoreboard[seat as usize],
+                    before,
+                    after,
+                    pt_delta: pt[after as usize - 1] - pt[before as usize - 1],
+                }
+            })
+            .collect()
+    }
+
+    /// Returns at most `n` kyokus that moved the placement of `seat` the
+    /// most, by the absolute values of the pt deltas and then of the score
+    /// deltas. Kyokus that changed neither are left out.
+    pub fn most_impactful_kyokus(&self, seat: u8, pt: [i32; 4], n: usize) -> Vec<PlacementSwing> {
+        let mut swings = self.placement_swings(seat, pt);
+        swings.retain(|s| s.pt_delta != 0 || s.score_delta != 0);
+        // stable, so ties keep the kyoku order
+        swings.sort_by_key(|s| std::cmp::Reverse((s.pt_delta.abs(), s.score_delta.abs())));
+        swings.truncate(n);
+        swings
+    }
+
+    /// Returns whether `hero_seat` is the dealer of each kyoku, where seat 0
+    /// is the starting dealer.
+    pub fn hero_is_dealer(&self, hero_seat: u8) -> Vec<bool> {
+        self.dealer_sequence()
+            .into_iter()
+            .map(|oya| oya == hero_seat)
+            .collect()
+    }
+
+    /// Returns the seat of the dealer of each kyoku in order, which repeats
+    /// over a renchan.
+    pub fn dealer_sequence(&self) -> Vec<u8> {
+        self.kyokus.iter().map(Kyoku::oya).collect()
+    }
+
+    /// Returns the riichi outcomes of `seat` over the kyokus of the log.
+    ///
+    /// A riichi whose declaring discard is dealt in is left out, as no stick
+    /// is paid for it, see [`Kyoku::paid_riichi`].
+    pub fn riichi_roi(&self, seat: u8) -> RiichiRoi {
+        let mut roi = RiichiRoi::default();
+
+        for kyoku in &self.kyokus {
+            if !kyoku.paid_riichi(seat) {
+                continue;
+            }
+
+            roi.riichi_count += 1;
+            roi.net += kyoku.leaving_scores()[seat as usize] - kyoku.scoreboard[seat as usize];
+            if kyoku.winners().contains(&seat) {
+                roi.won += 1;
+            } else if kyoku.deal_in_seat() == Some(seat) {
+                roi.dealt_in += 1;
+            } else {
+                roi.other += 1;
+            }
+        }
+
+        roi
+    }
+
+    /// Sums the ura dora of the riichi wins of `seat` over the log, see
+    /// [`Kyoku::ura_dora_count`].
+    pub fn ura_dora_stats(&self, seat: u8) -> std::result::Result<UraDoraStats, ConvertError> {
+        let mut stats = UraDoraStats::default();
+
+        for kyoku in &self.kyokus {
+            if let Some(ura) = kyoku.ura_dora_count(seat)? {
+                stats.riichi_wins += 1;
+                stats.total_ura += ura as u32;
+                if ura > 0 {
+                    stats.hits += 1;
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Returns the starting score of each player, derived from the points on
+    /// the table of the first kyoku.
+    ///
+    /// Returns None if there is no kyoku, or the points can not be evenly
+    /// split among the players.
+    pub fn starting_score(&self) -> Option<i32> {
+        let total = self.kyokus.first()?.points_on_table();
+        let num_players = self.game_kind.num_players() as i32;
+        if total % num_players == 0 {
+            Some(total / num_players)
+        } else {
+            None
+        }
+    }
+
+    /// Checks that the points on the table of every kyoku add up to
+    /// `starting_score` times the number of players.
+    pub fn verify_score_sum(&self, starting_score: i32) -> std::result::Result<(), ScoreSumError> {
+        let expected = starting_score * self.game_kind.num_players() as i32;
+        for (index, kyoku) in self.kyokus.iter().enumerate() {
+            let actual = kyoku.points_on_table();
+            if actual != expected {
+                return Err(ScoreSumError {
+                    index,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the kyotaku leaving each kyoku equals the kyotaku entering
+    /// the next one.
+    ///
+    /// This only makes sense on a complete log, not on a filtered one.
+    pub fn verify_kyotaku_flow(&self) -> std::result::Result<(), KyotakuFlowError> {
+        for (i, pair) in self.kyokus.windows(2).enumerate() {
+            let expected = pair[0].leaving_kyotaku();
+            let actual = pair[1].meta.kyotaku;
+            if expected != actual {
+                return Err(KyotakuFlowError {
+                    index: i + 1,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returned by [`Log::verify_kyotaku_flow`].
+#[derive(Debug, Error)]
+#[error(
+    "kyotaku of kyoku #{index} is expected to be {expected} from the previous kyoku, got {actual}"
+)]
+pub struct KyotakuFlowError {
+    /// Index of the kyoku in `Log::kyokus` whose kyotaku does not match.
+    pub index: usize,
+    pub expected: u8,
+    pub actual: u8,
+}
+
+/// Returned by [`Log::placement_swings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlacementSwing {
+    /// In tenhou.net/6 format, counts from 0.
+    pub kyoku: u8,
+    pub honba: u8,
+    pub score_delta: i32,
+    /// Placement entering the kyoku, from 1.
+    pub before: u8,
+    /// Placement leaving the kyoku, from 1.
+    pub after: u8,
+    pub pt_delta: i32,
+}
+
+/// What a player needs in the oorasu to rise one place, returned by
+/// [`Log::oorasu_targets`].
+///
+/// The ron values are the points of the hand before honba, rounded up to
+/// 100, counting the kyotaku on the table entering the kyoku and 300 per
+/// honba. 0 means any win is enough. Tsumo is not covered, as its payments
+/// depend on who the dealer is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PlacementTarget {
+    pub seat: u8,
+    /// Placement entering the kyoku, from 1.
+    pub placement: u8,
+    /// Points behind the player one place above, None for the top.
+    pub behind: Option<i32>,
+    /// Points ahead of the player one place below, None for the last.
+    pub ahead: Option<i32>,
+    /// The smallest ron off the player one place above that passes them.
+    pub direct_hit: Option<i32>,
+    /// The smallest ron off anyone else that passes the player one place
+    /// above.
+    pub ron: Option<i32>,
+}
+
+/// Returned by [`Log::riichi_roi`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RiichiRoi {
+    /// Number of kyokus with a riichi declared and paid for, each of which
+    /// costs a 1000 points stick.
+    pub riichi_count: u32,
+    /// Riichi that won the kyoku.
+    pub won: u32,
+    /// Riichi that dealt in.
+    pub dealt_in: u32,
+    /// Riichi that ended in ryukyoku or someone else's tsumo.
+    pub other: u32,
+    /// Sum of the score changes in those kyokus, including the sticks.
+    pub net: i32,
+}
+
+impl RiichiRoi {
+    #[inline]
+    pub fn invested(&self) -> i32 {
+        self.riichi_count as i32 * 1000
+    }
+
+    /// Returns the net points per invested point, or None without riichi.
+    #[inline]
+    pub fn roi(&self) -> Option<f64> {
+        if self.riichi_count == 0 {
+            None
+        } else {
+            Some(self.net as f64 / self.invested() as f64)
+        }
+    }
+}
+
+/// Returned by [`Log::ura_dora_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UraDoraStats {
+    pub riichi_wins: u32,
+    /// Riichi wins with at least one ura dora.
+    pub hits: u32,
+    pub total_ura: u32,
+}
+
+impl UraDoraStats {
+    /// Returns the ratio of riichi wins with ura dora, or None without any
+    /// riichi win.
+    #[inline]
+    pub fn hit_rate(&self) -> Option<f64> {
+        if self.riichi_wins == 0 {
+            None
+        } else {
+            Some(self.hits as f64 / self.riichi_wins as f64)
+        }
+    }
+
+    /// Returns the ura dora per riichi win, or None without any riichi win.
+    #[inline]
+    pub fn average(&self) -> Option<f64> {
+        if self.riichi_wins == 0 {
+            None
+        } else {
+            Some(self.total_ura as f64 / self.riichi_wins as f64)
+        }
+    }
+}
+
+/// Returned by [`Kyoku::check_tile_conservation`].
+#[derive(Debug, Error)]
+#[error("{pai} shows up {count} times in the kyoku")]
+pub struct TileConflict {
+    pub pai: Pai,
+    pub count: u8,
+}
+
+/// Identifies the game of a log, to link back to it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameInfo {
+    /// The log id, such as "2019050417gm-0029-0000-4f2a8622" for tenhou, or
+    /// the id of another platform.
+    pub log_id: Option<String>,
+}
+
+impl GameInfo {
+    /// Returns the URL to view the game on tenhou.net from `seat`, or None if
+    /// there is no log id or it is not a tenhou one.
+    pub fn viewer_url(&self, seat: u8) -> Option<String> {
+        let log_id = self.log_id.as_deref()?;
+        let is_tenhou_id = log_id.contains("gm-")
+            && log_id
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-');
+        is_tenhou_id.then(|| format!("https://tenhou.net/0/?log={}&tw={}", log_id, seat))
+    }
+}
+
+/// Returned by [`Kyoku::verify_dora_count`].
+#[derive(Debug, Error)]
+#[error("{actual} dora indicators do not match {kans} kans in the kyoku")]
+pub struct DoraCountError {
+    pub kans: usize,
+    pub actual: usize,
+}
+
+/// Returned by [`Kyoku::validate_actions`].
+#[derive(Debug, Error)]
+pub enum ActionError {
+    #[error("kyoku cannot be replayed: {0}")]
+    Convert(#[from] ConvertError),
+
+    /// `turn` is the index of the take in "取" of `seat` that the action is
+    /// or follows.
+    #[error("impossible action by seat {seat} at turn {turn}: {kind}")]
+    Impossible {
+        seat: u8,
+        turn: usize,
+        kind: ImpossibleAction,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ImpossibleAction {
+    #[error("{0} is not in hand")]
+    NotInHand(Pai),
+
+    #[error("{0} is not the last discard of the called player")]
+    NotDiscarded(Pai),
+
+    #[error("drawing after the wall is exhausted")]
+    WallExhausted,
+}
+
+/// Returned by [`Log::from_json_lines`].
+pub struct JsonLines<R> {
+    lines: io::Lines<R>,
+    line_num: usize,
+}
+
+impl<R: BufRead> Iterator for JsonLines<R> {
+    type Item = std::result::Result<Log, LineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = self.lines.next()?;
+            self.line_num += 1;
+            let line_num = self.line_num;
+
+            let ret = match line {
+                Ok(line) if line.trim().is_empty() => continue,
+                Ok(line) => Log::from_json_str(&line).map_err(|source| LineError::Parse {
+                    line: line_num,
+                    source,
+                }),
+                Err(source) => Err(LineError::Io {
+                    line: line_num,
+                    source,
+                }),
+            };
+            return Some(ret);
+        }
+    }
+}
+
+/// Yielded by [`JsonLines`]. `line` counts from 1.
+#[derive(Debug, Error)]
+pub enum LineError {
+    #[error("failed to read line {line}: {source}")]
+    Io { line: usize, source: io::Error },
+
+    #[error("failed to parse tenhou log at line {line}: {source}")]
+    Parse { line: usize, source: json::Error },
+}
+
+/// Returned by [`RawLog::rename_player`].
+#[derive(Debug, Error)]
+#[error("seat {seat} is out of range for {players} players")]
+pub struct SeatOutOfRange {
+    pub seat: u8,
+    pub players: usize,
+}
+
+/// Returned by [`Log::verify_score_sum`].
+#[derive(Debug, Error)]
+#[error("points on the table of kyoku #{index} sum up to {actual}, expected {expected}")]
+pub struct ScoreSumError {
+    /// Index of the kyoku in `Log::kyokus` whose points do not add up.
+    pub index: usize,
+    pub expected: i32,
+    pub actual: i32,
+}
+
+/// Returns the smallest hand value, as a multiple of 100, such that
+/// `share` times it is more than `needed`, or at least `needed` with
+/// `wins_tie`.
+fn min_ron_value(needed: i32, share: i32, wins_tie: bool) -> i32 {
+    if needed < 0 || (needed == 0 && wins_tie) {
+        return 0;
+    }
+    let value = if wins_tie {
+        (needed + share - 1) / share
+    } else {
+        needed / share + 1
+    };
+    (value + 99) / 100 * 100
+}
+
+/// Rewrites discards like "o22", marked by a letter that is not used in
+/// discards, into the riichi "r22", and returns those letters.
+///
+/// tenhou.net/6 has no such notation, but other tools may mark variants of
+/// riichi like that.
+fn read_unknown_riichi(discards: &mut [ActionItem]) -> Vec<char> {
+    let mut markers = vec![];
+    for item in discards {
+        if let ActionItem::Naki(naki) = item {
+            let bytes = naki.as_bytes();
+            let is_unknown = bytes.len() == 3
+                && bytes[0].is_ascii_alphabetic()
+                && !matches!(bytes[0], b'r' | b'f' | b'k' | b'a')
+                && bytes[1..].iter().all(u8::is_ascii_digit);
+            if is_unknown {
+                markers.push(bytes[0] as char);
+                naki.replace_range(..1, "r");
+            }
+        }
+    }
+    markers
+}
+
+/// Ranks the seats from 1 by `scores`, breaking ties by seat order. The
+/// empty seat 3 of sanma gets 0.
+fn rank_scores(scores: &[i32; 4], game_kind: GameKind) -> [u8; 4] {
+    let mut seats = [0, 1, 2, 3];
+    let seats = &mut seats[..game_kind.num_players()];
+    seats.sort_by_key(|&seat| std::cmp::Reverse(scores[seat]));
+
+    let mut placements = [0; 4];
+    for (rank, &seat) in seats.iter().enumerate() {
+        placements[seat] = rank as u8 + 1;
+    }
+    placements
+}
+
+fn take_from_hand(tehai: &mut Vec<Pai>, pais: &[Pai]) -> std::result::Result<(), ImpossibleAction> {
+    for &pai in pais {
+        let idx = tehai
+            .iter()
+            .position(|&p| p == pai)
+            .ok_or(ImpossibleAction::NotInHand(pai))?;
+        tehai.remove(idx);
+    }
+    Ok(())
+}
+
+/// Replaces the aka in a naki string like "c521416" with normal 5s, keeping
+/// the letters and the other pais in place.
+fn deaka_naki(naki: &str) -> String {
+    let mut ret = String::with_capacity(naki.len());
+    let mut rest = naki;
+    while !rest.is_empty() {
+        if rest.as_bytes()[0].is_ascii_alphabetic() {
+            ret.push_str(&rest[..1]);
+            rest = &rest[1..];
+            continue;
+        }
+
+        let len = rest.len().min(2);
+        let (code, tail) = rest.split_at(len);
+        match code
+            .parse::<u8>()
+            .ok()
+            .and_then(|id| Pai::try_from(id).ok())
+        {
+            Some(pai) if pai.is_aka() => ret.push_str(&pai.deaka().as_u8().to_string()),
+            _ => ret.push_str(code),
+        }
+        rest = tail;
+    }
+    ret
+}
+
+fn hide_names(names: &mut [String; 4]) {
+    names.iter_mut().zip('A'..='D').for_each(|(name, alias)| {
+        name.clear();
+        name.push(alias);
+        name.push_str("さん");
+    });
 }
 
 impl From<RawLog> for Log {
     fn from(raw_log: RawLog) -> Self {
+        let game_kind = raw_log.game_kind();
         let RawLog {
             logs, names, rule, ..
         } = raw_log;
 
-        let game_length = if rule.disp.contains('東') {
-            GameLength::Tonpuu
+        let mut warnings = vec![];
+        let (game_length, has_aka) = if let Some(rule) = rule {
+            let game_length = if rule.disp.contains('東') {
+                GameLength::Tonpuu
+            } else {
+                GameLength::Hanchan
+            };
+            let has_aka = rule.aka + rule.aka51 + rule.aka52 + rule.aka53 > 0;
+            (game_length, has_aka)
         } else {
-            GameLength::Hanchan
+            warnings.push(Warning::MissingRule);
+            let has_aka = logs.iter().any(json_scheme::Kyoku::has_aka_pai);
+            (GameLength::Hanchan, has_aka)
         };
-        let has_aka = rule.aka + rule.aka51 + rule.aka52 + rule.aka53 > 0;
 
         let kyokus = logs
             .into_iter()
@@ -284,11 +2105,24 @@ impl From<RawLog> for Log {
                         },
                     ],
                     end_status: kyoku::EndStatus::Ryukyoku {
+                        reason: kyoku::RyukyokuReason::Unknown,
                         score_deltas: [0; 4], // default
+                        tenpai_seats: [false; 4],
                     },
                 };
 
-                if let Some(json_scheme::ResultItem::Status(status_text)) = log.results.get(0) {
+                for (seat, table) in (0..4).zip(&mut item.action_tables) {
+                    for marker in read_unknown_riichi(&mut table.discards) {
+                        warnings.push(Warning::UnknownRiichiNotation {
+                            kyoku: item.meta.kyoku_num,
+                            honba: item.meta.honba,
+                            seat,
+                            marker,
+                        });
+                    }
+                }
+
+                if let Some(json_scheme::ResultItem::Status(status_text)) = log.results.first() {
                     if status_text == "和了" {
                         let hora_details = log.results[1..]
                             .chunks_exact(2)
@@ -299,10 +2133,19 @@ impl From<RawLog> for Log {
                                 ) = (&detail_tuple[0], &detail_tuple[1])
                                 {
                                     // TODO: it can actually fail, maybe impl TryFrom instead
+                                    let who = who_target_tuple[0].as_u64().unwrap_or(0) as u8;
+                                    let target = who_target_tuple[1].as_u64().unwrap_or(0) as u8;
+                                    // the third one is the liable seat, or who if none
+                                    let pao = who_target_tuple
+                                        .get(2)
+                                        .and_then(|v| v.as_u64())
+                                        .map(|p| p as u8)
+                                        .filter(|&p| p != who && p != target);
                                     let hora_detail = kyoku::HoraDetail {
                                         score_deltas: *score_deltas,
-                                        who: who_target_tuple[0].as_u64().unwrap_or(0) as u8,
-                                        target: who_target_tuple[1].as_u64().unwrap_or(0) as u8,
+                                        who,
+                                        target,
+                                        pao,
                                     };
                                     Some(hora_detail)
                                 } else {
@@ -323,7 +2166,11 @@ impl From<RawLog> for Log {
                             [0; 4]
                         };
 
-                        item.end_status = kyoku::EndStatus::Ryukyoku { score_deltas };
+                        item.end_status = kyoku::EndStatus::Ryukyoku {
+                            reason: kyoku::RyukyokuReason::from_status(status_text),
+                            score_deltas,
+                            tenpai_seats: kyoku::tenpai_seats_from(status_text, &score_deltas),
+                        };
                     }
                 }
 
@@ -334,8 +2181,10 @@ impl From<RawLog> for Log {
         Log {
             names,
             game_length,
+            game_kind,
             has_aka,
             kyokus,
+            warnings,
         }
     }
 }
@@ -0,0 +1,404 @@
+//! Judgement of yaku that depend on the flow of a kyoku rather than the
+//! shape of the hand, over mjai events, counting of dora and pao, and names
+//! of yaku.
+
+use crate::mjai::Event;
+use crate::Pai;
+
+/// Returns true if the hora at `events[hora_index]` is an ippatsu, where
+/// `events` are the events of a kyoku.
+///
+/// The ippatsu window opens with the riichi declaring discard of the winner
+/// and closes with the winner's next discard. Any chi, pon or kan by any
+/// player in between breaks it, except a kakan robbed by the hora itself,
+/// since the kan is never completed. The kakan may come before the other
+/// horas of a multiple ron.
+pub fn is_ippatsu(events: &[Event], hora_index: usize) -> bool {
+    let winner = match events.get(hora_index) {
+        Some(&Event::Hora { actor, .. }) => actor,
+        _ => return false,
+    };
+    let last_before_horas = events[..hora_index]
+        .iter()
+        .rposition(|ev| !matches!(ev, Event::Hora { .. }));
+
+    let mut is_declaring = false;
+    let mut in_window = false;
+    for (i, event) in events[..hora_index].iter().enumerate() {
+        match *event {
+            Event::StartKyoku { .. } => {
+                is_declaring = false;
+                in_window = false;
+            }
+
+            Event::Reach { actor } if actor == winner => is_declaring = true,
+
+            Event::Dahai { actor, .. } if actor == winner => {
+                in_window = is_declaring;
+                is_declaring = false;
+            }
+
+            Event::Kakan { .. } if Some(i) == last_before_horas => (), // chankan
+
+            Event::Chi { .. }
+            | Event::Pon { .. }
+            | Event::Daiminkan { .. }
+            | Event::Kakan { .. }
+            | Event::Ankan { .. } => in_window = false,
+
+            _ => (),
+        }
+    }
+
+    in_window
+}
+
+/// Returns the seat liable for the hora at `events[hora_index]` under pao
+/// (sekinin barai), where `events` are the events of a kyoku.
+///
+/// The player who fed the pon or daiminkan completing the winner's third
+/// dragon set for daisangen, or fourth wind set for daisuushi, is liable.
+/// Ankans count as sets but hold nobody liable. Returns `None` if the
+/// winner's calls do not guarantee either yakuman.
+pub fn pao_seat(events: &[Event], hora_index: usize) -> Option<u8> {
+    let winner = match events.get(hora_index) {
+        Some(&Event::Hora { actor, .. }) => actor,
+        _ => return None,
+    };
+
+    let mut dragons = 0;
+    let mut winds = 0;
+    let mut liable = None;
+    for event in &events[..hora_index] {
+        let (pai, feeder) = match *event {
+            Event::StartKyoku { .. } => {
+                dragons = 0;
+                winds = 0;
+                liable = None;
+                continue;
+            }
+
+            Event::Pon {
+                actor, target, pai, ..
+            }
+            | Event::Daiminkan {
+                actor, target, pai, ..
+            } if actor == winner => (pai, Some(target)),
+
+            Event::Ankan { actor, consumed } if actor == winner => (consumed.as_array()[0], None),
+
+            _ => continue,
+        };
+
+        if is_dragon(pai) {
+            dragons += 1;
+            if dragons == 3 {
+                liable = feeder;
+            }
+        } else if is_wind(pai) {
+            winds += 1;
+            if winds == 4 {
+                liable = feeder;
+            }
+        }
+    }
+
+    liable
+}
+
+#[inline]
+fn is_dragon(pai: Pai) -> bool {
+    matches!(pai, Pai::Haku | Pai::Hatsu | Pai::Chun)
+}
+
+#[inline]
+fn is_wind(pai: Pai) -> bool {
+    matches!(pai, Pai::East | Pai::South | Pai::West | Pai::North)
+}
+
+/// Returns the han from dora, ura dora and aka in `pais`, which are all the
+/// pais of a winning hand including fuuros.
+///
+/// Aka only count as han when `has_aka` is true, which should come from
+/// [`Log::has_aka`](crate::tenhou::Log::has_aka). Otherwise they count just
+/// like normal 5s.
+pub fn dora_han(pais: &[Pai], dora_indicators: &[Pai], has_aka: bool) -> u8 {
+    let dora = pais
+        .iter()
+        .map(|pai| {
+            dora_indicators
+                .iter()
+                .filter(|indicator| indicator.next_dora() == pai.deaka())
+                .count()
+        })
+        .sum::<usize>();
+    let aka = if has_aka {
+        pais.iter().filter(|pai| pai.is_aka()).count()
+    } else {
+        0
+    };
+
+    (dora + aka) as u8
+}
+
+/// The language of names of [`Yaku`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    Japanese,
+    English,
+}
+
+/// Yaku in the order tenhou lists them, with dora at the end.
+///
+/// Yakuhai are split by pai, just like tenhou does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Yaku {
+    MenzenTsumo,
+    Riichi,
+    Ippatsu,
+    Chankan,
+    RinshanKaihou,
+    HaiteiRaoyue,
+    HouteiRaoyui,
+    Pinfu,
+    Tanyao,
+    Iipeikou,
+    SeatWindEast,
+    SeatWindSouth,
+    SeatWindWest,
+    SeatWindNorth,
+    RoundWindEast,
+    RoundWindSouth,
+    RoundWindWest,
+    RoundWindNorth,
+    Haku,
+    Hatsu,
+    Chun,
+    DoubleRiichi,
+    Chiitoitsu,
+    Chanta,
+    Ittsu,
+    SanshokuDoujun,
+    SanshokuDoukou,
+    Sankantsu,
+    Toitoi,
+    Sanankou,
+    Shousangen,
+    Honroutou,
+    Ryanpeikou,
+    Junchan,
+    Honitsu,
+    Chinitsu,
+    Tenhou,
+    Chiihou,
+    Daisangen,
+    Suuankou,
+    SuuankouTanki,
+    Tsuuiisou,
+    Ryuuiisou,
+    Chinroutou,
+    ChuurenPoutou,
+    JunseiChuurenPoutou,
+    KokushiMusou,
+    KokushiMusou13,
+    Daisuushii,
+    Shousuushii,
+    Suukantsu,
+    Dora,
+    UraDora,
+    AkaDora,
+}
+
+impl Yaku {
+    const ALL: &'static [Self] = &[
+        Self::MenzenTsumo,
+        Self::Riichi,
+        Self::Ippatsu,
+        Self::Chankan,
+        Self::RinshanKaihou,
+        Self::HaiteiRaoyue,
+        Self::HouteiRaoyui,
+        Self::Pinfu,
+        Self::Tanyao,
+        Self::Iipeikou,
+        Self::SeatWindEast,
+        Self::SeatWindSouth,
+        Self::SeatWindWest,
+        Self::SeatWindNorth,
+        Self::RoundWindEast,
+        Self::RoundWindSouth,
+        Self::RoundWindWest,
+        Self::RoundWindNorth,
+        Self::Haku,
+        Self::Hatsu,
+        Self::Chun,
+        Self::DoubleRiichi,
+        Self::Chiitoitsu,
+        Self::Chanta,
+        Self::Ittsu,
+        Self::SanshokuDoujun,
+        Self::SanshokuDoukou,
+        Self::Sankantsu,
+        Self::Toitoi,
+        Self::Sanankou,
+        Self::Shousangen,
+        Self::Honroutou,
+        Self::Ryanpeikou,
+        Self::Junchan,
+        Self::Honitsu,
+        Self::Chinitsu,
+        Self::Tenhou,
+        Self::Chiihou,
+        Self::Daisangen,
+        Self::Suuankou,
+        Self::SuuankouTanki,
+        Self::Tsuuiisou,
+        Self::Ryuuiisou,
+        Self::Chinroutou,
+        Self::ChuurenPoutou,
+        Self::JunseiChuurenPoutou,
+        Self::KokushiMusou,
+        Self::KokushiMusou13,
+        Self::Daisuushii,
+        Self::Shousuushii,
+        Self::Suukantsu,
+        Self::Dora,
+        Self::UraDora,
+        Self::AkaDora,
+    ];
+
+    /// Returns the name of the yaku, where the Japanese one is the same as
+    /// tenhou's.
+    pub const fn name(self, locale: Locale) -> &'static str {
+        let (ja, en) = self.names();
+        match locale {
+            Locale::Japanese => ja,
+            Locale::English => en,
+        }
+    }
+
+    /// Parses a name in either locale. Returns None if it is unknown.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|yaku| {
+            let (ja, en) = yaku.names();
+            name == ja || name == en
+        })
+    }
+
+    #[inline]
+    pub const fn is_yakuman(self) -> bool {
+        matches!(
+            self,
+            Self::Tenhou
+                | Self::Chiihou
+                | Self::Daisangen
+                | Self::Suuankou
+                | Self::SuuankouTanki
+                | Self::Tsuuiisou
+                | Self::Ryuuiisou
+                | Self::Chinroutou
+                | Self::ChuurenPoutou
+                | Self::JunseiChuurenPoutou
+                | Self::KokushiMusou
+                | Self::KokushiMusou13
+                | Self::Daisuushii
+                | Self::Shousuushii
+                | Self::Suukantsu
+        )
+    }
+
+    #[inline]
+    pub const fn is_dora(self) -> bool {
+        matches!(self, Self::Dora | Self::UraDora | Self::AkaDora)
+    }
+
+    const fn names(self) -> (&'static str, &'static str) {
+        match self {
+            Self::MenzenTsumo => ("門前清自摸和", "Menzen Tsumo"),
+            Self::Riichi => ("立直", "Riichi"),
+            Self::Ippatsu => ("一発", "Ippatsu"),
+            Self::Chankan => ("槍槓", "Chankan"),
+            Self::RinshanKaihou => ("嶺上開花", "Rinshan Kaihou"),
+            Self::HaiteiRaoyue => ("海底摸月", "Haitei Raoyue"),
+            Self::HouteiRaoyui => ("河底撈魚", "Houtei Raoyui"),
+            Self::Pinfu => ("平和", "Pinfu"),
+            Self::Tanyao => ("断幺九", "Tanyao"),
+            Self::Iipeikou => ("一盃口", "Iipeikou"),
+            Self::SeatWindEast => ("自風 東", "Seat Wind East"),
+            Self::SeatWindSouth => ("自風 南", "Seat Wind South"),
+            Self::SeatWindWest => ("自風 西", "Seat Wind West"),
+            Self::SeatWindNorth => ("自風 北", "Seat Wind North"),
+            Self::RoundWindEast => ("場風 東", "Round Wind East"),
+            Self::RoundWindSouth => ("場風 南", "Round Wind South"),
+            Self::RoundWindWest => ("場風 西", "Round Wind West"),
+            Self::RoundWindNorth => ("場風 北", "Round Wind North"),
+            Self::Haku => ("役牌 白", "Haku"),
+            Self::Hatsu => ("役牌 發", "Hatsu"),
+            Self::Chun => ("役牌 中", "Chun"),
+            Self::DoubleRiichi => ("両立直", "Double Riichi"),
+            Self::Chiitoitsu => ("七対子", "Chiitoitsu"),
+            Self::Chanta => ("混全帯幺九", "Chanta"),
+            Self::Ittsu => ("一気通貫", "Ittsu"),
+            Self::SanshokuDoujun => ("三色同順", "Sanshoku Doujun"),
+            Self::SanshokuDoukou => ("三色同刻", "Sanshoku Doukou"),
+            Self::Sankantsu => ("三槓子", "Sankantsu"),
+            Self::Toitoi => ("対々和", "Toitoi"),
+            Self::Sanankou => ("三暗刻", "Sanankou"),
+            Self::Shousangen => ("小三元", "Shousangen"),
+            Self::Honroutou => ("混老頭", "Honroutou"),
+            Self::Ryanpeikou => ("二盃口", "Ryanpeikou"),
+            Self::Junchan => ("純全帯幺九", "Junchan"),
+            Self::Honitsu => ("混一色", "Honitsu"),
+            Self::Chinitsu => ("清一色", "Chinitsu"),
+            Self::Tenhou => ("天和", "Tenhou"),
+            Self::Chiihou => ("地和", "Chiihou"),
+            Self::Daisangen => ("大三元", "Daisangen"),
+            Self::Suuankou => ("四暗刻", "Suuankou"),
+            Self::SuuankouTanki => ("四暗刻単騎", "Suuankou Tanki"),
+            Self::Tsuuiisou => ("字一色", "Tsuuiisou"),
+            Self::Ryuuiisou => ("緑一色", "Ryuuiisou"),
+            Self::Chinroutou => ("清老頭", "Chinroutou"),
+            Self::ChuurenPoutou => ("九蓮宝燈", "Chuuren Poutou"),
+            Self::JunseiChuurenPoutou => ("純正九蓮宝燈", "Junsei Chuuren Poutou"),
+            Self::KokushiMusou => ("国士無双", "Kokushi Musou"),
+            Self::KokushiMusou13 => ("国士無双１３面", "Kokushi Musou 13-sided"),
+            Self::Daisuushii => ("大四喜", "Daisuushii"),
+            Self::Shousuushii => ("小四喜", "Shousuushii"),
+            Self::Suukantsu => ("四槓子", "Suukantsu"),
+            Self::Dora => ("ドラ", "Dora"),
+            Self::UraDora => ("裏ドラ", "Ura Dora"),
+            Self::AkaDora => ("赤ドラ", "Aka Dora"),
+        }
+    }
+}
+
+/// Formats the yaku of a hand with their han, such as "立直(1飜)" and
+/// "Riichi (1 han)", in the conventional order: yakuman first, then by han
+/// from high to low, with dora at the end.
+///
+/// If there is any yakuman, only yakuman are listed since nothing else
+/// counts. Combined yakuman are listed one by one, where a yakuman of 26 han
+/// or more is a double yakuman.
+pub fn format_yaku(yaku: &[(Yaku, u8)], locale: Locale) -> Vec<String> {
+    let has_yakuman = yaku.iter().any(|(y, _)| y.is_yakuman());
+    let mut yaku: Vec<_> = yaku
+        .iter()
+        .copied()
+        .filter(|(y, _)| !has_yakuman || y.is_yakuman())
+        .collect();
+    yaku.sort_by_key(|&(y, han)| (y.is_dora(), std::cmp::Reverse(han), y));
+
+    yaku.into_iter()
+        .map(|(y, han)| {
+            let name = y.name(locale);
+            match (locale, y.is_yakuman(), han >= 26) {
+                (Locale::Japanese, true, false) => format!("{}(役満)", name),
+                (Locale::Japanese, true, true) => format!("{}(ダブル役満)", name),
+                (Locale::Japanese, false, _) => format!("{}({}飜)", name, han),
+                (Locale::English, true, false) => format!("{} (yakuman)", name),
+                (Locale::English, true, true) => format!("{} (double yakuman)", name),
+                (Locale::English, false, _) => format!("{} ({} han)", name, han),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,187 @@
+mod testdata;
+
+use convlog::mjai::Event;
+use convlog::*;
+use testdata::{TestCase, TESTDATA};
+
+#[test]
+fn test_visible_tiles() {
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data)
+            .unwrap_or_else(|_| panic!("failed to parse tenhou log (case: {})", description));
+        let events = tenhou_to_mjai(&log)
+            .unwrap_or_else(|_| panic!("failed to convert into mjai (case: {})", description));
+
+        let mut board = board::Board::default();
+        for (i, event) in events.iter().enumerate() {
+            board.update(event);
+            if matches!(event, Event::StartGame { .. } | Event::EndGame) {
+                continue;
+            }
+
+            for hero_seat in 0..4 {
+                let visible = board.visible_tiles(hero_seat);
+                assert!(visible.len() <= 136, "case: {}", description);
+
+                let mut counts = [0; 34];
+                for pai in visible {
+                    let idx = pai.as_index34().unwrap_or_else(|| {
+                        panic!("unknown pai at event #{} (case: {})", i, description)
+                    });
+                    counts[idx] += 1;
+                }
+                assert!(
+                    counts.iter().all(|&c| c <= 4),
+                    "more than 4 pais of a kind at event #{} (case: {})",
+                    i,
+                    description,
+                );
+
+                let live = board.live_tiles(hero_seat);
+                assert!(
+                    (0..34).all(|idx| live.count34(idx) == 4 - counts[idx]),
+                    "live tiles do not match the visible ones at event #{} (case: {})",
+                    i,
+                    description,
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_visible_tiles_by_turn() {
+    let log = tenhou::Log::from_json_str(testdata::TESTDATA[0].data).unwrap();
+    let events = tenhou_to_mjai(&log).unwrap();
+    let kyoku_events = &events[1..];
+
+    // haipai and the first dora indicator
+    assert_eq!(board::visible_tiles(kyoku_events, 0, 1).len(), 14);
+    // the oya's tsumo is only visible to the oya
+    assert_eq!(board::visible_tiles(kyoku_events, 0, 2).len(), 15);
+    assert_eq!(board::visible_tiles(kyoku_events, 1, 2).len(), 14);
+}
+
+#[test]
+fn test_kan_dora_timing() {
+    let mut checked = [0; 2];
+
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        let events = tenhou_to_mjai(&log).unwrap();
+        let dora_count = |turn: usize| board::Board::replay(&events[..turn]).dora_indicators.len();
+
+        for (k, event) in events.iter().enumerate() {
+            let (actor, is_ankan) = match *event {
+                Event::Ankan { actor, .. } => (actor, true),
+                Event::Daiminkan { actor, .. } | Event::Kakan { actor, .. } => (actor, false),
+                _ => continue,
+            };
+
+            // the rinshan tsumo, absent if the kakan was robbed by chankan
+            let rinshan = events[k..]
+                .iter()
+                .take_while(|ev| !matches!(ev, Event::Hora { .. }))
+                .position(|ev| matches!(*ev, Event::Tsumo { actor: a, .. } if a == actor));
+            let r = match rinshan {
+                Some(offset) => k + offset + 1,
+                None => continue,
+            };
+            // skip kans declared while the dora of the previous kan is
+            // still pending
+            let start = events[..k]
+                .iter()
+                .rposition(|ev| matches!(ev, Event::StartKyoku { .. }))
+                .unwrap();
+            let kans_before = events[start..k]
+                .iter()
+                .filter(|ev| {
+                    matches!(
+                        ev,
+                        Event::Ankan { .. } | Event::Daiminkan { .. } | Event::Kakan { .. }
+                    )
+                })
+                .count();
+            let before = dora_count(k);
+            if before != kans_before + 1 {
+                continue;
+            }
+
+            if is_ankan {
+                // revealed before the rinshan tsumo
+                assert_eq!(dora_count(r), before + 1, "case: {}", description);
+                checked[0] += 1;
+            } else {
+                // not known while deciding on the rinshan tsumo, and revealed
+                // with the discard that follows
+                assert_eq!(dora_count(r), before, "case: {}", description);
+                if let [Event::Dora { .. }, Event::Dahai { .. }, ..] = events[r..] {
+                    assert_eq!(dora_count(r + 2), before + 1, "case: {}", description);
+                    checked[1] += 1;
+                }
+            }
+        }
+    }
+
+    assert!(checked.iter().all(|&c| c > 0), "checked: {:?}", checked);
+}
+
+#[test]
+fn test_snapshot_visibility() {
+    use convlog::board::Visibility;
+
+    let log = tenhou::Log::from_json_str(testdata::TESTDATA[0].data).unwrap();
+    let events = tenhou_to_mjai(&log).unwrap();
+    let board = board::Board::replay(&events[1..20]);
+
+    let snapshot = board.snapshot(1, Visibility::default());
+    assert_eq!(snapshot.visibility, Visibility::HeroOnly);
+    for seat in 0..4 {
+        let tehai = &snapshot.tehais[seat];
+        assert_eq!(tehai.len(), board.tehais[seat].len());
+        if seat == 1 {
+            assert_eq!(*tehai, board.tehais[seat]);
+        } else {
+            assert!(tehai.iter().all(|&p| p == Pai::Unknown));
+        }
+    }
+    // the public parts are the same either way
+    assert_eq!(snapshot.kawas, board.kawas);
+    assert_eq!(snapshot.fuuros, board.fuuros);
+    assert_eq!(snapshot.dora_indicators, board.dora_indicators);
+
+    let snapshot = board.snapshot(1, Visibility::Full);
+    assert_eq!(snapshot.tehais, board.tehais);
+    assert!(snapshot.tehais.iter().flatten().all(|&p| p != Pai::Unknown));
+}
+
+#[test]
+fn test_replay_frames() {
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        let events = tenhou_to_mjai(&log).unwrap();
+
+        let mut frame_count = 0;
+        for kyoku in &log.kyokus {
+            let frames = kyoku.replay_frames(0).unwrap();
+            assert!(
+                frames
+                    .iter()
+                    .all(|f| f.tehais[1..].iter().flatten().all(|&p| p == Pai::Unknown)),
+                "case: {}",
+                description,
+            );
+            frame_count += frames.len();
+        }
+
+        // everything but StartGame and EndGame
+        assert_eq!(frame_count, events.len() - 2, "case: {}", description);
+    }
+
+    let log = tenhou::Log::from_json_str(testdata::TESTDATA[0].data).unwrap();
+    let frames = log.kyokus[0].replay_frames(0).unwrap();
+    let json = serde_json::to_value(&frames[0]).unwrap();
+    assert_eq!(json["visibility"], "hero_only");
+    assert_eq!(json["tehais"][0].as_array().unwrap().len(), 13);
+    assert_eq!(json["tehais"][1][0], "?");
+}
@@ -0,0 +1,54 @@
+use convlog::decision_id::ParseError;
+use convlog::DecisionId;
+
+use serde_json as json;
+
+#[test]
+fn test_decision_id_round_trip() {
+    let id = DecisionId::new(0, 0, 2, 7);
+    assert_eq!(id.to_string(), "E1-0-s2-t7");
+
+    for kyoku in 0..16 {
+        for &(honba, seat, turn) in &[(0, 0, 0), (3, 1, 12), (10, 3, 25)] {
+            let id = DecisionId::new(kyoku, honba, seat, turn);
+            let s = id.to_string();
+            assert_eq!(s.parse::<DecisionId>().unwrap(), id, "id: {}", s);
+        }
+    }
+    assert_eq!(DecisionId::new(7, 1, 3, 9).to_string(), "S4-1-s3-t9");
+    assert_eq!(
+        "n2-0-s1-t3".parse::<DecisionId>().unwrap(),
+        DecisionId::new(13, 0, 1, 3),
+    );
+
+    // serializes as the string, so it can be a JSON key
+    let s = json::to_string(&id).unwrap();
+    assert_eq!(s, r#""E1-0-s2-t7""#);
+    assert_eq!(json::from_str::<DecisionId>(&s).unwrap(), id);
+}
+
+#[test]
+fn test_decision_id_parse_error() {
+    let cases = [
+        ("E1-0-s2", "wrong part count"),
+        ("E1-0-s2-t7-1", "wrong part count"),
+        ("X1-0-s2-t7", "kyoku"),
+        ("E5-0-s2-t7", "kyoku"),
+        ("E12-0-s2-t7", "kyoku"),
+        ("E1-a-s2-t7", "honba"),
+        ("E1-0-2-t7", "seat"),
+        ("E1-0-s4-t7", "seat"),
+        ("E1-0-s2-7", "turn"),
+    ];
+    for &(s, expected) in &cases {
+        let err = s.parse::<DecisionId>().unwrap_err();
+        let kind = match err {
+            ParseError::WrongPartCount(_) => "wrong part count",
+            ParseError::InvalidKyoku(_) => "kyoku",
+            ParseError::InvalidHonba(_) => "honba",
+            ParseError::InvalidSeat(_) => "seat",
+            ParseError::InvalidTurn(_) => "turn",
+        };
+        assert_eq!(kind, expected, "id: {}", s);
+    }
+}
@@ -0,0 +1,648 @@
+mod testdata;
+
+use convlog::tenhou::kyoku::{EndStatus, RyukyokuReason};
+use convlog::*;
+use testdata::{testdata, TestCase, TESTDATA};
+
+use serde_json as json;
+
+#[test]
+fn test_ryukyoku_reason() {
+    let log = tenhou::Log::from_json_str(testdata("kyushukyuhai")).unwrap();
+    let kyoku = &log.kyokus[0];
+    assert!(matches!(
+        kyoku.end_status,
+        EndStatus::Ryukyoku {
+            reason: RyukyokuReason::KyuushuKyuuhai,
+            ..
+        }
+    ));
+    assert!(kyoku.is_first_turn_abort());
+
+    let log = tenhou::Log::from_json_str(testdata("four_reach")).unwrap();
+    let kyoku = &log.kyokus[0];
+    assert!(matches!(
+        kyoku.end_status,
+        EndStatus::Ryukyoku {
+            reason: RyukyokuReason::SuuchaRiichi,
+            ..
+        }
+    ));
+    assert!(!kyoku.is_first_turn_abort());
+
+    let log = tenhou::Log::from_json_str(testdata("ryukyoku")).unwrap();
+    let kyoku = &log.kyokus[0];
+    assert!(matches!(
+        kyoku.end_status,
+        EndStatus::Ryukyoku {
+            reason: RyukyokuReason::Exhaustive,
+            score_deltas: [1500, 1500, -1500, -1500],
+            tenpai_seats: [true, true, false, false],
+        }
+    ));
+    assert!(!kyoku.is_first_turn_abort());
+}
+
+#[test]
+fn test_skip_first_turn_aborts() {
+    let mut raw_log: tenhou::RawLog = json::from_str(testdata("kyushukyuhai")).unwrap();
+    raw_log.skip_first_turn_aborts();
+    assert!(raw_log.is_empty());
+
+    let mut raw_log: tenhou::RawLog = json::from_str(testdata("ryukyoku")).unwrap();
+    raw_log.skip_first_turn_aborts();
+    assert_eq!(raw_log.len(), 1);
+}
+
+#[test]
+fn test_retain_deal_ins() {
+    // seat 2 dealt in E1 and E2.1, E2 was a tsumo
+    let mut raw_log: tenhou::RawLog = json::from_str(testdata("ranked_game")).unwrap();
+    raw_log.retain_deal_ins(2);
+    let log = tenhou::Log::from(raw_log);
+    let kyokus: Vec<_> = log
+        .kyokus
+        .iter()
+        .map(|k| (k.meta.kyoku_num, k.meta.honba))
+        .collect();
+    assert_eq!(kyokus, [(0, 0), (1, 1)]);
+    assert!(log.kyokus.iter().all(|k| k.deal_in_seat() == Some(2)));
+
+    let mut raw_log: tenhou::RawLog = json::from_str(testdata("ranked_game")).unwrap();
+    raw_log.retain_deal_ins(0);
+    assert!(raw_log.is_empty());
+
+    // a double ron is a single deal-in
+    let mut raw_log: tenhou::RawLog = json::from_str(testdata("double_ron")).unwrap();
+    raw_log.retain_deal_ins(3);
+    assert_eq!(raw_log.len(), 1);
+}
+
+#[test]
+fn test_retain_tenpai() {
+    // seat 0 never got to tenpai in E2, where seat 1 won by tsumo
+    let raw: tenhou::RawLog = json::from_str(testdata("ranked_game")).unwrap();
+    let log = tenhou::Log::from(raw.clone());
+    assert!(!log.kyokus[1].reached_tenpai(0));
+    assert!(log.kyokus[1].reached_tenpai(1));
+
+    let mut raw_log = raw.clone();
+    raw_log.retain_tenpai(0);
+    let log = tenhou::Log::from(raw_log);
+    let kyokus: Vec<_> = log
+        .kyokus
+        .iter()
+        .map(|k| (k.meta.kyoku_num, k.meta.honba))
+        .collect();
+    assert_eq!(kyokus, [(0, 0), (1, 1)]);
+
+    // seat 2 was only tenpai in E1, where it dealt in
+    let mut raw_log = raw;
+    raw_log.retain_tenpai(2);
+    assert_eq!(raw_log.len(), 1);
+
+    // both winners of a double ron count as tenpai
+    let log = tenhou::Log::from_json_str(testdata("double_ron")).unwrap();
+    assert!(log.kyokus[0].reached_tenpai(0) && log.kyokus[0].reached_tenpai(2));
+    assert!(!log.kyokus[0].reached_tenpai(1));
+}
+
+#[test]
+fn test_winners_and_deal_in_seat() {
+    let log = tenhou::Log::from_json_str(testdata("double_ron")).unwrap();
+    let kyoku = &log.kyokus[0];
+    assert_eq!(kyoku.winners(), vec![0, 2]);
+    assert_eq!(kyoku.deal_in_seat(), Some(3));
+
+    let log = tenhou::Log::from_json_str(testdata("rinshan")).unwrap();
+    let kyoku = &log.kyokus[0];
+    assert_eq!(kyoku.winners(), vec![3]);
+    assert_eq!(kyoku.deal_in_seat(), None);
+
+    let log = tenhou::Log::from_json_str(testdata("ryukyoku")).unwrap();
+    let kyoku = &log.kyokus[0];
+    assert!(kyoku.winners().is_empty());
+    assert_eq!(kyoku.deal_in_seat(), None);
+}
+
+#[test]
+fn test_verify_kyotaku_flow() {
+    let log = tenhou::Log::from_json_str(testdata("ranked_game")).unwrap();
+    log.verify_kyotaku_flow().unwrap();
+
+    // 四家立直 leaves 4 riichi sticks for the next kyoku
+    let mut log = tenhou::Log::from_json_str(testdata("four_reach")).unwrap();
+    assert_eq!(log.kyokus[0].riichi_count(), 4);
+    assert_eq!(log.kyokus[0].leaving_kyotaku(), 4);
+
+    let mut next_kyoku = log.kyokus[0].clone();
+    next_kyoku.meta.honba += 1;
+    next_kyoku.meta.kyotaku = 4;
+    log.kyokus.push(next_kyoku);
+    log.verify_kyotaku_flow().unwrap();
+
+    log.kyokus[1].meta.kyotaku = 3;
+    let err = log.verify_kyotaku_flow().unwrap_err();
+    assert_eq!((err.index, err.expected, err.actual), (1, 4, 3));
+}
+
+#[test]
+fn test_starting_score() {
+    let log = tenhou::Log::from_json_str(testdata("ranked_game")).unwrap();
+    assert_eq!(log.starting_score(), Some(25000));
+    log.verify_score_sum(25000).unwrap();
+
+    // a game started at 30000
+    let mut log = tenhou::Log::from_json_str(testdata("ryukyoku")).unwrap();
+    assert_eq!(log.starting_score(), Some(30000));
+    log.verify_score_sum(30000).unwrap();
+    assert!(log.verify_score_sum(25000).is_err());
+
+    let mut next_kyoku = log.kyokus[0].clone();
+    next_kyoku.scoreboard[0] -= 1000;
+    log.kyokus.push(next_kyoku);
+    let err = log.verify_score_sum(30000).unwrap_err();
+    assert_eq!((err.index, err.expected, err.actual), (1, 120000, 119000));
+}
+
+#[test]
+fn test_sanma_starting_score() {
+    let data = testdata("ranked_game").replacen("特南喰赤", "三特南喰赤", 1);
+    let mut log = tenhou::Log::from_json_str(&data).unwrap();
+    assert_eq!(log.game_kind, tenhou::GameKind::Sanma);
+
+    // a game of three started at 35000, with seat 3 empty
+    let first_kyoku = &mut log.kyokus[0];
+    first_kyoku.scoreboard = [35000, 35000, 35000, 0];
+    first_kyoku.meta.kyotaku = 0;
+    assert_eq!(log.starting_score(), Some(35000));
+    log.kyokus.truncate(1);
+    log.verify_score_sum(35000).unwrap();
+
+    log.kyokus[0].scoreboard[0] += 1000;
+    assert_eq!(log.starting_score(), None);
+    let err = log.verify_score_sum(35000).unwrap_err();
+    assert_eq!((err.index, err.expected, err.actual), (0, 105000, 106000));
+}
+
+#[test]
+fn test_turn_count() {
+    let log = tenhou::Log::from_json_str(testdata("rinshan")).unwrap();
+    let tsumo_turns = log.kyokus[0].turn_count();
+
+    let log = tenhou::Log::from_json_str(testdata("ryukyoku")).unwrap();
+    let exhaustive_turns = log.kyokus[0].turn_count();
+
+    assert_eq!((tsumo_turns, exhaustive_turns), (9, 19));
+}
+
+#[test]
+fn test_leaving_scores() {
+    for TestCase { description, data } in TESTDATA {
+        let raw: json::Value = json::from_str(data).unwrap();
+        let log = tenhou::Log::from_json_str(data).unwrap();
+
+        for pair in log.kyokus.windows(2) {
+            assert_eq!(
+                pair[0].leaving_scores(),
+                pair[1].scoreboard,
+                "case: {}",
+                description,
+            );
+        }
+
+        // "sc" holds the final scores and the resulting pt alternately
+        if let Some(sc) = raw["sc"].as_array() {
+            let final_scores: Vec<_> = sc.iter().step_by(2).map(|s| s.as_i64().unwrap()).collect();
+            let leaving_scores = log.kyokus.last().unwrap().leaving_scores();
+            let leaving_scores: Vec<_> = leaving_scores.iter().map(|&s| s as i64).collect();
+            assert_eq!(leaving_scores, final_scores, "case: {}", description);
+        }
+    }
+}
+
+#[test]
+fn test_riichi_dealt_in() {
+    // seat 2 deals in with its last discard in the first kyoku, which is
+    // made the one declaring riichi
+    let mut raw: json::Value = json::from_str(testdata("ranked_game")).unwrap();
+    let to_riichi = |discard: &mut json::Value| {
+        *discard = json::json!(format!("r{}", discard.as_u64().unwrap()));
+    };
+    to_riichi(
+        raw["log"][0][6 + 3 * 2]
+            .as_array_mut()
+            .unwrap()
+            .last_mut()
+            .unwrap(),
+    );
+
+    let log = tenhou::Log::from_json_str(&raw.to_string()).unwrap();
+    let kyoku = &log.kyokus[0];
+    assert_eq!(kyoku.deal_in_seat(), Some(2));
+    assert!(kyoku.action_tables[2].riichi_turn().is_some());
+    assert!(!kyoku.paid_riichi(2));
+    assert!(!kyoku.paid_riichi(1));
+    assert_eq!(kyoku.leaving_scores(), log.kyokus[1].scoreboard);
+
+    // a riichi of seat 0 on its first discard is paid for
+    to_riichi(&mut raw["log"][0][6][0]);
+    let log = tenhou::Log::from_json_str(&raw.to_string()).unwrap();
+    let kyoku = &log.kyokus[0];
+    assert!(kyoku.paid_riichi(0));
+    assert_eq!(
+        kyoku.leaving_scores()[0],
+        log.kyokus[1].scoreboard[0] - 1000
+    );
+}
+
+#[test]
+fn test_final_scores() {
+    // the scores of "sc" are by seat, so they only match the replayed ones if
+    // seat 0 is the starting dealer, which tenhou always does
+    let raw_log: tenhou::RawLog = json::from_str(testdata("ranked_game")).unwrap();
+    let log = tenhou::Log::from(raw_log.clone());
+    assert_eq!(log.kyokus[0].meta.kyoku_num, 0);
+    assert_eq!(log.dealer_sequence()[0], 0);
+    assert_eq!(
+        raw_log.final_scores(),
+        Some(log.kyokus.last().unwrap().leaving_scores()),
+    );
+    assert_eq!(raw_log.final_scores(), Some([21100, 55000, -4900, 28800]));
+
+    // kept as given on the way back, but not in a log of only some kyokus
+    let raw: json::Value = json::from_str(testdata("ranked_game")).unwrap();
+    assert_eq!(json::to_value(&raw_log).unwrap()["sc"], raw["sc"]);
+    let standalone = raw_log.to_standalone_log(0).unwrap();
+    assert_eq!(standalone.final_scores(), None);
+    assert!(json::to_value(&standalone).unwrap().get("sc").is_none());
+
+    let raw_log: tenhou::RawLog = json::from_str(testdata("double_ron")).unwrap();
+    assert_eq!(raw_log.final_scores(), None);
+}
+
+#[test]
+fn test_ended_by_tobi() {
+    // ranked_game ends with seat 2 at -4900, move its last kyoku to S2
+    let mut raw: json::Value = json::from_str(testdata("ranked_game")).unwrap();
+    let kyokus = raw["log"].as_array_mut().unwrap();
+    let last = kyokus.len() - 1;
+    kyokus[last][0][0] = json::json!(5);
+
+    let log = tenhou::Log::from_json_str(&raw.to_string()).unwrap();
+    assert!(log.is_ended_by_tobi());
+    assert_eq!(log.kyokus.last().unwrap().meta.kyoku_num, 5);
+    assert_eq!(log.kyokus.last().unwrap().leaving_scores()[2], -4900);
+
+    // the game ends right after the kyoku, not at the end of a full hanchan
+    let events = tenhou_to_mjai(&log).unwrap();
+    assert!(matches!(
+        events[events.len() - 2..],
+        [mjai::Event::EndKyoku, mjai::Event::EndGame],
+    ));
+
+    let log = tenhou::Log::from_json_str(testdata("ryukyoku")).unwrap();
+    assert!(!log.is_ended_by_tobi());
+}
+
+#[test]
+fn test_placements() {
+    // ends with [21100, 55000, -4900, 28800]
+    let mut log = tenhou::Log::from_json_str(testdata("ranked_game")).unwrap();
+    let placements = log.placements();
+    assert_eq!(placements, [3, 1, 4, 2]);
+    assert_eq!(placements.iter().sum::<u8>(), 1 + 2 + 3 + 4);
+
+    // seat 0 and seat 3 tie at 28800, seat 0 is closer to the starting dealer
+    let last_kyoku = log.kyokus.last_mut().unwrap();
+    last_kyoku.scoreboard[0] += 28800 - 21100;
+    last_kyoku.scoreboard[1] -= 28800 - 21100;
+    assert_eq!(log.placements(), [2, 1, 4, 3]);
+
+    // seat 0 and seat 1 stay at 25000 after the chankan
+    let log = tenhou::Log::from_json_str(testdata("chankan")).unwrap();
+    assert_eq!(log.placements(), [2, 3, 1, 4]);
+}
+
+#[test]
+fn test_oorasu_targets() {
+    // S4 entering at [41800, 26300, 37400, 14500], no honba nor kyotaku
+    let mut log = tenhou::Log::from_json_str(testdata("double_ron")).unwrap();
+    let targets = log.oorasu_targets().unwrap();
+    assert_eq!(targets.len(), 4);
+
+    let top = targets[0];
+    assert_eq!(
+        (top.placement, top.behind, top.ahead),
+        (1, None, Some(4400))
+    );
+    assert_eq!((top.direct_hit, top.ron), (None, None));
+
+    // seat 2 needs a direct hit of 2300 off seat 0, which wins a tie, or
+    // 4500 off anyone else
+    let second = targets[2];
+    assert_eq!(second.placement, 2);
+    assert_eq!((second.behind, second.ahead), (Some(4400), Some(11100)));
+    assert_eq!((second.direct_hit, second.ron), (Some(2300), Some(4500)));
+
+    // seat 1 wins a tie against seat 2, so an exact half is enough
+    let third = targets[1];
+    assert_eq!((third.placement, third.behind), (3, Some(11100)));
+    assert_eq!((third.direct_hit, third.ron), (Some(5600), Some(11100)));
+    assert_eq!(targets[3].ahead, None);
+
+    // honba and kyotaku count toward the gap
+    log.kyokus[0].meta.honba = 2;
+    log.kyokus[0].meta.kyotaku = 1;
+    let second = log.oorasu_targets().unwrap()[2];
+    assert_eq!((second.direct_hit, second.ron), (Some(1200), Some(2900)));
+
+    // E2 of a hanchan is not the oorasu
+    let log = tenhou::Log::from_json_str(testdata("ranked_game")).unwrap();
+    assert_eq!(log.oorasu_targets(), None);
+}
+
+#[test]
+fn test_placement_swings() {
+    // seat 1 climbs from 3rd to 1st by winning E2, the decisive hand
+    let log = tenhou::Log::from_json_str(testdata("ranked_game")).unwrap();
+    let pt = [90, 45, 0, -135];
+    let swings = log.placement_swings(1, pt);
+    let summary: Vec<_> = swings
+        .iter()
+        .map(|s| {
+            (
+                s.kyoku,
+                s.honba,
+                s.score_delta,
+                s.before,
+                s.after,
+                s.pt_delta,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            // tied at the start, seat 1 is 2nd by seat order
+            (0, 0, 0, 2, 3, -45),
+            (1, 0, 11700, 3, 1, 90),
+            (1, 1, 18300, 1, 1, 0),
+        ],
+    );
+
+    let impactful = log.most_impactful_kyokus(1, pt, 1);
+    assert_eq!(impactful, [swings[1]]);
+    let impactful = log.most_impactful_kyokus(1, pt, 5);
+    assert_eq!(impactful, [swings[1], swings[0], swings[2]]);
+
+    // seat 3 is empty in sanma
+    let data = testdata("ranked_game").replacen("特南喰赤", "三特南喰赤", 1);
+    let log = tenhou::Log::from_json_str(&data).unwrap();
+    assert_eq!(log.placement_swings(0, pt).len(), 3);
+    assert!(log.placement_swings(3, pt).is_empty());
+    assert!(log.most_impactful_kyokus(3, pt, 5).is_empty());
+    assert!(log.placement_swings(4, pt).is_empty());
+}
+
+#[test]
+fn test_dealer_sequence() {
+    // the renchan of E2.1 keeps seat 1 as the dealer
+    let log = tenhou::Log::from_json_str(testdata("ranked_game")).unwrap();
+    assert_eq!(log.dealer_sequence(), [0, 1, 1]);
+    let honbas: Vec<_> = log.kyokus.iter().map(|k| k.meta.honba).collect();
+    assert_eq!(honbas, [0, 0, 1]);
+
+    let log = tenhou::Log::from_json_str(testdata("confusing_nakis_3")).unwrap();
+    assert_eq!(log.dealer_sequence(), [3]);
+}
+
+#[test]
+fn test_hero_is_dealer() {
+    // ranked_game covers E1, E2 and E2.1
+    let log = tenhou::Log::from_json_str(testdata("ranked_game")).unwrap();
+    assert_eq!(log.hero_is_dealer(0), [true, false, false]);
+    assert_eq!(log.hero_is_dealer(1), [false, true, true]);
+
+    // a full hanchan rotation from E1 to S4
+    let mut log = tenhou::Log::from_json_str(testdata("chankan")).unwrap();
+    let kyoku = log.kyokus[0].clone();
+    log.kyokus = (0..8)
+        .map(|kyoku_num| {
+            let mut k = kyoku.clone();
+            k.meta.kyoku_num = kyoku_num;
+            k
+        })
+        .collect();
+    for seat in 0..4 {
+        let is_dealer = log.hero_is_dealer(seat);
+        assert_eq!(is_dealer.len(), 8);
+        for round in is_dealer.chunks(4) {
+            assert_eq!(round.iter().filter(|&&d| d).count(), 1);
+            assert!(round[seat as usize]);
+        }
+    }
+}
+
+#[test]
+fn test_tenpai_seats_at_ryukyoku() {
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        for kyoku in &log.kyokus {
+            let tenpai_seats = match kyoku.end_status {
+                EndStatus::Ryukyoku {
+                    reason: RyukyokuReason::Exhaustive,
+                    tenpai_seats,
+                    ..
+                } => tenpai_seats,
+                _ => continue,
+            };
+
+            // agrees with the hands after the last discard
+            for (seat, table) in kyoku.action_tables.iter().enumerate() {
+                let last_shanten = table.shanten_by_turn().into_iter().flatten().last();
+                assert_eq!(
+                    tenpai_seats[seat],
+                    last_shanten == Some(0),
+                    "case: {}, seat: {}",
+                    description,
+                    seat,
+                );
+            }
+        }
+    }
+
+    // an abortive draw has no tenpai payment
+    let log = tenhou::Log::from_json_str(testdata("four_reach")).unwrap();
+    assert!(matches!(
+        log.kyokus[0].end_status,
+        EndStatus::Ryukyoku {
+            tenpai_seats: [false, false, false, false],
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_sanma_placements() {
+    let log = tenhou::Log::from_json_str(testdata("ranked_game")).unwrap();
+    assert_eq!(log.game_kind, tenhou::GameKind::Yonma);
+
+    let data = testdata("ranked_game").replacen("特南喰赤", "三特南喰赤", 1);
+    let mut log = tenhou::Log::from_json_str(&data).unwrap();
+    assert_eq!(log.game_kind, tenhou::GameKind::Sanma);
+
+    // seat 3 is empty, seat 2 is the last even below 0
+    let last_kyoku = log.kyokus.last_mut().unwrap();
+    last_kyoku.scoreboard[0] += last_kyoku.scoreboard[3];
+    last_kyoku.scoreboard[3] = 0;
+    assert_eq!(last_kyoku.leaving_scores(), [49900, 55000, -4900, 0]);
+    assert_eq!(log.placements(), [2, 1, 3, 0]);
+}
+
+#[test]
+fn test_dealer_over_renchan() {
+    // E2 is a renchan of seat 1 into E2.1
+    let log = tenhou::Log::from_json_str(testdata("ranked_game")).unwrap();
+    let kyokus: Vec<_> = log
+        .kyokus
+        .iter()
+        .map(|k| (k.meta.kyoku_num, k.meta.honba, k.oya()))
+        .collect();
+    assert_eq!(kyokus, [(0, 0, 0), (1, 0, 1), (1, 1, 1)]);
+
+    // the same in mjai, and back again
+    let events = tenhou_to_mjai(&log).unwrap();
+    let starts: Vec<_> = events
+        .iter()
+        .filter_map(|ev| match *ev {
+            mjai::Event::StartKyoku {
+                kyoku, honba, oya, ..
+            } => Some((kyoku, honba, oya)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(starts, [(1, 0, 0), (2, 0, 1), (2, 1, 1)]);
+
+    let log = mjai_to_tenhou(&events).unwrap();
+    let oyas: Vec<_> = log.kyokus.iter().map(|k| k.oya()).collect();
+    assert_eq!(oyas, [0, 1, 1]);
+    for seat in 0..4 {
+        let expected: Vec<_> = oyas.iter().map(|&oya| oya == seat).collect();
+        assert_eq!(log.hero_is_dealer(seat), expected);
+    }
+}
+
+#[test]
+fn test_winds_over_hanchan() {
+    use Pai::{East as E, North as N, South as S, West as W};
+
+    let log = tenhou::Log::from_json_str(testdata("ranked_game")).unwrap();
+    let mut kyoku = log.kyokus[0].clone();
+
+    // seat winds of seats 0 to 3, E1 to E4 and then S1 to S4
+    let expected = [[E, S, W, N], [N, E, S, W], [W, N, E, S], [S, W, N, E]];
+    for kyoku_num in 0..8 {
+        kyoku.meta.kyoku_num = kyoku_num;
+        let round_wind = if kyoku_num < 4 { E } else { S };
+        assert_eq!(kyoku.round_wind(), round_wind);
+
+        let winds: Vec<_> = (0..4)
+            .map(|seat| kyoku.seat_wind(seat, log.game_kind))
+            .collect();
+        assert_eq!(winds, expected[kyoku_num as usize % 4]);
+    }
+
+    // the renchan keeps the winds of E2
+    let renchan = &log.kyokus[2];
+    assert_eq!(renchan.meta.honba, 1);
+    assert_eq!(renchan.round_wind(), E);
+    assert_eq!(renchan.seat_wind(1, log.game_kind), E);
+    assert_eq!(renchan.seat_wind(0, log.game_kind), N);
+
+    // in sanma nobody is North
+    let expected = [[E, S, W], [W, E, S], [S, W, E]];
+    for kyoku_num in [0, 1, 2, 4, 5, 6] {
+        kyoku.meta.kyoku_num = kyoku_num;
+        let winds: Vec<_> = (0..3)
+            .map(|seat| kyoku.seat_wind(seat, tenhou::GameKind::Sanma))
+            .collect();
+        assert_eq!(winds, expected[kyoku_num as usize % 4]);
+    }
+}
+
+#[test]
+fn test_winds_of_early_ending_yonma() {
+    use Pai::{East as E, North as N, South as S, West as W};
+
+    // the dealer declares kyuushu kyuuhai on the first draw, before seat 3
+    // ever draws
+    let data = testdata("kyushukyuhai")
+        .replacen("[[3,0,0]", "[[0,0,0]", 1)
+        .replacen("[37],[42],", "[],[],", 1);
+    let log = tenhou::Log::from_json_str(&data).unwrap();
+    let kyoku = &log.kyokus[0];
+    assert_eq!(log.game_kind, tenhou::GameKind::Yonma);
+    assert!(kyoku.action_tables[3].takes.is_empty());
+
+    let winds: Vec<_> = (0..4)
+        .map(|seat| kyoku.seat_wind(seat, log.game_kind))
+        .collect();
+    assert_eq!(winds, [E, S, W, N]);
+}
+
+#[test]
+fn test_string_score_deltas() {
+    // the same as ranked_game, with score deltas like "-7700"
+    let log = tenhou::Log::from_json_str(testdata("string_score_deltas")).unwrap();
+    let expected = tenhou::Log::from_json_str(testdata("ranked_game")).unwrap();
+    let leaving_scores: Vec<_> = log.kyokus.iter().map(|k| k.leaving_scores()).collect();
+    let expected_scores: Vec<_> = expected.kyokus.iter().map(|k| k.leaving_scores()).collect();
+    assert_eq!(leaving_scores, expected_scores);
+    assert_eq!(log.kyokus[2].winners(), [1]);
+    assert_eq!(log.kyokus[2].deal_in_seat(), Some(2));
+
+    let data = testdata("string_score_deltas").replacen("\"-7700\"", "\"-77OO\"", 1);
+    let err = tenhou::Log::from_json_str(&data).unwrap_err();
+    assert!(
+        err.to_string().contains("invalid score delta \"-77OO\""),
+        "{}",
+        err,
+    );
+}
+
+#[test]
+fn test_kyoku_pov() {
+    let log = tenhou::Log::from_json_str(testdata("ranked_game")).unwrap();
+    // seat 1 wins E2.1 from seat 2
+    let kyoku = &log.kyokus[2];
+
+    for hero in 0..4 {
+        let pov = kyoku.pov(hero);
+        for seat in 0..4 {
+            assert_eq!(pov.seat(pov.relative(seat)), seat);
+        }
+        assert_eq!(pov.unrotate(pov.scoreboard()), kyoku.scoreboard);
+        assert_eq!(pov.unrotate(pov.leaving_scores()), kyoku.leaving_scores());
+        assert_eq!(pov.rotate(pov.unrotate([0, 1, 2, 3])), [0, 1, 2, 3]);
+        assert_eq!(pov.seat(0), hero);
+        assert_eq!(
+            pov.action_table(0).haipai,
+            kyoku.action_tables[hero as usize].haipai
+        );
+    }
+
+    // from the winner, the deal-in is from 下家 and the dealer is the hero
+    let pov = kyoku.pov(1);
+    assert_eq!(pov.oya(), 0);
+    assert_eq!(pov.score_deltas(), [18300, -18300, 0, 0]);
+    assert_eq!(
+        tenhou::kyoku::RELATIVE_SEAT_NAMES[pov.relative(2) as usize],
+        "下家"
+    );
+
+    // from the loser, the winner is 上家
+    let pov = kyoku.pov(2);
+    assert_eq!(pov.relative(1), 3);
+    assert_eq!(pov.score_deltas(), [-18300, 0, 0, 18300]);
+}
@@ -0,0 +1,18 @@
+mod testdata;
+
+use convlog::export::{ExternalFormat, Mjai};
+use convlog::*;
+use testdata::testdata;
+
+#[test]
+fn test_export_mjai_golden() {
+    let data = testdata("rinshan");
+    let log = tenhou::Log::from_json_str(data).unwrap();
+
+    let mut exported = vec![];
+    Mjai.export(&log, &mut exported).unwrap();
+    let exported = String::from_utf8(exported).unwrap();
+
+    assert_eq!(exported, include_str!("testdata/rinshan.mjai.json"));
+    assert_eq!(Mjai.name(), "mjai");
+}
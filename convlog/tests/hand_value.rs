@@ -0,0 +1,220 @@
+use convlog::hand_value::{self, Direction, HanRange, HandDirection};
+use convlog::mjai::{Consumed2, Event};
+use convlog::pai::pais_from_mpsz;
+use convlog::Pai;
+use std::convert::TryInto;
+
+fn pai(s: &str) -> Pai {
+    Pai::from_mpsz(s).unwrap()
+}
+
+#[test]
+fn test_hand_matures() {
+    let haipai: [Pai; 13] = pais_from_mpsz("123m456p789s234s1z")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let events = vec![
+        Event::StartKyoku {
+            bakaze: pai("1z"),
+            dora_marker: pai("4s"),
+            kyoku: 1,
+            honba: 0,
+            kyotaku: 0,
+            oya: 0,
+            scores: [25000; 4],
+            tehais: [
+                haipai,
+                [Pai::Unknown; 13],
+                [Pai::Unknown; 13],
+                [Pai::Unknown; 13],
+            ],
+        },
+        Event::Tsumo {
+            actor: 0,
+            pai: pai("9p"),
+        },
+        Event::Dahai {
+            actor: 0,
+            pai: pai("9p"),
+            tsumogiri: true,
+        },
+        Event::Tsumo {
+            actor: 0,
+            pai: pai("0s"),
+        },
+        Event::Dahai {
+            actor: 0,
+            pai: pai("1z"),
+            tsumogiri: false,
+        },
+        Event::Tsumo {
+            actor: 0,
+            pai: pai("6s"),
+        },
+        Event::Dahai {
+            actor: 0,
+            pai: pai("1m"),
+            tsumogiri: false,
+        },
+    ];
+
+    let progression = hand_value::progression(&events, 0, true);
+    assert_eq!(progression.len(), 3);
+
+    // tanki on East without any yaku, only worth riichi and tsumo
+    assert_eq!(progression[0].turn, 2);
+    assert_eq!(progression[0].junme, 1);
+    assert_eq!(progression[0].shanten, 0);
+    assert_eq!(progression[0].waits, vec![27]);
+    assert_eq!(progression[0].han, Some(HanRange { min: 0, max: 2 }));
+
+    // an aka 5s that is also dora, waiting on 2s or the other dora 5s
+    assert_eq!(progression[1].waits, vec![19, 22]);
+    assert_eq!(progression[1].han, Some(HanRange { min: 2, max: 5 }));
+
+    // breaking the tenpai for tanyao
+    assert_eq!(progression[2].junme, 3);
+    assert_eq!(progression[2].shanten, 1);
+    assert!(progression[2].waits.is_empty());
+    assert_eq!(progression[2].han, None);
+
+    // aka count as normal 5s without aka
+    let progression = hand_value::progression(&events, 0, false);
+    assert_eq!(progression[1].han, Some(HanRange { min: 1, max: 4 }));
+}
+
+#[test]
+fn test_open_honitsu() {
+    let haipai: [Pai; 13] = pais_from_mpsz("123567m55z99m77z1p")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let events = vec![
+        Event::StartKyoku {
+            bakaze: pai("1z"),
+            dora_marker: pai("9p"),
+            kyoku: 1,
+            honba: 0,
+            kyotaku: 0,
+            oya: 1,
+            scores: [25000; 4],
+            tehais: [
+                haipai,
+                [Pai::Unknown; 13],
+                [Pai::Unknown; 13],
+                [Pai::Unknown; 13],
+            ],
+        },
+        Event::Pon {
+            actor: 0,
+            target: 3,
+            pai: pai("7z"),
+            consumed: Consumed2::from([pai("7z"), pai("7z")]),
+        },
+        Event::Dahai {
+            actor: 0,
+            pai: pai("1p"),
+            tsumogiri: false,
+        },
+    ];
+
+    // shanpon on 9m or haku, with honitsu and chun, and haku as yakuhai
+    // the pon is the first junme
+    let progression = hand_value::progression(&events, 0, true);
+    assert_eq!(progression[0].junme, 1);
+    assert_eq!(progression[0].waits, vec![8, 31]);
+    assert_eq!(progression[0].han, Some(HanRange { min: 3, max: 4 }));
+}
+
+#[test]
+fn test_hand_direction() {
+    let direction = |s| HandDirection::from_haipai(&pais_from_mpsz(s).unwrap());
+
+    // 9 souzu and 3 jihai, leaning toward honitsu but not chinitsu
+    let flush = direction("1p123456789s557z");
+    assert_eq!(flush.main_suit, 2);
+    assert_eq!(flush.main_suit_count, 9);
+    assert_eq!(flush.jihai_count, 3);
+    assert_eq!(flush.hint(), Some(Direction::Honitsu));
+
+    let flush = direction("1123456789m12p3s");
+    assert_eq!(flush.main_suit, 0);
+    assert_eq!(flush.hint(), Some(Direction::Chinitsu));
+
+    assert_eq!(
+        direction("123m789m11p99p789s").hint(),
+        Some(Direction::Junchan),
+    );
+    assert_eq!(
+        direction("123m789p11s99s123z").hint(),
+        Some(Direction::Chanta)
+    );
+
+    let plain = direction("234m456p567s3466s");
+    assert_eq!(plain.yaochuu_count, 0);
+    assert_eq!(plain.hint(), None);
+}
+
+#[test]
+fn test_efficiency() {
+    let haipai: [Pai; 13] = pais_from_mpsz("123m456p789s24s55z")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let tsumo = |p| Event::Tsumo {
+        actor: 0,
+        pai: pai(p),
+    };
+    let dahai = |p| Event::Dahai {
+        actor: 0,
+        pai: pai(p),
+        tsumogiri: false,
+    };
+    let events = vec![
+        Event::StartKyoku {
+            bakaze: pai("1z"),
+            dora_marker: pai("1z"),
+            kyoku: 1,
+            honba: 0,
+            kyotaku: 0,
+            oya: 0,
+            scores: [25000; 4],
+            tehais: [
+                haipai,
+                [Pai::Unknown; 13],
+                [Pai::Unknown; 13],
+                [Pai::Unknown; 13],
+            ],
+        },
+        tsumo("9m"),
+        // breaking the kanchan tenpai instead of discarding 9m
+        dahai("2s"),
+        tsumo("3s"),
+        dahai("9m"),
+        tsumo("1z"),
+        Event::Reach { actor: 0 },
+        dahai("1z"),
+        Event::ReachAccepted { actor: 0 },
+        tsumo("1p"),
+        dahai("1p"),
+    ];
+
+    let checks = hand_value::efficiency(&events, 0);
+    assert_eq!(checks.len(), 3);
+
+    assert_eq!(checks[0].turn, 2);
+    assert_eq!(checks[0].discarded, pai("2s"));
+    assert_eq!((checks[0].shanten, checks[0].best_shanten), (1, 0));
+    assert_eq!(checks[0].best, [pai("9m")]);
+    assert!(checks[0].is_mistake());
+
+    // 25s of which 2s is in the river
+    assert_eq!((checks[1].shanten, checks[1].ukeire), (0, 7));
+    assert_eq!(checks[1].best, [pai("9m")]);
+    assert!(!checks[1].is_mistake());
+
+    // the discard of the riichi is checked, the ones after it are not
+    assert_eq!(checks[2].discarded, pai("1z"));
+    assert!(!checks[2].is_mistake());
+}
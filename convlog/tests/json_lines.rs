@@ -0,0 +1,45 @@
+mod testdata;
+
+use convlog::tenhou::{LineError, Log};
+use testdata::testdata;
+
+use serde_json as json;
+
+/// Minifies a pretty-printed log into a single line.
+fn one_line(description: &str) -> String {
+    let value: json::Value = json::from_str(testdata(description)).unwrap();
+    json::to_string(&value).unwrap()
+}
+
+#[test]
+fn test_json_lines_mixed_validity() {
+    let input = [
+        one_line("ranked_game"),
+        "{\"log\": [".to_owned(),
+        String::new(),
+        one_line("ryukyoku"),
+        "not json at all".to_owned(),
+        one_line("chankan"),
+    ]
+    .join("\n");
+
+    let results: Vec<_> = Log::from_json_lines(input.as_bytes()).collect();
+    assert_eq!(results.len(), 5);
+
+    let bad_lines: Vec<_> = results
+        .iter()
+        .filter_map(|r| match r {
+            Err(LineError::Parse { line, .. }) => Some(*line),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(bad_lines, [2, 5]);
+
+    let logs: Vec<_> = results.into_iter().filter_map(Result::ok).collect();
+    assert_eq!(logs.len(), 3);
+    for (log, description) in logs.iter().zip(["ranked_game", "ryukyoku", "chankan"]) {
+        let expected = Log::from_json_str(testdata(description)).unwrap();
+        assert_eq!(log.kyokus.len(), expected.kyokus.len());
+        assert_eq!(log.names, expected.names);
+    }
+}
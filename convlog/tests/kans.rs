@@ -0,0 +1,81 @@
+mod testdata;
+
+use convlog::mjai::Event;
+use convlog::*;
+use testdata::{testdata, TestCase, TESTDATA};
+
+#[test]
+fn test_kans_remaining() {
+    let log = tenhou::Log::from_json_str(testdata("suukantsu_0")).unwrap();
+    let kyoku = &log.kyokus[0];
+
+    assert_eq!(kyoku.kans_remaining(0), 4);
+    assert_eq!(kyoku.kans_remaining(15), 4);
+    assert_eq!(kyoku.kans_remaining(16), 3);
+    assert_eq!(kyoku.kans_remaining(17), 2);
+    // after three kans, only one remains
+    assert_eq!(kyoku.kans_remaining(18), 1);
+    assert_eq!(kyoku.kans_remaining(kyoku.turn_count()), 0);
+}
+
+#[test]
+fn test_kans_remaining_matches_mjai() {
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        let events = tenhou_to_mjai(&log).unwrap();
+
+        let mut kan_counts = vec![];
+        for event in &events {
+            match event {
+                Event::StartKyoku { .. } => kan_counts.push(0),
+                Event::Daiminkan { .. } | Event::Ankan { .. } | Event::Kakan { .. } => {
+                    *kan_counts.last_mut().unwrap() += 1;
+                }
+                _ => (),
+            }
+        }
+
+        let remaining: Vec<_> = log
+            .kyokus
+            .iter()
+            .map(|k| k.kans_remaining(k.turn_count()))
+            .collect();
+        let expected: Vec<_> = kan_counts.iter().map(|c| 4 - c).collect();
+        assert_eq!(remaining, expected, "case: {}", description);
+    }
+}
+
+#[test]
+fn test_verify_dora_count() {
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        for kyoku in &log.kyokus {
+            kyoku
+                .verify_dora_count()
+                .unwrap_or_else(|e| panic!("{} (case: {})", e, description));
+        }
+    }
+
+    // no kan and 1 indicator
+    let mut log = tenhou::Log::from_json_str(testdata("ryukyoku")).unwrap();
+    let kyoku = &mut log.kyokus[0];
+    assert_eq!(kyoku.kans_remaining(usize::MAX), 4);
+    assert_eq!(kyoku.dora_indicators.len(), 1);
+    kyoku.dora_indicators.push(Pai::Man1);
+    let err = kyoku.verify_dora_count().unwrap_err();
+    assert_eq!((err.kans, err.actual), (0, 2));
+
+    // two kakans, the second robbed by chankan, with only 1 indicator
+    let log = tenhou::Log::from_json_str(testdata("double_kakan_then_chankan")).unwrap();
+    let kyoku = &log.kyokus[0];
+    assert_eq!(kyoku.kans_remaining(usize::MAX), 2);
+    assert_eq!(kyoku.dora_indicators.len(), 1);
+
+    // suukantsu with 4 kans and 5 indicators
+    let mut log = tenhou::Log::from_json_str(testdata("suukantsu_0")).unwrap();
+    let kyoku = &mut log.kyokus[0];
+    assert_eq!(kyoku.dora_indicators.len(), 5);
+    kyoku.dora_indicators.truncate(3);
+    let err = kyoku.verify_dora_count().unwrap_err();
+    assert_eq!((err.kans, err.actual), (4, 3));
+}
@@ -0,0 +1,59 @@
+mod testdata;
+
+use convlog::tenhou::{ActionItem, ActionTable};
+use convlog::*;
+use testdata::testdata;
+
+#[test]
+fn test_kita() {
+    let mut haipai = [Pai::Unknown; 13];
+    haipai.copy_from_slice(&[
+        Pai::Man1,
+        Pai::Man9,
+        Pai::Pin1,
+        Pai::Pin2,
+        Pai::Pin3,
+        Pai::Pin4,
+        Pai::Pin5,
+        Pai::Pin6,
+        Pai::Sou7,
+        Pai::Sou8,
+        Pai::Sou9,
+        Pai::North,
+        Pai::North,
+    ]);
+
+    // two kitas, each followed by a rinshan, then 9m is discarded
+    let table = ActionTable {
+        haipai,
+        takes: vec![
+            ActionItem::Pai(Pai::Man1),
+            ActionItem::Pai(Pai::Haku),
+            ActionItem::Pai(Pai::Man9),
+        ],
+        discards: vec![
+            ActionItem::Naki("f44".to_owned()),
+            ActionItem::Naki("f44".to_owned()),
+            ActionItem::Pai(Pai::Haku),
+        ],
+    };
+
+    assert!(table.discards[0].is_kita());
+    assert!(!ActionItem::Naki("r44".to_owned()).is_kita());
+    assert_eq!(table.kita_count(), 2);
+
+    // 11m99m123456p789s, a tenpai for shanpon after the 2 kitas
+    assert_eq!(table.shanten_by_turn(), vec![None, None, Some(0)]);
+}
+
+#[test]
+fn test_kita_is_not_converted() {
+    let mut log = tenhou::Log::from_json_str(testdata("chankan")).unwrap();
+    log.kyokus[0].action_tables[1].discards[0] = ActionItem::Naki("f44".to_owned());
+    assert_eq!(log.kyokus[0].kita_counts(), [0, 1, 0, 0]);
+
+    assert!(matches!(
+        tenhou_to_mjai(&log),
+        Err(ConvertError::UnsupportedKita(_)),
+    ));
+}
@@ -0,0 +1,39 @@
+use convlog::LogDate;
+
+#[test]
+fn test_log_date_from_log_id() {
+    let date = |year, month, day| Some(LogDate { year, month, day });
+
+    assert_eq!(
+        LogDate::from_log_id("2019062701gm-00a9-0000-0a1b2c3d"),
+        date(2019, 6, 27),
+    );
+    assert_eq!(
+        LogDate::from_log_id("2020123123gm-00c1-0000-deadbeef"),
+        date(2020, 12, 31),
+    );
+    assert_eq!(
+        LogDate::from_log_id("190627-0a1b2c3d-4e5f-6789-abcd-ef0123456789"),
+        date(2019, 6, 27),
+    );
+
+    for undated in [
+        "",
+        "report",
+        "2019062701",
+        "2019132701gm-00a9-0000-0a1b2c3d", // month 13
+        "190600-0a1b2c3d",                 // day 0
+        "19x627-0a1b2c3d",
+        "2019062701xx-00a9-0000-0a1b2c3d",
+    ] {
+        assert_eq!(LogDate::from_log_id(undated), None, "{:?}", undated);
+    }
+
+    let mut dates: Vec<_> = ["2020010100gm-0", "2019123123gm-0", "191231-0"]
+        .iter()
+        .filter_map(|id| LogDate::from_log_id(id))
+        .collect();
+    dates.sort();
+    let dates: Vec<_> = dates.iter().map(ToString::to_string).collect();
+    assert_eq!(dates, ["2019-12-31", "2019-12-31", "2020-01-01"]);
+}
@@ -0,0 +1,72 @@
+mod testdata;
+
+use convlog::tenhou::{GameLength, Warning};
+use convlog::*;
+use testdata::testdata;
+
+use serde_json as json;
+
+fn without_rule(data: &str) -> tenhou::RawLog {
+    let mut value: json::Value = json::from_str(data).unwrap();
+    value.as_object_mut().unwrap().remove("rule");
+    json::from_value(value).unwrap()
+}
+
+#[test]
+fn test_missing_rule() {
+    // "Gold Room South" with aka
+    let log = tenhou::Log::from_json_str(testdata("confusing_nakis_7")).unwrap();
+    assert!(log.warnings.is_empty());
+    assert!(log.has_aka);
+
+    let raw_log = without_rule(testdata("confusing_nakis_7"));
+    let value = json::to_value(&raw_log).unwrap();
+    assert!(value.get("rule").is_none());
+
+    let log = tenhou::Log::from(raw_log);
+    assert_eq!(log.warnings, vec![Warning::MissingRule]);
+    assert!(matches!(log.game_length, GameLength::Hanchan));
+    assert!(log.has_aka);
+    tenhou_to_mjai(&log).unwrap();
+}
+
+#[test]
+fn test_force_game_length() {
+    // a rule without "disp" to tell the game length
+    let mut log = tenhou::Log::from_json_str(testdata("confusing_nakis_2")).unwrap();
+    assert!(log.warnings.is_empty());
+    assert_eq!(log.game_length, GameLength::Hanchan);
+
+    log.force_game_length(GameLength::Tonpuu);
+    assert_eq!(log.game_length, GameLength::Tonpuu);
+    assert!(log.warnings.is_empty());
+    let events = tenhou_to_mjai(&log).unwrap();
+    assert!(matches!(
+        events[0],
+        mjai::Event::StartGame { kyoku_first: 4, .. },
+    ));
+
+    // a hanchan that never leaves the east round
+    log.force_game_length(GameLength::Hanchan);
+    assert_eq!(
+        log.warnings,
+        vec![Warning::GameLengthMismatch {
+            forced: GameLength::Hanchan,
+            last_kyoku: 0,
+        }],
+    );
+
+    // a tonpuu reaching the west round
+    let mut log = tenhou::Log::from_json_str(testdata("confusing_nakis_3")).unwrap();
+    log.force_game_length(GameLength::Tonpuu);
+    assert!(log.warnings.is_empty());
+    log.kyokus[0].meta.kyoku_num = 8;
+    log.force_game_length(GameLength::Tonpuu);
+    assert_eq!(
+        log.warnings,
+        vec![Warning::GameLengthMismatch {
+            forced: GameLength::Tonpuu,
+            last_kyoku: 8,
+        }],
+    );
+}
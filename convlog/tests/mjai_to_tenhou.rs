@@ -0,0 +1,109 @@
+mod testdata;
+
+use convlog::mjai::Event;
+use convlog::tenhou::kyoku::{EndStatus, RyukyokuReason};
+use convlog::*;
+use testdata::{testdata, TestCase, TESTDATA};
+
+use serde_json as json;
+
+#[test]
+fn test_mjai_to_tenhou() {
+    TESTDATA.iter().for_each(|TestCase { description, data }| {
+        let tenhou_log = tenhou::Log::from_json_str(data)
+            .unwrap_or_else(|_| panic!("failed to parse tenhou log (case: {})", description));
+        let mjai_log = tenhou_to_mjai(&tenhou_log)
+            .unwrap_or_else(|_| panic!("failed to transform tenhou log (case: {})", description));
+
+        let tenhou_log_from_mjai = mjai_to_tenhou(&mjai_log)
+            .unwrap_or_else(|_| panic!("failed to transform mjai log (case: {})", description));
+        assert_eq!(tenhou_log_from_mjai.names, tenhou_log.names);
+        assert_eq!(tenhou_log_from_mjai.kyokus.len(), tenhou_log.kyokus.len());
+
+        let mjai_log_round_trip = tenhou_to_mjai(&tenhou_log_from_mjai).unwrap_or_else(|_| {
+            panic!(
+                "failed to transform tenhou log from mjai (case: {})",
+                description
+            )
+        });
+        assert_eq!(mjai_log_round_trip, mjai_log, "case: {}", description);
+    });
+}
+
+fn ryukyoku_reason(log: &tenhou::Log) -> RyukyokuReason {
+    match log.kyokus[0].end_status {
+        EndStatus::Ryukyoku { reason, .. } => reason,
+        _ => panic!("not a ryukyoku"),
+    }
+}
+
+/// A kyoku where the players from seat 0 draw and tsumogiri the pais of
+/// `turns` in turn, then it ends in ryukyoku.
+fn mjai_kyoku(bakaze: &str, kyoku: u8, turns: &[&str]) -> Vec<Event> {
+    let mut lines = vec![
+        json::json!({
+            "type": "start_game",
+            "kyoku_first": 0,
+            "aka_flag": true,
+            "names": ["A", "B", "C", "D"],
+        }),
+        json::json!({
+            "type": "start_kyoku",
+            "bakaze": bakaze,
+            "dora_marker": "1p",
+            "kyoku": kyoku,
+            "honba": 0,
+            "kyotaku": 0,
+            "oya": 0,
+            "scores": [25000, 25000, 25000, 25000],
+            "tehais": vec![vec!["?"; 13]; 4],
+        }),
+    ];
+    for (i, pai) in turns.iter().enumerate() {
+        let actor = i % 4;
+        lines.push(json::json!({"type": "tsumo", "actor": actor, "pai": pai}));
+        lines.push(json::json!({"type": "dahai", "actor": actor, "pai": pai, "tsumogiri": true}));
+    }
+    lines.push(json::json!({"type": "ryukyoku"}));
+    lines.push(json::json!({"type": "end_kyoku"}));
+    lines.push(json::json!({"type": "end_game"}));
+    lines
+        .into_iter()
+        .map(|v| json::from_value(v).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_first_turn_aborts() {
+    let log = tenhou::Log::from_json_str(testdata("kyushukyuhai")).unwrap();
+    let log = mjai_to_tenhou(&tenhou_to_mjai(&log).unwrap()).unwrap();
+    assert_eq!(ryukyoku_reason(&log), RyukyokuReason::KyuushuKyuuhai);
+    assert!(log.kyokus[0].is_first_turn_abort());
+
+    let log = mjai_to_tenhou(&mjai_kyoku("E", 1, &["N"; 4])).unwrap();
+    assert_eq!(ryukyoku_reason(&log), RyukyokuReason::SuufuuRenda);
+    assert!(log.kyokus[0].is_first_turn_abort());
+
+    // not all the same, or not a wind
+    let log = mjai_to_tenhou(&mjai_kyoku("E", 1, &["N", "N", "N", "S"])).unwrap();
+    assert_eq!(ryukyoku_reason(&log), RyukyokuReason::Unknown);
+    let log = mjai_to_tenhou(&mjai_kyoku("E", 1, &["P"; 4])).unwrap();
+    assert_eq!(ryukyoku_reason(&log), RyukyokuReason::Unknown);
+
+    let log = tenhou::Log::from_json_str(testdata("ryukyoku")).unwrap();
+    let log = mjai_to_tenhou(&tenhou_to_mjai(&log).unwrap()).unwrap();
+    assert!(!log.kyokus[0].is_first_turn_abort());
+}
+
+#[test]
+fn test_invalid_start_kyoku() {
+    for (bakaze, kyoku) in [("E", 0), ("S", 5), ("P", 1), ("5m", 1)] {
+        let err = mjai_to_tenhou(&mjai_kyoku(bakaze, kyoku, &[])).unwrap_err();
+        assert!(
+            matches!(err, ConvertError::InvalidStartKyoku { kyoku: k, .. } if k == kyoku),
+            "{:?}",
+            err,
+        );
+    }
+    assert!(mjai_to_tenhou(&mjai_kyoku("N", 4, &[])).is_ok());
+}
@@ -0,0 +1,29 @@
+mod testdata;
+
+use convlog::*;
+use testdata::testdata;
+
+use serde_json as json;
+
+#[test]
+fn test_rename_player() {
+    let mut raw_log: tenhou::RawLog = json::from_str(testdata("ranked_game")).unwrap();
+    let names = raw_log.get_names().clone();
+
+    raw_log.rename_player(2, "Hero").unwrap();
+    let renamed = raw_log.get_names();
+    assert_eq!(renamed[2], "Hero");
+    for seat in [0, 1, 3] {
+        assert_eq!(renamed[seat], names[seat]);
+    }
+
+    let err = raw_log.rename_player(4, "Hero").unwrap_err();
+    assert_eq!((err.seat, err.players), (4, 4));
+
+    // there is no seat 3 in sanma
+    let data = testdata("ranked_game").replacen("特南喰赤", "三特南喰赤", 1);
+    let mut raw_log: tenhou::RawLog = json::from_str(&data).unwrap();
+    raw_log.rename_player(2, "Hero").unwrap();
+    let err = raw_log.rename_player(3, "Hero").unwrap_err();
+    assert_eq!((err.seat, err.players), (3, 3));
+}
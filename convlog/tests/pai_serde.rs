@@ -0,0 +1,111 @@
+use convlog::pai::{pais_to_mpsz, Hand, Mpsz};
+use convlog::Pai;
+
+use serde::{Deserialize, Serialize};
+use serde_json as json;
+use serde_with::{serde_as, DisplayFromStr};
+
+#[serde_as]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Pais {
+    tenhou: Vec<Pai>,
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    mjai: Vec<Pai>,
+    #[serde_as(as = "Vec<Mpsz>")]
+    mpsz: Vec<Pai>,
+}
+
+#[test]
+fn test_pai_encodings() {
+    let pais = vec![Pai::Man3, Pai::AkaPin5, Pai::Sou5, Pai::East, Pai::Chun];
+    let all = Pais {
+        tenhou: pais.clone(),
+        mjai: pais.clone(),
+        mpsz: pais,
+    };
+
+    let value = json::to_value(&all).unwrap();
+    assert_eq!(
+        value,
+        json::json!({
+            "tenhou": [13, 52, 35, 41, 47],
+            "mjai": ["3m", "5pr", "5s", "E", "C"],
+            "mpsz": ["3m", "0p", "5s", "1z", "7z"],
+        }),
+    );
+
+    let parsed: Pais = json::from_value(value).unwrap();
+    assert_eq!(parsed, all);
+}
+
+#[test]
+fn test_invalid_mpsz() {
+    assert_eq!(Pai::from_mpsz("0p").unwrap(), Pai::AkaPin5);
+    assert!(Pai::from_mpsz("8z").is_err());
+    assert!(Pai::from_mpsz("E").is_err());
+
+    #[serde_as]
+    #[derive(Debug, Deserialize)]
+    struct Wrapper(#[serde_as(as = "Mpsz")] Pai);
+    assert_eq!(json::from_str::<Wrapper>("\"0s\"").unwrap().0, Pai::AkaSou5);
+    assert!(json::from_str::<Wrapper>("\"0z\"").is_err());
+}
+
+#[test]
+fn test_hand_notation() {
+    let hand: Hand = "123m456p789s11z 5m".parse().unwrap();
+    assert_eq!(hand.tehai.len(), 11);
+    assert_eq!(hand.tehai[..3], [Pai::Man1, Pai::Man2, Pai::Man3]);
+    assert_eq!(hand.tehai[9..], [Pai::East, Pai::East]);
+    assert_eq!(hand.tsumo, Some(Pai::Man5));
+    assert_eq!(hand.to_string(), "123m456p789s11z 5m");
+
+    // aka, no tsumo, and a suit showing up twice keeps its order
+    let hand: Hand = "0m067p0s1m77z".parse().unwrap();
+    assert_eq!(
+        hand.tehai,
+        [
+            Pai::AkaMan5,
+            Pai::AkaPin5,
+            Pai::Pin6,
+            Pai::Pin7,
+            Pai::AkaSou5,
+            Pai::Man1,
+            Pai::Chun,
+            Pai::Chun,
+        ],
+    );
+    assert_eq!(hand.tsumo, None);
+    assert_eq!(hand.to_string(), "0m067p0s1m77z");
+
+    assert_eq!(pais_to_mpsz(&[Pai::Sou1, Pai::Unknown, Pai::Sou2]), "1s?2s");
+
+    for invalid in ["123", "m123m", "8z", "123m 5m 6m", "123m 56m"] {
+        assert!(
+            invalid.parse::<Hand>().is_err(),
+            "{:?} should not parse",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn test_distance() {
+    let pai = |s| Pai::from_mpsz(s).unwrap();
+
+    assert_eq!(pai("2m").distance(pai("5m")), Some(3));
+    assert_eq!(pai("9s").distance(pai("1s")), Some(8));
+    assert_eq!(pai("4p").distance(pai("4p")), Some(0));
+    // aka are normal 5s
+    assert_eq!(pai("0p").distance(pai("3p")), Some(2));
+    assert_eq!(pai("0s").distance(pai("5s")), Some(0));
+
+    // across suits
+    assert_eq!(pai("3m").distance(pai("3p")), None);
+    assert_eq!(pai("9m").distance(pai("1p")), None);
+    // jihai and unknown
+    assert_eq!(pai("1z").distance(pai("2z")), None);
+    assert_eq!(pai("7z").distance(pai("7z")), None);
+    assert_eq!(pai("1s").distance(pai("1z")), None);
+    assert_eq!(Pai::Unknown.distance(pai("1m")), None);
+}
@@ -1,20 +1,159 @@
 mod testdata;
 
 use convlog::*;
-use testdata::{TestCase, TESTDATA};
+use testdata::{testdata, TestCase, TESTDATA};
+
+use serde_json as json;
 
 #[test]
 fn test_parse_and_convert() {
     TESTDATA.iter().for_each(|TestCase { description, data }| {
-        let tenhou_log = tenhou::Log::from_json_str(data).expect(&*format!(
-            "failed to parse tenhou log (case: {})",
-            description
-        ));
-        let mjai_log = tenhou_to_mjai(&tenhou_log).expect(&*format!(
-            "failed to transform tenhou log (case: {})",
-            description
-        ));
+        let tenhou_log = tenhou::Log::from_json_str(data)
+            .unwrap_or_else(|_| panic!("failed to parse tenhou log (case: {})", description));
+        let mjai_log = tenhou_to_mjai(&tenhou_log)
+            .unwrap_or_else(|_| panic!("failed to transform tenhou log (case: {})", description));
 
         assert!(mjai_log.len() >= 4);
     });
 }
+
+#[test]
+fn test_strict_tsumogiri() {
+    let options = ConvertOptions {
+        strict_tsumogiri: true,
+    };
+    TESTDATA.iter().for_each(|TestCase { description, data }| {
+        let tenhou_log = tenhou::Log::from_json_str(data).unwrap();
+        let strict = tenhou_to_mjai_with_options(&tenhou_log, options)
+            .unwrap_or_else(|err| panic!("case {}: {}", description, err));
+        assert_eq!(strict, tenhou_to_mjai(&tenhou_log).unwrap());
+    });
+
+    // drop the draw of the first tsumogiri and every draw after it
+    let data = testdata("ryukyoku");
+    let mut tenhou_log = tenhou::Log::from_json_str(data).unwrap();
+    let (seat, turn) = tenhou_log.kyokus[0]
+        .action_tables
+        .iter()
+        .enumerate()
+        .find_map(|(seat, table)| {
+            let turn = table
+                .discards
+                .iter()
+                .position(|d| matches!(d, tenhou::ActionItem::Tsumogiri(_)))?;
+            Some((seat, turn))
+        })
+        .unwrap();
+    tenhou_log.kyokus[0].action_tables[seat]
+        .takes
+        .truncate(turn);
+
+    let err = tenhou_to_mjai_with_options(&tenhou_log, options).unwrap_err();
+    match err {
+        ConvertError::UnresolvableTsumogiri {
+            kyoku,
+            honba,
+            seat: err_seat,
+            turn: err_turn,
+        } => assert_eq!(
+            (kyoku, honba, err_seat as usize, err_turn),
+            (0, 1, seat, turn),
+        ),
+        _ => panic!("unexpected error: {}", err),
+    }
+
+    // the default is still lenient and fails later, if at all
+    let lenient = tenhou_to_mjai(&tenhou_log);
+    assert!(!matches!(
+        lenient,
+        Err(ConvertError::UnresolvableTsumogiri { .. })
+    ));
+}
+
+#[test]
+fn test_unknown_riichi_notation() {
+    // tenhou has no notation for open riichi, an unknown letter before a
+    // discard is read as a riichi with a warning
+    let data = testdata("four_reach").replacen("\"r22\"", "\"o22\"", 1);
+    let tenhou_log = tenhou::Log::from_json_str(&data).unwrap();
+    assert_eq!(
+        tenhou_log.warnings,
+        [tenhou::Warning::UnknownRiichiNotation {
+            kyoku: 2,
+            honba: 0,
+            seat: 0,
+            marker: 'o',
+        }],
+    );
+    assert_eq!(tenhou_log.kyokus[0].riichi_count(), 4);
+
+    let expected = tenhou::Log::from_json_str(testdata("four_reach")).unwrap();
+    assert!(expected.warnings.is_empty());
+    assert_eq!(
+        tenhou_to_mjai(&tenhou_log).unwrap(),
+        tenhou_to_mjai(&expected).unwrap(),
+    );
+}
+
+#[test]
+fn test_malformed_riichi_notation() {
+    // only the reading of a raw log rewrites an unknown riichi notation, any
+    // other malformed naki of 3 letters is still an error
+    let mut tenhou_log = tenhou::Log::from_json_str(testdata("four_reach")).unwrap();
+    let discards = &mut tenhou_log.kyokus[0].action_tables[0].discards;
+    let riichi = discards
+        .iter()
+        .position(|item| matches!(item, tenhou::ActionItem::Naki(n) if n == "r22"))
+        .unwrap();
+    discards[riichi] = tenhou::ActionItem::Naki("o22".to_owned());
+
+    let err = tenhou_to_mjai(&tenhou_log).unwrap_err();
+    assert!(
+        matches!(&err, ConvertError::InvalidNaki(naki) if naki == "o22"),
+        "{}",
+        err,
+    );
+}
+
+#[test]
+fn test_deaka() {
+    TESTDATA.iter().for_each(|TestCase { description, data }| {
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        let mut deaka_log = log.clone();
+        deaka_log.deaka();
+        assert!(!deaka_log.has_aka);
+
+        // the same events with every aka written as its normal 5
+        let expected = json::to_string(&tenhou_to_mjai(&log).unwrap())
+            .unwrap()
+            .replace("\"5mr\"", "\"5m\"")
+            .replace("\"5pr\"", "\"5p\"")
+            .replace("\"5sr\"", "\"5s\"")
+            .replace("\"aka_flag\":true", "\"aka_flag\":false");
+        let actual = json::to_string(&tenhou_to_mjai(&deaka_log).unwrap()).unwrap();
+        assert_eq!(actual, expected, "case {}", description);
+    });
+
+    // the aka in naki strings go too, with the letters kept in place
+    let mut log = tenhou::Log::from_json_str(testdata("confusing_nakis_0")).unwrap();
+    let nakis = |log: &tenhou::Log| -> Vec<String> {
+        log.kyokus
+            .iter()
+            .flat_map(|k| &k.action_tables)
+            .flat_map(|t| t.takes.iter().chain(&t.discards))
+            .filter_map(|item| match item {
+                tenhou::ActionItem::Naki(naki) => Some(naki.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+    let before = nakis(&log);
+    assert!(before.iter().any(|n| n == "k51151515"));
+    assert!(before.iter().any(|n| n == "c365337"));
+
+    log.deaka();
+    let after = nakis(&log);
+    assert_eq!(after.len(), before.len());
+    assert!(after.iter().any(|n| n == "k15151515"));
+    assert!(after.iter().any(|n| n == "c363537"));
+}
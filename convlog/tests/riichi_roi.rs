@@ -0,0 +1,85 @@
+mod testdata;
+
+use convlog::tenhou::{RiichiRoi, UraDoraStats};
+use convlog::*;
+use testdata::testdata;
+
+#[test]
+fn test_riichi_roi_won_and_lost() {
+    let log = tenhou::Log::from_json_str(testdata("double_ron")).unwrap();
+
+    // seat 0 wins the 跳満 with riichi
+    let roi = log.riichi_roi(0);
+    assert_eq!(
+        roi,
+        RiichiRoi {
+            riichi_count: 1,
+            won: 1,
+            dealt_in: 0,
+            other: 0,
+            net: 12000,
+        },
+    );
+    assert_eq!(roi.invested(), 1000);
+    assert_eq!(roi.roi(), Some(12.));
+
+    // seat 3 deals in both rons with the pai declaring riichi, which is then
+    // not paid for, so nothing is invested
+    assert!(log.kyokus[0].action_tables[3].riichi_turn().is_some());
+    assert!(!log.kyokus[0].paid_riichi(3));
+    let roi = log.riichi_roi(3);
+    assert_eq!(roi, RiichiRoi::default());
+    assert_eq!((roi.invested(), roi.roi()), (0, None));
+}
+
+#[test]
+fn test_riichi_roi_drawn() {
+    let log = tenhou::Log::from_json_str(testdata("four_reach")).unwrap();
+    for seat in 0..4 {
+        let roi = log.riichi_roi(seat);
+        assert_eq!((roi.riichi_count, roi.other, roi.net), (1, 1, -1000));
+        assert_eq!(roi.roi(), Some(-1.));
+    }
+
+    let log = tenhou::Log::from_json_str(testdata("rinshan")).unwrap();
+    let roi = log.riichi_roi(0);
+    assert_eq!(roi, RiichiRoi::default());
+    assert_eq!(roi.roi(), None);
+}
+
+#[test]
+fn test_ura_dora_two_hits() {
+    let mut log = tenhou::Log::from_json_str(testdata("double_ron")).unwrap();
+    // the log records no ura, so reveal 8m and 9p, which makes the pair of 9m
+    // in the riichi win of seat 0 two ura
+    log.kyokus[0].ura_indicators = vec![Pai::Man8, Pai::Pin9];
+
+    assert_eq!(log.kyokus[0].ura_dora_count(0).unwrap(), Some(2));
+    // seat 2 wins without riichi, and seat 3 deals in
+    assert_eq!(log.kyokus[0].ura_dora_count(2).unwrap(), None);
+    assert_eq!(log.kyokus[0].ura_dora_count(3).unwrap(), None);
+
+    let stats = log.ura_dora_stats(0).unwrap();
+    assert_eq!(
+        stats,
+        UraDoraStats {
+            riichi_wins: 1,
+            hits: 1,
+            total_ura: 2,
+        },
+    );
+    assert_eq!(stats.hit_rate(), Some(1.));
+    assert_eq!(stats.average(), Some(2.));
+}
+
+#[test]
+fn test_ura_dora_stats() {
+    // 裏ドラ(4飜) for the riichi ron of seat 0 in the last kyoku
+    let log = tenhou::Log::from_json_str(testdata("suukantsu_1")).unwrap();
+    let stats = log.ura_dora_stats(0).unwrap();
+    assert_eq!((stats.riichi_wins, stats.hits, stats.total_ura), (1, 1, 4));
+
+    let stats = log.ura_dora_stats(1).unwrap();
+    assert_eq!(stats, UraDoraStats::default());
+    assert_eq!((stats.hit_rate(), stats.average()), (None, None));
+}
@@ -0,0 +1,245 @@
+mod testdata;
+
+use convlog::board::LiveTiles;
+use convlog::mjai::Event;
+use convlog::safety::{DealInDanger, SafetyClass, SujiKind};
+use convlog::*;
+use testdata::testdata;
+
+/// Returns the danger of the first ron in the log.
+fn first_deal_in_danger(description: &str) -> DealInDanger {
+    let log = tenhou::Log::from_json_str(testdata(description)).unwrap();
+    let events = tenhou_to_mjai(&log).unwrap();
+    (0..events.len())
+        .find_map(|i| safety::deal_in_danger(&events, i))
+        .unwrap()
+}
+
+#[test]
+fn test_classify() {
+    let genbutsu = [Pai::Pin1, Pai::Pin7, Pai::Man4, Pai::East];
+
+    assert_eq!(
+        safety::classify(Pai::Pin1, &genbutsu),
+        SafetyClass::Genbutsu
+    );
+    assert_eq!(
+        safety::classify(Pai::East, &genbutsu),
+        SafetyClass::Genbutsu
+    );
+    assert_eq!(safety::classify(Pai::Pin4, &genbutsu), SafetyClass::Suji);
+    assert_eq!(safety::classify(Pai::Man1, &genbutsu), SafetyClass::Suji);
+    assert_eq!(safety::classify(Pai::Man7, &genbutsu), SafetyClass::Suji);
+    assert_eq!(safety::classify(Pai::Pin5, &genbutsu), SafetyClass::Musuji);
+    assert_eq!(safety::classify(Pai::South, &genbutsu), SafetyClass::Jihai);
+
+    // 4m is only half suji with 1m alone
+    assert_eq!(
+        safety::classify(Pai::Man4, &[Pai::Man1]),
+        SafetyClass::Musuji
+    );
+    assert_eq!(
+        safety::classify(Pai::AkaSou5, &[Pai::Sou2, Pai::Sou8]),
+        SafetyClass::Suji,
+    );
+}
+
+#[test]
+fn test_genbutsu_after_riichi() {
+    let dahai = |actor, pai| Event::Dahai {
+        actor,
+        pai,
+        tsumogiri: true,
+    };
+    let events = [
+        dahai(0, Pai::Man1),
+        dahai(1, Pai::Man2),
+        Event::Reach { actor: 0 },
+        dahai(0, Pai::Man3),
+        Event::ReachAccepted { actor: 0 },
+        dahai(1, Pai::Man4),
+    ];
+    assert_eq!(
+        safety::genbutsu(&events, 0),
+        [Pai::Man1, Pai::Man3, Pai::Man4],
+    );
+    assert_eq!(safety::genbutsu(&events, 1), [Pai::Man2, Pai::Man4]);
+}
+
+#[test]
+fn test_deal_in_danger() {
+    // seat 2 dealt 9p into the 369p wait of seat 3
+    let danger = first_deal_in_danger("ranked_game");
+    assert_eq!(
+        danger,
+        DealInDanger {
+            pai: Pai::Pin9,
+            winner: 3,
+            class: SafetyClass::Musuji,
+            waits: vec![Pai::Pin3, Pai::Pin6, Pai::Pin9],
+            live_waits: 7,
+            suji_risk: None,
+        },
+    );
+
+    // seat 1 dealt suji 2s into a tanki wait
+    let danger = first_deal_in_danger("suukantsu_0");
+    assert_eq!(danger.pai, Pai::Sou2);
+    assert_eq!(danger.class, SafetyClass::Suji);
+    assert_eq!(danger.waits, [Pai::Sou2]);
+    assert_eq!(danger.suji_risk.unwrap().kind, SujiKind::Suji);
+
+    // a tsumo has no deal-in
+    let log = tenhou::Log::from_json_str(testdata("rinshan")).unwrap();
+    let events = tenhou_to_mjai(&log).unwrap();
+    assert!((0..events.len()).all(|i| safety::deal_in_danger(&events, i).is_none()));
+}
+
+#[test]
+fn test_waits() {
+    // 123m456p789s11z + 23s waits 1s and 4s
+    let mut tehai = [0; 34];
+    for pai in [
+        Pai::Man1,
+        Pai::Man2,
+        Pai::Man3,
+        Pai::Pin4,
+        Pai::Pin5,
+        Pai::Pin6,
+        Pai::Sou7,
+        Pai::Sou8,
+        Pai::Sou9,
+        Pai::East,
+        Pai::East,
+        Pai::Sou2,
+        Pai::Sou3,
+    ] {
+        tehai[pai.as_index34().unwrap()] += 1;
+    }
+    let waits: Vec<_> = shanten::waits(&tehai, 0)
+        .into_iter()
+        .filter_map(Pai::from_index34)
+        .collect();
+    assert_eq!(waits, [Pai::Sou1, Pai::Sou4]);
+
+    assert_eq!(Pai::from_index34(33), Some(Pai::Chun));
+    assert_eq!(Pai::from_index34(34), None);
+}
+
+#[test]
+fn test_suji_kind() {
+    let genbutsu = [Pai::Pin1, Pai::Pin7, Pai::Man4, Pai::East];
+
+    assert_eq!(
+        safety::suji_kind(Pai::Pin4, &genbutsu),
+        Some(SujiKind::NakaSuji)
+    );
+    assert_eq!(
+        safety::suji_kind(Pai::Man1, &genbutsu),
+        Some(SujiKind::Suji)
+    );
+    assert_eq!(
+        safety::suji_kind(Pai::Man7, &genbutsu),
+        Some(SujiKind::Suji)
+    );
+    assert_eq!(safety::suji_kind(Pai::Man5, &genbutsu), None);
+    assert_eq!(safety::suji_kind(Pai::Pin1, &genbutsu), None);
+    assert_eq!(safety::suji_kind(Pai::South, &genbutsu), None);
+    // only half of 4p
+    assert_eq!(
+        safety::suji_kind(Pai::Pin4, &[Pai::Pin1]),
+        Some(SujiKind::Katasuji)
+    );
+}
+
+#[test]
+fn test_suji_risk() {
+    // 1p against 4p, with three 1p visible: no kanchan, penchan nor shanpon
+    let genbutsu = [Pai::Pin4];
+    let live = LiveTiles::from_visible(&[Pai::Pin1, Pai::Pin1, Pai::Pin1]);
+    let risk = safety::suji_risk(Pai::Pin1, &genbutsu, &live).unwrap();
+    assert_eq!(risk.kind, SujiKind::Suji);
+    assert!(!risk.kanchan && !risk.penchan && !risk.shanpon);
+    assert!(!risk.is_trap());
+
+    // naka-suji 5s against 2s and 8s, still open to a kanchan 46s
+    let genbutsu = [Pai::Sou2, Pai::Sou8];
+    let live = LiveTiles::from_visible(&[Pai::Sou5, Pai::Sou5, Pai::Sou5]);
+    let risk = safety::suji_risk(Pai::AkaSou5, &genbutsu, &live).unwrap();
+    assert_eq!(risk.kind, SujiKind::NakaSuji);
+    assert!(risk.kanchan && !risk.shanpon);
+    assert!(risk.is_trap());
+
+    // no chance on 6s leaves no kanchan
+    let live = LiveTiles::from_visible(&[Pai::Sou6; 4]);
+    let risk = safety::suji_risk(Pai::Sou5, &genbutsu, &live).unwrap();
+    assert!(!risk.kanchan);
+    assert!(risk.shanpon);
+
+    // 3m against 6m can be a penchan 12m, but not once 2m are all visible
+    let genbutsu = [Pai::Man6];
+    let risk = safety::suji_risk(
+        Pai::Man3,
+        &genbutsu,
+        &LiveTiles::from_visible(&[Pai::Man3; 3]),
+    )
+    .unwrap();
+    assert!(risk.penchan && risk.kanchan);
+    let risk = safety::suji_risk(
+        Pai::Man3,
+        &genbutsu,
+        &LiveTiles::from_visible(&[Pai::Man2; 4]),
+    )
+    .unwrap();
+    assert!(!risk.penchan && !risk.kanchan);
+
+    assert_eq!(
+        safety::suji_risk(Pai::Man5, &genbutsu, &LiveTiles::from_visible(&[])),
+        None
+    );
+}
+
+#[test]
+fn test_fold_path() {
+    // 5p is musuji against both, and 9s is only genbutsu against the second
+    let tehai = [Pai::Pin5, Pai::East, Pai::Sou9, Pai::Man1, Pai::East];
+    let genbutsus = [
+        vec![Pai::East, Pai::Man1],
+        vec![Pai::East, Pai::Man1, Pai::Sou9],
+    ];
+    let path = safety::fold_path(&tehai, &genbutsus, 3);
+    assert_eq!(
+        path.order,
+        [
+            (Pai::East, SafetyClass::Genbutsu),
+            (Pai::Man1, SafetyClass::Genbutsu),
+            (Pai::East, SafetyClass::Genbutsu),
+            (Pai::Pin5, SafetyClass::Musuji),
+            (Pai::Sou9, SafetyClass::Musuji),
+        ],
+    );
+    // exactly enough genbutsu to the draw, but not with one more turn
+    assert_eq!(path.safe_turns, 3);
+    assert!(path.survives());
+    assert!(!safety::fold_path(&tehai, &genbutsus, 4).survives());
+}
+
+#[test]
+fn test_fold_path_at() {
+    // seat 3 is about to discard for its riichi against the riichi of seat
+    // 0, with 32 pais left in the wall, 8 of which are for seat 3
+    let log = tenhou::Log::from_json_str(testdata("double_ron")).unwrap();
+    let events = tenhou_to_mjai(&log).unwrap();
+    let reach = events
+        .iter()
+        .position(|ev| *ev == Event::Reach { actor: 3 })
+        .unwrap();
+    let path = safety::fold_path_at(&events[..reach], 3).unwrap();
+    assert_eq!((path.safe_turns, path.turns_left), (3, 9));
+    let safest: Vec<_> = path.order[..3].iter().map(|&(pai, _)| pai).collect();
+    assert_eq!(safest, [Pai::AkaSou5, Pai::Sou5, Pai::Sou1]);
+    assert!(!path.survives());
+
+    // no one else is in riichi for seat 0
+    assert_eq!(safety::fold_path_at(&events[..reach], 0), None);
+}
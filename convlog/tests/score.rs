@@ -0,0 +1,239 @@
+mod testdata;
+
+use convlog::score::{score, NotenExposure, ScorePayment, ScoreRule};
+use convlog::tenhou;
+use convlog::tenhou::kyoku::EndStatus;
+use convlog::tenhou::GameKind;
+use testdata::{testdata, TestCase, TESTDATA};
+
+#[test]
+fn test_score_non_mangan() {
+    assert_eq!(score(1, 30, false, false), ScorePayment::Ron(1000));
+    assert_eq!(score(1, 30, true, false), ScorePayment::Ron(1500));
+    assert_eq!(score(2, 25, false, false), ScorePayment::Ron(1600));
+    assert_eq!(score(3, 40, false, false), ScorePayment::Ron(5200));
+    assert_eq!(score(4, 30, false, false), ScorePayment::Ron(7700));
+    assert_eq!(score(4, 30, true, false), ScorePayment::Ron(11600));
+    assert_eq!(score(3, 60, false, false), ScorePayment::Ron(7700));
+
+    assert_eq!(
+        score(1, 30, false, true),
+        ScorePayment::Tsumo {
+            dealer: 500,
+            non_dealer: 300,
+        },
+    );
+    assert_eq!(score(2, 20, true, true), ScorePayment::DealerTsumo(700));
+    assert_eq!(
+        score(4, 30, false, true),
+        ScorePayment::Tsumo {
+            dealer: 3900,
+            non_dealer: 2000,
+        },
+    );
+}
+
+#[test]
+fn test_score_kiriage_mangan() {
+    let rule = ScoreRule {
+        kiriage_mangan: true,
+        ..ScoreRule::default()
+    };
+    assert_eq!(rule.score(4, 30, false, false), ScorePayment::Ron(8000));
+    assert_eq!(rule.score(3, 60, true, false), ScorePayment::Ron(12000));
+    assert_eq!(
+        rule.score(4, 30, true, true),
+        ScorePayment::DealerTsumo(4000)
+    );
+    // 3han 50fu does not reach 1920 base points
+    assert_eq!(rule.score(3, 50, false, false), ScorePayment::Ron(6400));
+}
+
+#[test]
+fn test_score_limit_hands() {
+    // mangan by fu
+    assert_eq!(score(4, 40, false, false), ScorePayment::Ron(8000));
+    assert_eq!(score(3, 70, false, false), ScorePayment::Ron(8000));
+
+    assert_eq!(score(5, 30, false, false), ScorePayment::Ron(8000));
+    assert_eq!(score(6, 30, false, false), ScorePayment::Ron(12000));
+    assert_eq!(score(7, 30, true, false), ScorePayment::Ron(18000));
+    assert_eq!(score(8, 30, false, false), ScorePayment::Ron(16000));
+    assert_eq!(score(10, 30, true, true), ScorePayment::DealerTsumo(8000));
+    assert_eq!(score(11, 30, false, false), ScorePayment::Ron(24000));
+    assert_eq!(score(13, 30, false, false), ScorePayment::Ron(32000));
+    assert_eq!(
+        score(26, 30, false, true),
+        ScorePayment::Tsumo {
+            dealer: 16000,
+            non_dealer: 8000,
+        },
+    );
+}
+
+#[test]
+fn test_score_deltas() {
+    let payment = score(1, 30, false, true);
+    assert_eq!(payment.total(), 1100);
+    assert_eq!(payment.deltas(1, 1, 2), [-300, 1100, -500, -300]);
+
+    let payment = score(3, 30, true, false);
+    assert_eq!(payment.total(), 5800);
+    assert_eq!(payment.deltas(0, 3, 0), [5800, 0, 0, -5800]);
+
+    let payment = score(5, 30, true, true);
+    assert_eq!(payment.deltas(2, 2, 2), [-4000, -4000, 12000, -4000]);
+}
+
+#[test]
+fn test_honba_bonus() {
+    let rule = ScoreRule::default();
+    let ron = score(1, 30, false, false);
+    assert_eq!(rule.deltas(ron, 1, 2, 0, 2), [0, 1600, -1600, 0]);
+    let tsumo = score(1, 30, false, true);
+    assert_eq!(rule.deltas(tsumo, 1, 1, 0, 2), [-700, 1700, -500, -500]);
+
+    // +1500 per honba
+    let rule = ScoreRule {
+        honba_bonus: 1500,
+        ..ScoreRule::default()
+    };
+    assert_eq!(rule.deltas(ron, 1, 2, 0, 2), [0, 4000, -4000, 0]);
+    assert_eq!(rule.deltas(tsumo, 1, 1, 0, 1), [-1000, 2600, -800, -800]);
+    assert_eq!(rule.deltas(tsumo, 1, 1, 0, 0), tsumo.deltas(1, 1, 0));
+}
+
+#[test]
+fn test_is_renchan() {
+    let rule = ScoreRule::default();
+
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        for pair in log.kyokus.windows(2) {
+            let expected = pair[0].meta.kyoku_num == pair[1].meta.kyoku_num;
+            assert_eq!(rule.is_renchan(&pair[0]), expected, "case: {}", description);
+        }
+    }
+
+    // seat 0 is the dealer and tenpai
+    let tenhou_log = tenhou::Log::from_json_str(testdata("ryukyoku")).unwrap();
+    let kyoku = &tenhou_log.kyokus[0];
+    assert!(rule.is_renchan(kyoku));
+
+    let rule = ScoreRule {
+        renchan_on_tenpai: false,
+        ..ScoreRule::default()
+    };
+    assert!(!rule.is_renchan(kyoku));
+}
+
+#[test]
+fn test_deltas_with_pao() {
+    let rule = ScoreRule::default();
+
+    // non-dealer daisangen by seat 0, seat 3 fed the last dragon
+    let tsumo = score(13, 30, false, true);
+    assert_eq!(
+        rule.deltas_with_pao(tsumo, 0, 0, 0, 3),
+        [32000, 0, 0, -32000]
+    );
+    assert_eq!(
+        rule.deltas_with_pao(tsumo, 0, 0, 1, 3),
+        [32300, 0, 0, -32300]
+    );
+
+    let ron = score(13, 30, false, false);
+    assert_eq!(
+        rule.deltas_with_pao(ron, 0, 1, 1, 3),
+        [32300, -16300, 0, -16000],
+    );
+    // dealt in by the liable player
+    assert_eq!(
+        rule.deltas_with_pao(ron, 0, 3, 0, 3),
+        rule.deltas(ron, 0, 3, 1, 0),
+    );
+
+    // dealer daisangen
+    let tsumo = score(13, 30, true, true);
+    assert_eq!(
+        rule.deltas_with_pao(tsumo, 1, 1, 0, 2),
+        [0, 48000, -48000, 0]
+    );
+}
+
+#[test]
+fn test_sanma_tsumo() {
+    let rule = ScoreRule {
+        game_kind: GameKind::Sanma,
+        ..ScoreRule::default()
+    };
+
+    // the dealer gets 3900 from each of the two others
+    let payment = score(4, 30, true, true);
+    assert_eq!(payment.total_in(GameKind::Sanma), 7800);
+    assert_eq!(rule.deltas(payment, 0, 0, 0, 0), [7800, -3900, -3900, 0]);
+    assert_eq!(rule.deltas(payment, 0, 0, 0, 1), [8100, -4050, -4050, 0]);
+
+    // the share of the missing non-dealer is lost
+    let payment = score(3, 30, false, true);
+    assert_eq!(rule.deltas(payment, 1, 1, 0, 0), [-2000, 3000, -1000, 0]);
+    assert_eq!(payment.total(), 4000);
+
+    // ron is the same as in yonma
+    let payment = score(3, 30, false, false);
+    assert_eq!(
+        rule.deltas(payment, 1, 2, 0, 0),
+        ScoreRule::default().deltas(payment, 1, 2, 0, 0),
+    );
+}
+
+#[test]
+fn test_noten_exposure() {
+    let rule = ScoreRule::default();
+    assert_eq!(
+        rule.noten_deltas([true, false, false, false]),
+        [3000, -1000, -1000, -1000]
+    );
+    assert_eq!(
+        rule.noten_deltas([true, true, true, false]),
+        [1000, 1000, 1000, -3000]
+    );
+    assert_eq!(rule.noten_deltas([true; 4]), [0; 4]);
+    assert_eq!(rule.noten_deltas([false; 4]), [0; 4]);
+
+    // seat 0 and seat 1 are tenpai at the exhaustive draw
+    let log = tenhou::Log::from_json_str(testdata("ryukyoku")).unwrap();
+    let kyoku = &log.kyokus[0];
+    let last_turn = kyoku.turn_count() - 1;
+    let (tenpai_seats, score_deltas) = match kyoku.end_status {
+        EndStatus::Ryukyoku {
+            tenpai_seats,
+            score_deltas,
+            ..
+        } => (tenpai_seats, score_deltas),
+        _ => unreachable!(),
+    };
+    assert_eq!(kyoku.tenpai_seats_at(last_turn), tenpai_seats);
+    assert_eq!(rule.noten_deltas(tenpai_seats), score_deltas);
+
+    // seat 2 is noten against two tenpai opponents, getting tenpai would
+    // turn paying 1500 into receiving 1000
+    let exposure = rule.noten_exposure(kyoku, 2, last_turn);
+    assert_eq!(
+        exposure,
+        NotenExposure {
+            hero_tenpai: false,
+            others_tenpai: 2,
+            if_tenpai: 1000,
+            if_noten: -1500,
+        },
+    );
+    assert_eq!(exposure.delta(), -1500);
+
+    let exposure = rule.noten_exposure(kyoku, 0, last_turn);
+    assert_eq!((exposure.hero_tenpai, exposure.others_tenpai), (true, 1));
+    assert_eq!((exposure.delta(), exposure.if_noten), (1500, -1000));
+
+    // nobody is tenpai right after the first discard
+    assert_eq!(rule.noten_exposure(kyoku, 2, 0).others_tenpai, 0);
+}
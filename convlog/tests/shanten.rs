@@ -0,0 +1,188 @@
+mod testdata;
+
+use convlog::board::LiveTiles;
+use convlog::tenhou::kyoku::EndStatus;
+use convlog::tenhou::{ActionItem, ActionTable};
+use convlog::*;
+use testdata::{TestCase, TESTDATA};
+
+fn pais(mpsz: &str) -> Vec<Pai> {
+    pai::pais_from_mpsz(mpsz).unwrap()
+}
+
+fn tehai(mpsz: &str) -> [u8; 34] {
+    let mut tehai = [0; 34];
+    for pai in pais(mpsz) {
+        tehai[pai.as_index34().unwrap()] += 1;
+    }
+    tehai
+}
+
+#[test]
+fn test_shanten() {
+    assert_eq!(shanten::calc(&tehai("123m456p789s1122z"), 0), 0);
+    assert_eq!(shanten::calc(&tehai("123m456p789s11222z"), 0), -1);
+    assert_eq!(shanten::calc(&tehai("1m258p369s1234567z"), 0), 4);
+    assert_eq!(shanten::calc_kokushi(&tehai("1m258p369s1234567z")), 4);
+    assert_eq!(shanten::calc(&tehai("19m19p19s1234567z"), 0), 0);
+    assert_eq!(shanten::calc(&tehai("113355779m1133p"), 0), 0);
+    assert_eq!(shanten::calc(&tehai("11335577m113p5z"), 0), 1);
+    assert_eq!(shanten::calc(&tehai("067m46p23789s"), 1), 1);
+    assert_eq!(shanten::calc(&tehai("11z"), 4), -1);
+    assert_eq!(shanten::calc(&tehai("1z"), 4), 0);
+
+    assert_eq!(shanten::calc_chiitoi(&tehai("113355m1133p579s")), 1);
+    assert_eq!(shanten::calc(&tehai("113355m1133p579s"), 0), 1);
+
+    // chiitoitsu is not counted with fuuro
+    assert_eq!(shanten::calc(&tehai("113355m1133p"), 1), 2);
+}
+
+#[test]
+fn test_trivial_discard() {
+    let east = Pai::East.as_index34().unwrap();
+    let man1 = Pai::Man1.as_index34().unwrap();
+    let starting_shanten = shanten::calc(&tehai("2355m456p2378s12z"), 0);
+
+    // a lone East after drawing a lone West
+    assert!(shanten::is_trivial_discard(
+        &tehai("2355m456p2378s123z"),
+        east,
+        starting_shanten,
+    ));
+
+    // 1m next to 2m and 3m is part of the shape
+    let starting_shanten = shanten::calc(&tehai("12355m456p2378s1z"), 0);
+    assert!(!shanten::is_trivial_discard(
+        &tehai("12355m456p2378s13z"),
+        man1,
+        starting_shanten,
+    ));
+
+    // a pair is not lone
+    let starting_shanten = shanten::calc(&tehai("2355m456p2378s11z"), 0);
+    assert!(!shanten::is_trivial_discard(
+        &tehai("2355m456p2378s113z"),
+        east,
+        starting_shanten,
+    ));
+
+    // the draw of 9s moved the hand forward, so the discard is a real choice
+    let starting_shanten = shanten::calc(&tehai("2355m456p2378s12z"), 0);
+    assert!(!shanten::is_trivial_discard(
+        &tehai("2355m456p23789s12z"),
+        east,
+        starting_shanten,
+    ));
+
+    // every yaochuu pai counts for kokushi musou
+    let starting_shanten = shanten::calc(&tehai("19m19p19s1234567z"), 0);
+    assert!(!shanten::is_trivial_discard(
+        &tehai("119m19p19s1234567z"),
+        east,
+        starting_shanten,
+    ));
+}
+
+#[test]
+fn test_tenpai_turn() {
+    // tenpai from the 2nd discard, riichi at the 5th one
+    let mut haipai = [Pai::Unknown; 13];
+    haipai.copy_from_slice(&pais("123m3456p789s115z"));
+    let table = ActionTable {
+        haipai,
+        takes: pais("9m4p7z6z1m")
+            .into_iter()
+            .map(ActionItem::Pai)
+            .collect(),
+        discards: vec![
+            ActionItem::Tsumogiri(60),
+            ActionItem::Pai(Pai::Haku),
+            ActionItem::Tsumogiri(60),
+            ActionItem::Tsumogiri(60),
+            ActionItem::Naki("r60".to_owned()),
+        ],
+    };
+
+    assert_eq!(
+        table.shanten_by_turn(),
+        vec![Some(1), Some(0), Some(0), Some(0), Some(0)],
+    );
+    assert_eq!(table.tenpai_turn(), Some(1));
+    assert_eq!(table.riichi_turn(), Some(4));
+    assert_eq!(table.riichi_delay(), Some(3));
+}
+
+#[test]
+fn test_ron_winners_are_tenpai() {
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data)
+            .unwrap_or_else(|_| panic!("failed to parse tenhou log (case: {})", description));
+
+        for kyoku in &log.kyokus {
+            let details = match &kyoku.end_status {
+                EndStatus::Hora { details } => details,
+                _ => continue,
+            };
+
+            for detail in details.iter().filter(|d| d.who != d.target) {
+                let shanten = kyoku.action_tables[detail.who as usize]
+                    .shanten_by_turn()
+                    .into_iter()
+                    .flatten()
+                    .last();
+                assert_eq!(shanten, Some(0), "case: {}", description);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_tenpai_breaks() {
+    // tenpai on 3p from the 1st discard, breaks it to fold with 1p at the
+    // 3rd one
+    let mut haipai = [Pai::Unknown; 13];
+    haipai.copy_from_slice(&pais("123m12p456p789s15z"));
+    let table = ActionTable {
+        haipai,
+        takes: pais("1z9m7z2p").into_iter().map(ActionItem::Pai).collect(),
+        discards: vec![
+            ActionItem::Pai(Pai::Haku),
+            ActionItem::Tsumogiri(60),
+            ActionItem::Pai(Pai::Pin1),
+            ActionItem::Tsumogiri(60),
+        ],
+    };
+    assert_eq!(
+        table.shanten_by_turn(),
+        vec![Some(0), Some(0), Some(1), Some(1)],
+    );
+    assert_eq!(table.tenpai_breaks(), vec![2]);
+
+    // every break is a discard right after a tenpai discard
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        for kyoku in &log.kyokus {
+            for table in &kyoku.action_tables {
+                let shanten: Vec<_> = table.shanten_by_turn().into_iter().flatten().collect();
+                let breaks = shanten.windows(2).filter(|w| w[0] <= 0 && w[1] > 0).count();
+                assert_eq!(table.tenpai_breaks().len(), breaks, "case: {}", description);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_ukeire() {
+    let tenpai = tehai("123m456p789s34s55z");
+    let live = LiveTiles::from_visible(&pais("123m456p789s34s55z"));
+    assert_eq!(shanten::ukeire(&tenpai, 0, &live), 8);
+    // 2s in a river and all 5s visible, one of them aka
+    let live = LiveTiles::from_visible(&pais("123m456p789s34s55z2s0555s"));
+    assert_eq!(shanten::ukeire(&tenpai, 0, &live), 3);
+
+    // agari already, nothing lowers it
+    let agari = tehai("123m456p789s345s55z");
+    let live = LiveTiles::from_visible(&pais("123m456p789s345s55z"));
+    assert_eq!(shanten::ukeire(&agari, 0, &live), 0);
+}
@@ -1,17 +1,15 @@
 mod testdata;
 
 use convlog::*;
-use testdata::{TestCase, TESTDATA};
+use testdata::{testdata, TestCase, TESTDATA};
 
 use serde_json as json;
 
 #[test]
 fn test_split_by_kyoku() {
     TESTDATA.iter().for_each(|TestCase { description, data }| {
-        let raw_log: tenhou::RawLog = json::from_str(data).expect(&*format!(
-            "failed to parse tenhou log (case: {})",
-            description
-        ));
+        let raw_log: tenhou::RawLog = json::from_str(data)
+            .unwrap_or_else(|_| panic!("failed to parse tenhou log (case: {})", description));
         let splited_raw_logs = raw_log.split_by_kyoku();
 
         let log = tenhou::Log::from(raw_log.clone());
@@ -26,15 +24,94 @@ fn test_split_by_kyoku() {
             ..log.clone()
         };
 
-        let mjai_log = tenhou_to_mjai(&log).expect(&*format!(
-            "failed to transform tenhou (case: {})",
-            description
-        ));
-        let mjai_log_joined = tenhou_to_mjai(&joined_logs).expect(&*format!(
-            "failed to transform tenhou (case: {})",
-            description
-        ));
+        let mjai_log = tenhou_to_mjai(&log)
+            .unwrap_or_else(|_| panic!("failed to transform tenhou (case: {})", description));
+        let mjai_log_joined = tenhou_to_mjai(&joined_logs)
+            .unwrap_or_else(|_| panic!("failed to transform tenhou (case: {})", description));
 
         assert_eq!(mjai_log, mjai_log_joined);
     });
 }
+
+#[test]
+fn test_to_standalone_log() {
+    let data = testdata("ranked_game");
+    let raw_log: tenhou::RawLog = json::from_str(data).unwrap();
+    let full_log = tenhou::Log::from(raw_log.clone());
+
+    for (index, kyoku) in full_log.kyokus.iter().enumerate() {
+        let standalone = raw_log.to_standalone_log(index).unwrap();
+        assert_eq!(standalone.len(), 1);
+
+        // round trip through JSON just like the viewer gets it
+        let json_string = json::to_string(&standalone).unwrap();
+        let log = tenhou::Log::from_json_str(&json_string).unwrap();
+        assert_eq!(log.names, full_log.names);
+        assert_eq!(log.kyokus.len(), 1);
+        assert_eq!(log.kyokus[0].meta.kyoku_num, kyoku.meta.kyoku_num);
+        assert_eq!(log.kyokus[0].meta.honba, kyoku.meta.honba);
+        assert_eq!(log.kyokus[0].scoreboard, kyoku.scoreboard);
+        assert!(log.warnings.is_empty());
+
+        tenhou_to_mjai(&log).unwrap();
+    }
+
+    assert!(raw_log.to_standalone_log(full_log.kyokus.len()).is_none());
+}
+
+#[test]
+fn test_partial_log_serialization() {
+    let data = testdata("ranked_game");
+
+    // a long log of 60 kyokus
+    let mut raw: json::Value = json::from_str(data).unwrap();
+    let kyokus = raw["log"].as_array().unwrap().clone();
+    raw["log"] = kyokus.iter().cycle().take(60).cloned().collect();
+    let raw_log: tenhou::RawLog = json::from_value(raw).unwrap();
+
+    for (index, partial_log) in raw_log.split_by_kyoku().iter().enumerate() {
+        let json_string = json::to_string(partial_log).unwrap();
+
+        // the kyokus of the parent are not written
+        assert_eq!(json_string.matches("\"log\"").count(), 1);
+        let standalone = raw_log.to_standalone_log(index).unwrap();
+        assert_eq!(json_string, json::to_string(&standalone).unwrap());
+
+        let value: json::Value = json::from_str(&json_string).unwrap();
+        assert_eq!(value["log"].as_array().unwrap().len(), 1);
+        assert_eq!(value["rule"]["disp"], "特南喰赤");
+        assert_eq!(value["dan"][2], "九段");
+    }
+}
+
+#[test]
+fn test_viewer_url() {
+    let data = testdata("ranked_game");
+    let raw_log: tenhou::RawLog = json::from_str(data).unwrap();
+    let game_info = raw_log.game_info();
+    assert_eq!(
+        game_info.log_id.as_deref(),
+        Some("2019050417gm-0029-0000-4f2a8622"),
+    );
+    assert_eq!(
+        game_info.viewer_url(2).as_deref(),
+        Some("https://tenhou.net/0/?log=2019050417gm-0029-0000-4f2a8622&tw=2"),
+    );
+
+    // the id is kept by each kyoku of a split log
+    let split: Vec<_> = raw_log
+        .split_by_kyoku()
+        .into_iter()
+        .map(tenhou::RawLog::from)
+        .collect();
+    assert!(split.iter().all(|l| l.game_info() == game_info));
+    let value = json::to_value(raw_log.split_by_kyoku()[0]).unwrap();
+    assert_eq!(value["ref"], "2019050417gm-0029-0000-4f2a8622");
+
+    // no id, or not a tenhou one
+    assert_eq!(tenhou::GameInfo::default().viewer_url(0), None);
+    let mjsoul = tenhou::GameInfo {
+        log_id: Some("210101-12345678-abcd-ef01-2345-6789abcdef01".to_owned()),
+    };
+    assert_eq!(mjsoul.viewer_url(0), None);
+}
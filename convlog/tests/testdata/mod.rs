@@ -76,6 +76,10 @@ pub const TESTDATA: &[TestCase] = &[
         description: "ryukyoku",
         data: include_str!("ryukyoku.json"),
     },
+    TestCase {
+        description: "string_score_deltas",
+        data: include_str!("string_score_deltas.json"),
+    },
     TestCase {
         description: "suukantsu_0",
         data: include_str!("suukantsu_0.json"),
@@ -85,3 +89,13 @@ pub const TESTDATA: &[TestCase] = &[
         data: include_str!("suukantsu_1.json"),
     },
 ];
+
+/// Returns the data of the test case named `description`.
+#[allow(dead_code)] // not every test file looks up a single case
+pub fn testdata(description: &str) -> &'static str {
+    TESTDATA
+        .iter()
+        .find(|c| c.description == description)
+        .map(|TestCase { data, .. }| *data)
+        .unwrap_or_else(|| panic!("no such test case: {}", description))
+}
@@ -0,0 +1,108 @@
+mod testdata;
+
+use convlog::tenhou::ActionItem;
+use convlog::*;
+use testdata::{TestCase, TESTDATA};
+
+#[test]
+fn test_tile_conservation() {
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        for kyoku in &log.kyokus {
+            kyoku
+                .check_tile_conservation()
+                .unwrap_or_else(|err| panic!("case {}: {}", description, err));
+        }
+    }
+}
+
+#[test]
+fn test_tile_conservation_duplicate() {
+    let log = tenhou::Log::from_json_str(TESTDATA[0].data).unwrap();
+    let mut kyoku = log.kyokus[0].clone();
+
+    // turn draws of seat 0 into its first haipai pai until there are 5 of it
+    let pai = kyoku.action_tables[0].haipai[0];
+    let copies = kyoku
+        .action_tables
+        .iter()
+        .flat_map(|t| &t.haipai)
+        .filter(|p| p.deaka() == pai.deaka())
+        .count();
+    let mut injected = 0;
+    for item in &mut kyoku.action_tables[0].takes {
+        if copies + injected > 4 {
+            break;
+        }
+        if let ActionItem::Pai(p) = item {
+            *p = pai.deaka();
+            injected += 1;
+        }
+    }
+
+    let err = kyoku.check_tile_conservation().unwrap_err();
+    assert_eq!(err.pai, pai.deaka());
+    assert_eq!(err.count, 5);
+}
+
+#[test]
+fn test_tile_conservation_aka() {
+    let log = tenhou::Log::from_json_str(TESTDATA[0].data).unwrap();
+    let mut kyoku = log.kyokus[0].clone();
+
+    kyoku.dora_indicators = vec![Pai::AkaPin5];
+    kyoku.ura_indicators = vec![Pai::AkaPin5];
+
+    let err = kyoku.check_tile_conservation();
+    assert!(matches!(
+        err,
+        Err(tenhou::TileConflict {
+            pai: Pai::AkaPin5,
+            count: 2,
+        })
+    ));
+}
+
+#[test]
+fn test_full_set() {
+    use convlog::pai::AkaConfig;
+    use convlog::tenhou::GameKind;
+
+    let yonma = Pai::full_set(GameKind::Yonma, &AkaConfig::STANDARD);
+    assert_eq!(yonma.len(), 136);
+    assert_eq!(yonma.iter().filter(|p| p.is_aka()).count(), 3);
+    assert_eq!(yonma.iter().filter(|&&p| p == Pai::Man5).count(), 3);
+    for kind in yonma.iter().filter_map(|p| p.as_index34()) {
+        let count = yonma
+            .iter()
+            .filter(|p| p.as_index34() == Some(kind))
+            .count();
+        assert_eq!(count, 4);
+    }
+
+    let sanma = Pai::full_set(GameKind::Sanma, &AkaConfig::STANDARD);
+    assert_eq!(sanma.len(), 108);
+    assert!(!sanma.contains(&Pai::Man2));
+    assert!(!sanma.contains(&Pai::AkaMan5));
+    assert_eq!(sanma.iter().filter(|p| p.is_aka()).count(), 2);
+
+    let no_aka = Pai::full_set(GameKind::Yonma, &AkaConfig::default());
+    assert_eq!(no_aka.len(), 136);
+    assert!(!no_aka.iter().any(|p| p.is_aka()));
+
+    // the haipai of every kyoku can be dealt from the wall
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        for kyoku in &log.kyokus {
+            let mut wall = yonma.clone();
+            for pai in kyoku.action_tables.iter().flat_map(|t| &t.haipai) {
+                let idx = wall
+                    .iter()
+                    .position(|p| p == pai)
+                    .unwrap_or_else(|| panic!("case {}: too many {}", description, pai));
+                wall.swap_remove(idx);
+            }
+            assert_eq!(wall.len(), 136 - 13 * 4);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+mod testdata;
+
+use convlog::tenhou::{ActionError, ActionItem, ImpossibleAction};
+use convlog::*;
+use testdata::{testdata, TestCase, TESTDATA};
+
+#[test]
+fn test_validate_actions() {
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        for kyoku in &log.kyokus {
+            kyoku
+                .validate_actions(log.game_kind)
+                .unwrap_or_else(|err| panic!("case {}: {}", description, err));
+        }
+    }
+}
+
+#[test]
+fn test_discard_not_in_hand() {
+    let log = tenhou::Log::from_json_str(testdata("ryukyoku")).unwrap();
+    let mut kyoku = log.kyokus[0].clone();
+
+    // discard a pai seat 0 has never held instead of its first discard
+    let table = &kyoku.action_tables[0];
+    let held: Vec<_> = table
+        .haipai
+        .iter()
+        .chain(table.takes.iter().filter_map(|item| match item {
+            ActionItem::Pai(pai) => Some(pai),
+            _ => None,
+        }))
+        .map(|p| p.deaka())
+        .collect();
+    let pai = (0..34)
+        .filter_map(Pai::from_index34)
+        .find(|p| !held.contains(p))
+        .unwrap();
+    let first_discard = kyoku.action_tables[0]
+        .discards
+        .iter_mut()
+        .position(|item| matches!(item, ActionItem::Pai(_)))
+        .unwrap();
+    kyoku.action_tables[0].discards[first_discard] = ActionItem::Pai(pai);
+
+    let err = kyoku.validate_actions(log.game_kind).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            ActionError::Impossible {
+                seat: 0,
+                kind: ImpossibleAction::NotInHand(p),
+                turn,
+            } if p == pai && turn == first_discard
+        ),
+        "{}",
+        err,
+    );
+}
+
+#[test]
+fn test_draw_after_exhaustion() {
+    let log = tenhou::Log::from_json_str(testdata("ryukyoku")).unwrap();
+    let mut kyoku = log.kyokus[0].clone();
+    kyoku.validate_actions(log.game_kind).unwrap();
+
+    // one more draw and tsumogiri for the seat after the last discarder
+    let (seat, _) = kyoku
+        .action_tables
+        .iter()
+        .enumerate()
+        .min_by_key(|(seat, t)| (t.takes.len(), *seat))
+        .unwrap();
+    let table = &mut kyoku.action_tables[seat];
+    table.takes.push(ActionItem::Pai(Pai::East));
+    table.discards.push(ActionItem::Tsumogiri(60));
+
+    let err = kyoku.validate_actions(log.game_kind).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            ActionError::Impossible {
+                kind: ImpossibleAction::WallExhausted,
+                ..
+            }
+        ),
+        "{}",
+        err,
+    );
+}
+
+#[test]
+fn test_wall_of_game_kind() {
+    // the whole wall of yonma is drawn, which is more than sanma has
+    let log = tenhou::Log::from_json_str(testdata("ryukyoku")).unwrap();
+    let kyoku = &log.kyokus[0];
+    assert_eq!(log.game_kind, tenhou::GameKind::Yonma);
+    kyoku.validate_actions(log.game_kind).unwrap();
+    let err = kyoku.validate_actions(tenhou::GameKind::Sanma).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            ActionError::Impossible {
+                kind: ImpossibleAction::WallExhausted,
+                ..
+            }
+        ),
+        "{}",
+        err,
+    );
+
+    // a yonma kyoku ending before seat 3 ever draws is still yonma
+    let data = testdata("kyushukyuhai")
+        .replacen("[[3,0,0]", "[[0,0,0]", 1)
+        .replacen("[37],[42],", "[],[],", 1);
+    let log = tenhou::Log::from_json_str(&data).unwrap();
+    assert!(log.kyokus[0].action_tables[3].takes.is_empty());
+    log.kyokus[0].validate_actions(log.game_kind).unwrap();
+}
+
+#[test]
+fn test_call_not_discarded() {
+    // the pon of 3m turned into a pon of 9m, which no one discarded, so
+    // the calls can no longer be matched with the discards
+    let data = testdata("chankan").replacen("\"13p1313\"", "\"19p1919\"", 1);
+    let log = tenhou::Log::from_json_str(&data).unwrap();
+    let err = log.kyokus[0].validate_actions(log.game_kind).unwrap_err();
+    assert!(matches!(err, ActionError::Convert(_)), "{}", err);
+}
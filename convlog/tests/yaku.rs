@@ -0,0 +1,397 @@
+mod testdata;
+
+use convlog::mjai::{Consumed2, Consumed3, Consumed4, Event};
+use convlog::*;
+use testdata::{testdata, TestCase, TESTDATA};
+
+use serde_json as json;
+
+fn dahai(actor: u8, pai: Pai) -> Event {
+    Event::Dahai {
+        actor,
+        pai,
+        tsumogiri: false,
+    }
+}
+
+fn tsumo(actor: u8, pai: Pai) -> Event {
+    Event::Tsumo { actor, pai }
+}
+
+fn hora(actor: u8, target: u8) -> Event {
+    Event::Hora {
+        actor,
+        target,
+        deltas: None,
+        ura_markers: None,
+    }
+}
+
+/// Seat 0 declares riichi, then everyone discards once.
+fn riichi_and_go_around() -> Vec<Event> {
+    vec![
+        tsumo(0, Pai::Man1),
+        Event::Reach { actor: 0 },
+        dahai(0, Pai::Man1),
+        Event::ReachAccepted { actor: 0 },
+        tsumo(1, Pai::Pin1),
+        dahai(1, Pai::Pin1),
+        tsumo(2, Pai::Pin2),
+        dahai(2, Pai::Pin2),
+        tsumo(3, Pai::Pin3),
+        dahai(3, Pai::Pin3),
+    ]
+}
+
+#[test]
+fn test_ippatsu() {
+    // tsumo on the next draw
+    let mut events = riichi_and_go_around();
+    events.push(tsumo(0, Pai::Sou1));
+    events.push(hora(0, 0));
+    assert!(yaku::is_ippatsu(&events, events.len() - 1));
+
+    // ron after the next discard
+    let mut events = riichi_and_go_around();
+    events.push(tsumo(0, Pai::Sou1));
+    events.push(dahai(0, Pai::Sou1));
+    events.push(tsumo(1, Pai::Sou2));
+    events.push(dahai(1, Pai::Sou2));
+    events.push(hora(0, 1));
+    assert!(!yaku::is_ippatsu(&events, events.len() - 1));
+}
+
+#[test]
+fn test_ippatsu_broken_by_call() {
+    // seat 3 pons the discard of seat 1
+    let mut events = riichi_and_go_around();
+    events.truncate(6);
+    events.push(Event::Pon {
+        actor: 3,
+        target: 1,
+        pai: Pai::Pin1,
+        consumed: Consumed2::from([Pai::Pin1, Pai::Pin1]),
+    });
+    events.push(dahai(3, Pai::Chun));
+    events.push(hora(0, 3));
+    assert!(!yaku::is_ippatsu(&events, events.len() - 1));
+
+    // robbing a kakan keeps ippatsu
+    let mut events = riichi_and_go_around();
+    events.push(Event::Kakan {
+        actor: 3,
+        pai: Pai::Sou5,
+        consumed: Consumed3::from([Pai::Sou5, Pai::Sou5, Pai::Sou5]),
+    });
+    events.push(hora(0, 3));
+    assert!(yaku::is_ippatsu(&events, events.len() - 1));
+
+    // also for the second winner of a double ron on it
+    let mut events = riichi_and_go_around();
+    events.push(Event::Kakan {
+        actor: 3,
+        pai: Pai::Sou5,
+        consumed: Consumed3::from([Pai::Sou5, Pai::Sou5, Pai::Sou5]),
+    });
+    events.push(hora(2, 3));
+    events.push(hora(0, 3));
+    assert!(!yaku::is_ippatsu(&events, events.len() - 2));
+    assert!(yaku::is_ippatsu(&events, events.len() - 1));
+
+    // but not for a kakan before the last discard
+    let mut events = riichi_and_go_around();
+    events.insert(
+        8,
+        Event::Kakan {
+            actor: 2,
+            pai: Pai::Sou5,
+            consumed: Consumed3::from([Pai::Sou5, Pai::Sou5, Pai::Sou5]),
+        },
+    );
+    events.push(hora(2, 3));
+    events.push(hora(0, 3));
+    assert!(!yaku::is_ippatsu(&events, events.len() - 1));
+}
+
+#[test]
+fn test_ippatsu_matches_tenhou() {
+    for TestCase { description, data } in TESTDATA {
+        let raw: json::Value = json::from_str(data).unwrap();
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        let events = tenhou_to_mjai(&log).unwrap();
+
+        // tenhou lists the yaku of each hora in "結果"
+        let tenhou_ippatsu: Vec<bool> = raw["log"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|kyoku| {
+                let results = kyoku
+                    .as_array()
+                    .unwrap()
+                    .last()
+                    .unwrap()
+                    .as_array()
+                    .unwrap();
+                results
+                    .iter()
+                    .skip(2)
+                    .step_by(2)
+                    .map(|detail| detail.to_string().contains("一発"))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let ippatsu: Vec<bool> = events
+            .iter()
+            .enumerate()
+            .filter(|(_, ev)| matches!(ev, Event::Hora { .. }))
+            .map(|(i, _)| yaku::is_ippatsu(&events, i))
+            .collect();
+
+        assert_eq!(ippatsu, tenhou_ippatsu, "case: {}", description);
+    }
+}
+
+#[test]
+fn test_next_dora() {
+    assert_eq!(Pai::Man1.next_dora(), Pai::Man2);
+    assert_eq!(Pai::AkaPin5.next_dora(), Pai::Pin6);
+    assert_eq!(Pai::Sou9.next_dora(), Pai::Sou1);
+    assert_eq!(Pai::North.next_dora(), Pai::East);
+    assert_eq!(Pai::Chun.next_dora(), Pai::Haku);
+}
+
+#[test]
+fn test_dora_han_aka() {
+    // 234m067p345678s11z
+    let hand = [
+        Pai::Man2,
+        Pai::Man3,
+        Pai::Man4,
+        Pai::AkaPin5,
+        Pai::Pin6,
+        Pai::Pin7,
+        Pai::Sou3,
+        Pai::Sou4,
+        Pai::Sou5,
+        Pai::Sou6,
+        Pai::Sou7,
+        Pai::Sou8,
+        Pai::East,
+        Pai::East,
+    ];
+
+    assert_eq!(yaku::dora_han(&hand, &[Pai::Chun], true), 1);
+    assert_eq!(yaku::dora_han(&hand, &[Pai::Chun], false), 0);
+
+    // an aka 5p still counts as a normal 5p dora without aka
+    assert_eq!(yaku::dora_han(&hand, &[Pai::Pin4], true), 2);
+    assert_eq!(yaku::dora_han(&hand, &[Pai::Pin4], false), 1);
+    assert_eq!(yaku::dora_han(&hand, &[Pai::North, Pai::Sou3], false), 3);
+}
+
+fn pon(actor: u8, target: u8, pai: Pai) -> Event {
+    Event::Pon {
+        actor,
+        target,
+        pai,
+        consumed: Consumed2::from([pai, pai]),
+    }
+}
+
+#[test]
+fn test_pao_daisangen() {
+    // seat 0 pons haku from seat 1, hatsu from seat 2 and chun from seat 3
+    let mut events = vec![
+        pon(0, 1, Pai::Haku),
+        dahai(0, Pai::Man1),
+        pon(0, 2, Pai::Hatsu),
+        dahai(0, Pai::Man2),
+        pon(0, 3, Pai::Chun),
+        dahai(0, Pai::Man3),
+        tsumo(1, Pai::Pin1),
+        dahai(1, Pai::Pin1),
+    ];
+
+    let mut ron = events.clone();
+    ron.push(hora(0, 1));
+    assert_eq!(yaku::pao_seat(&ron, ron.len() - 1), Some(3));
+
+    events.push(tsumo(0, Pai::Sou1));
+    events.push(hora(0, 0));
+    assert_eq!(yaku::pao_seat(&events, events.len() - 1), Some(3));
+
+    // the third dragon by ankan holds nobody liable
+    let mut events = vec![
+        pon(0, 1, Pai::Haku),
+        dahai(0, Pai::Man1),
+        pon(0, 2, Pai::Hatsu),
+        dahai(0, Pai::Man2),
+        tsumo(0, Pai::Chun),
+        Event::Ankan {
+            actor: 0,
+            consumed: Consumed4::from([Pai::Chun; 4]),
+        },
+        tsumo(0, Pai::Sou1),
+        hora(0, 0),
+    ];
+    assert_eq!(yaku::pao_seat(&events, events.len() - 1), None);
+
+    // two dragon sets are not daisangen yet
+    events.truncate(4);
+    events.push(hora(0, 2));
+    assert_eq!(yaku::pao_seat(&events, events.len() - 1), None);
+}
+
+#[test]
+fn test_pao_matches_tenhou() {
+    for TestCase { description, data } in TESTDATA {
+        let log = tenhou::Log::from_json_str(data).unwrap();
+        let events = tenhou_to_mjai(&log).unwrap();
+
+        let tenhou_pao: Vec<_> = log
+            .kyokus
+            .iter()
+            .flat_map(|kyoku| match &kyoku.end_status {
+                tenhou::kyoku::EndStatus::Hora { details } => details.clone(),
+                _ => vec![],
+            })
+            .map(|detail| detail.pao)
+            .collect();
+
+        let pao: Vec<_> = events
+            .iter()
+            .enumerate()
+            .filter_map(|(i, ev)| match *ev {
+                Event::Hora { target, .. } => {
+                    Some(yaku::pao_seat(&events, i).filter(|&p| p != target))
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(pao, tenhou_pao, "case: {}", description);
+    }
+
+    // tenhou puts the liable seat after who and target
+    let data = testdata("ranked_game");
+    let data = data.replacen("[3,2,3", "[3,2,0", 1);
+    let log = tenhou::Log::from_json_str(&data).unwrap();
+    match &log.kyokus[0].end_status {
+        tenhou::kyoku::EndStatus::Hora { details } => assert_eq!(details[0].pao, Some(0)),
+        status => panic!("unexpected end status: {:?}", status),
+    }
+}
+
+#[test]
+fn test_yaku_names() {
+    use yaku::{Locale, Yaku};
+
+    assert_eq!(Yaku::Riichi.name(Locale::Japanese), "立直");
+    assert_eq!(Yaku::MenzenTsumo.name(Locale::Japanese), "門前清自摸和");
+    assert_eq!(Yaku::Pinfu.name(Locale::Japanese), "平和");
+    assert_eq!(Yaku::Riichi.name(Locale::English), "Riichi");
+    assert_eq!(Yaku::MenzenTsumo.name(Locale::English), "Menzen Tsumo");
+    assert_eq!(Yaku::Pinfu.name(Locale::English), "Pinfu");
+
+    assert_eq!(Yaku::from_name("役牌 發"), Some(Yaku::Hatsu));
+    assert_eq!(Yaku::from_name("Ura Dora"), Some(Yaku::UraDora));
+    assert_eq!(Yaku::from_name("役牌 発"), None);
+}
+
+#[test]
+fn test_format_yaku() {
+    use yaku::{format_yaku, Locale, Yaku};
+
+    let hand = [
+        (Yaku::AkaDora, 2),
+        (Yaku::Riichi, 1),
+        (Yaku::Honitsu, 3),
+        (Yaku::MenzenTsumo, 1),
+    ];
+    assert_eq!(
+        format_yaku(&hand, Locale::Japanese),
+        [
+            "混一色(3飜)",
+            "門前清自摸和(1飜)",
+            "立直(1飜)",
+            "赤ドラ(2飜)"
+        ],
+    );
+    assert_eq!(
+        format_yaku(&hand, Locale::English),
+        [
+            "Honitsu (3 han)",
+            "Menzen Tsumo (1 han)",
+            "Riichi (1 han)",
+            "Aka Dora (2 han)",
+        ],
+    );
+
+    // only yakuman count once there is any
+    let hand = [
+        (Yaku::Dora, 3),
+        (Yaku::Haku, 1),
+        (Yaku::Tsuuiisou, 13),
+        (Yaku::Daisangen, 13),
+        (Yaku::SuuankouTanki, 26),
+    ];
+    assert_eq!(
+        format_yaku(&hand, Locale::Japanese),
+        ["四暗刻単騎(ダブル役満)", "大三元(役満)", "字一色(役満)"],
+    );
+    assert_eq!(
+        format_yaku(&hand, Locale::English),
+        [
+            "Suuankou Tanki (double yakuman)",
+            "Daisangen (yakuman)",
+            "Tsuuiisou (yakuman)",
+        ],
+    );
+}
+
+#[test]
+fn test_format_yaku_matches_tenhou() {
+    use yaku::{format_yaku, Locale, Yaku};
+
+    for TestCase { description, data } in TESTDATA {
+        let raw: json::Value = json::from_str(data).unwrap();
+        for kyoku in raw["log"].as_array().unwrap() {
+            let results = kyoku.as_array().unwrap().last().unwrap();
+            for detail in results.as_array().unwrap().iter().skip(2).step_by(2) {
+                // e.g. [3, 2, 3, "30符4飜7700点", "役牌 發(1飜)", "混一色(2飜)"]
+                let mut tenhou_yaku: Vec<_> = detail
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .skip(4)
+                    .map(|v| v.as_str().unwrap())
+                    .collect();
+
+                let yaku: Vec<_> = tenhou_yaku
+                    .iter()
+                    .map(|s| {
+                        let (name, han) = s.trim_end_matches(')').split_once('(').unwrap();
+                        let yaku = Yaku::from_name(name)
+                            .unwrap_or_else(|| panic!("case {}: unknown yaku {}", description, s));
+                        let han = han.trim_end_matches('飜').parse().unwrap_or(13);
+                        (yaku, han)
+                    })
+                    .collect();
+
+                // logs from the English client only have the names in English
+                if tenhou_yaku
+                    .iter()
+                    .any(|s| s.starts_with(|c: char| c.is_ascii_alphabetic()))
+                {
+                    continue;
+                }
+                let mut formatted = format_yaku(&yaku, Locale::Japanese);
+                tenhou_yaku.sort_unstable();
+                formatted.sort_unstable();
+                assert_eq!(formatted, tenhou_yaku, "case: {}", description);
+            }
+        }
+    }
+}
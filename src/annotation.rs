@@ -0,0 +1,134 @@
+use crate::review::{Entry, KyokuReview};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use convlog::DecisionId;
+use serde::Deserialize;
+use serde_json as json;
+
+/// A note written by the user for a decision, to be shown alongside the
+/// review of akochan.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "RawAnnotation")]
+pub struct Annotation {
+    pub id: DecisionId,
+    pub note: String,
+}
+
+/// The decision is given either as an "id" such as "E1-0-s2-t7", or in
+/// separate fields.
+#[derive(Deserialize)]
+struct RawAnnotation {
+    #[serde(flatten)]
+    target: Target,
+    note: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Target {
+    Id {
+        id: DecisionId,
+    },
+    Fields {
+        kyoku: u8, // in tenhou.net/6 format, counts from 0
+        honba: u8,
+        seat: u8,
+        turn: u8, // same as junme of the entry
+    },
+}
+
+impl From<RawAnnotation> for Annotation {
+    fn from(raw: RawAnnotation) -> Self {
+        let id = match raw.target {
+            Target::Id { id } => id,
+            Target::Fields {
+                kyoku,
+                honba,
+                seat,
+                turn,
+            } => DecisionId::new(kyoku, honba, seat, turn),
+        };
+        Self { id, note: raw.note }
+    }
+}
+
+/// Loads annotations from a JSON array in `path`.
+pub fn load(path: &Path) -> Result<Vec<Annotation>> {
+    let file =
+        File::open(path).with_context(|| format!("failed to open annotations {:?}", path))?;
+    json::from_reader(BufReader::new(file))
+        .with_context(|| format!("failed to parse annotations {:?}", path))
+}
+
+/// Attaches each annotation to the first reviewed decision it matches, and
+/// returns the annotations that match no decision.
+pub fn apply(kyokus: &mut [KyokuReview], annotations: Vec<Annotation>) -> Vec<Annotation> {
+    let mut entries: HashMap<DecisionId, &mut Entry> = HashMap::new();
+    for kyoku_review in kyokus {
+        let (kyoku, honba) = (kyoku_review.kyoku, kyoku_review.honba);
+        for entry in &mut kyoku_review.entries {
+            let id = DecisionId::new(kyoku, honba, entry.actor, entry.junme);
+            entries.entry(id).or_insert(entry);
+        }
+    }
+
+    let mut unmatched = vec![];
+    for annotation in annotations {
+        match entries.get_mut(&annotation.id) {
+            Some(entry) => entry.note = Some(annotation.note),
+            None => unmatched.push(annotation),
+        }
+    }
+
+    unmatched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::entry;
+    use crate::review::Acceptance;
+
+    #[test]
+    fn apply_to_the_right_decision() {
+        let at = |junme| Entry {
+            junme,
+            ..entry(Acceptance::Agree, &[("N", 1.)], "N")
+        };
+        let mut kyokus = vec![
+            KyokuReview {
+                kyoku: 0,
+                entries: vec![at(3), at(7)],
+                ..Default::default()
+            },
+            KyokuReview {
+                kyoku: 1,
+                honba: 1,
+                entries: vec![at(3), at(7)],
+                ..Default::default()
+            },
+        ];
+
+        let annotations: Vec<Annotation> = json::from_str(
+            r#"[
+                {"id": "E2-1-s0-t7", "note": "by id"},
+                {"kyoku": 0, "honba": 0, "seat": 0, "turn": 3, "note": "by fields"},
+                {"id": "E2-0-s0-t7", "note": "wrong honba"}
+            ]"#,
+        )
+        .unwrap();
+        let unmatched = apply(&mut kyokus, annotations);
+
+        let notes: Vec<Vec<_>> = kyokus
+            .iter()
+            .map(|k| k.entries.iter().map(|e| e.note.as_deref()).collect())
+            .collect();
+        assert_eq!(notes, [[Some("by fields"), None], [None, Some("by id")]],);
+        assert_eq!(unmatched.len(), 1);
+        assert_eq!(unmatched[0].note, "wrong honba");
+    }
+}
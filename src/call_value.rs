@@ -0,0 +1,147 @@
+use crate::review::{compare_action_strict, Entry, KyokuReview};
+
+use convlog::mjai::Event;
+
+/// akochan's EV of a chi, pon or daiminkan the actor made against passing
+/// it, both evaluated at the same decision.
+#[derive(Debug, Clone)]
+pub struct CallValue<'a> {
+    pub kyoku: u8,
+    pub honba: u8,
+    pub entry: &'a Entry,
+    pub call_ev: f64,
+    pub pass_ev: f64,
+}
+
+impl CallValue<'_> {
+    /// How much the call gained over passing, negative if it lost.
+    #[inline]
+    pub fn gain(&self) -> f64 {
+        self.call_ev - self.pass_ev
+    }
+
+    /// Whether the call gained over passing, or broke even.
+    #[inline]
+    pub fn gained(&self) -> bool {
+        self.gain() >= 0.
+    }
+}
+
+/// Returns the value of every call in `kyokus` that akochan evaluated along
+/// with the pass.
+///
+/// Calls are skipped when akochan gives no EV for either, which happens on
+/// early turns or high shanten.
+pub fn call_values(kyokus: &[KyokuReview]) -> Vec<CallValue<'_>> {
+    kyokus
+        .iter()
+        .flat_map(|kyoku_review| {
+            kyoku_review.entries.iter().filter_map(move |entry| {
+                if !matches!(
+                    entry.actual.first(),
+                    Some(Event::Chi { .. } | Event::Pon { .. } | Event::Daiminkan { .. })
+                ) {
+                    return None;
+                }
+
+                let ev_of = |is_move: &dyn Fn(&[Event]) -> bool| {
+                    entry
+                        .details
+                        .iter()
+                        .find(|d| is_move(&d.moves))
+                        .and_then(|d| d.review.pt_exp_total)
+                };
+                let call_ev = ev_of(&|moves| compare_action_strict(&entry.actual, moves))?;
+                let pass_ev = ev_of(&|moves| moves.first() == Some(&Event::None))?;
+
+                Some(CallValue {
+                    kyoku: kyoku_review.kyoku,
+                    honba: kyoku_review.honba,
+                    entry,
+                    call_ev,
+                    pass_ev,
+                })
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::entry;
+    use crate::review::{Acceptance, DetailedAction};
+
+    use serde_json as json;
+
+    /// A hand-made entry of seat 0 ponning E from seat 1, where akochan
+    /// values the pon at `pon_ev` and passing at `pass_ev`.
+    fn pon_entry(pon_ev: Option<f64>, pass_ev: Option<f64>) -> Entry {
+        let candidate = |moves, ev| -> DetailedAction {
+            json::from_value(json::json!({
+                "moves": moves,
+                "review": {
+                    "total_houjuu_hai_prob_now": 0.,
+                    "total_houjuu_hai_value_now": 0.,
+                    "pt_exp_after": ev,
+                    "pt_exp_total": ev,
+                },
+            }))
+            .unwrap()
+        };
+        let pon = json::json!([
+            {"type": "pon", "actor": 0, "target": 1, "pai": "E", "consumed": ["E", "E"]},
+            {"type": "dahai", "actor": 0, "pai": "9s", "tsumogiri": false},
+        ]);
+        let pass = json::json!([{"type": "none"}]);
+
+        let details = vec![candidate(pon.clone(), pon_ev), candidate(pass, pass_ev)];
+        Entry {
+            actual: json::from_value(pon).unwrap(),
+            expected: details[0].moves.clone(),
+            details,
+            ..entry(Acceptance::Agree, &[("N", 1.)], "N")
+        }
+    }
+
+    fn kyokus(entries: Vec<Entry>) -> Vec<KyokuReview> {
+        vec![KyokuReview {
+            entries,
+            ..Default::default()
+        }]
+    }
+
+    #[test]
+    fn marginal_pon() {
+        let kyokus = kyokus(vec![
+            pon_entry(Some(1.002), Some(1.)),
+            pon_entry(Some(0.998), Some(1.)),
+            pon_entry(Some(1.), Some(1.)),
+        ]);
+        let values = call_values(&kyokus);
+        assert_eq!(values.len(), 3);
+
+        assert!(values[0].gain() > 0.);
+        assert!((values[0].gain() - 0.002).abs() < 1e-9);
+        assert!(values[0].gained());
+
+        assert!(values[1].gain() < 0.);
+        assert!((values[1].gain() + 0.002).abs() < 1e-9);
+        assert!(!values[1].gained());
+
+        // breaking even is not a loss
+        assert_eq!(values[2].gain(), 0.);
+        assert!(values[2].gained());
+    }
+
+    #[test]
+    fn skip_without_both_evs() {
+        let kyokus = kyokus(vec![
+            pon_entry(Some(1.), None),
+            pon_entry(None, Some(1.)),
+            // not a call
+            entry(Acceptance::Agree, &[("N", 1.)], "N"),
+        ]);
+        assert!(call_values(&kyokus).is_empty());
+    }
+}
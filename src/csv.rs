@@ -0,0 +1,162 @@
+use crate::mistake;
+use crate::review::{Entry, KyokuReview};
+use std::io::prelude::*;
+
+use anyhow::{Context, Result};
+use convlog::mjai::Event;
+
+const HEADER: &[&str] = &[
+    "game_id", "kyoku", "seat", "turn", "actual", "expected", "ev_delta", "mistake",
+];
+
+/// Writes one row per reviewed decision, with pais in mpsz notation.
+///
+/// `game_id` is left empty for anonymous reports. The EV deltas are
+/// multiplied by `ev_scale`, see [`EvUnit::scale`](crate::ev_unit::EvUnit::scale).
+/// The kyokus of several heroes go under the same header, told apart by the
+/// seat.
+pub fn write_csv<'a>(
+    mut w: impl Write,
+    game_id: &str,
+    kyokus: impl IntoIterator<Item = &'a KyokuReview>,
+    ev_scale: f64,
+) -> Result<()> {
+    writeln!(w, "{}", HEADER.join(",")).context("failed to write CSV header")?;
+
+    for kyoku_review in kyokus {
+        let label = kyoku_label(kyoku_review.kyoku, kyoku_review.honba);
+        for entry in &kyoku_review.entries {
+            let row = [
+                escape(game_id),
+                label.clone(),
+                entry.actor.to_string(),
+                entry.junme.to_string(),
+                escape(&action_to_string(&entry.actual)),
+                escape(&action_to_string(&entry.expected)),
+                format!("{:.5}", entry.dev * ev_scale),
+                mistake_flag(entry),
+            ];
+            writeln!(w, "{}", row.join(",")).context("failed to write CSV row")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns labels like "E1-0" and "S3-2".
+pub(crate) fn kyoku_label(kyoku: u8, honba: u8) -> String {
+    const BAKAZE: &[&str] = &["E", "S", "W", "N"];
+    format!(
+        "{}{}-{}",
+        BAKAZE[kyoku as usize / 4 % 4],
+        kyoku % 4 + 1,
+        honba,
+    )
+}
+
+fn mistake_flag(entry: &Entry) -> String {
+    mistake::classify(entry)
+        .map(|category| category.to_string())
+        .unwrap_or_default()
+}
+
+/// Describes an action such as "riichi 3p", "chi 4s5s 6s" and "pass".
+pub(crate) fn action_to_string(action: &[Event]) -> String {
+    let parts: Vec<_> = action
+        .iter()
+        .filter_map(|ev| {
+            let s = match *ev {
+                Event::None => "pass".to_owned(),
+                Event::Dahai { pai, .. } => pai.as_mpsz().to_owned(),
+                Event::Reach { .. } => "riichi".to_owned(),
+                Event::Chi { pai, consumed, .. } => {
+                    naki_to_string("chi", &consumed.as_array(), pai)
+                }
+                Event::Pon { pai, consumed, .. } => {
+                    naki_to_string("pon", &consumed.as_array(), pai)
+                }
+                Event::Daiminkan { pai, consumed, .. } => {
+                    naki_to_string("kan", &consumed.as_array(), pai)
+                }
+                Event::Kakan { pai, consumed, .. } => {
+                    naki_to_string("kakan", &consumed.as_array(), pai)
+                }
+                Event::Ankan { consumed, .. } => {
+                    format!("ankan {}", consumed.as_array()[0].as_mpsz())
+                }
+                Event::Hora { .. } => "hora".to_owned(),
+                Event::Ryukyoku { .. } => "ryukyoku".to_owned(),
+                _ => return None,
+            };
+            Some(s)
+        })
+        .collect();
+
+    parts.join(" ")
+}
+
+fn naki_to_string(name: &str, consumed: &[convlog::Pai], pai: convlog::Pai) -> String {
+    let consumed: String = consumed.iter().map(|p| p.as_mpsz()).collect();
+    format!("{} {} {}", name, consumed, pai.as_mpsz())
+}
+
+/// Quotes a field if it contains any of the CSV special characters.
+fn escape(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::entry;
+    use crate::review::Acceptance;
+
+    use serde_json as json;
+
+    #[test]
+    fn golden_csv() {
+        let mut push = entry(Acceptance::Disagree, &[("N", 1.), ("5mr", 0.9)], "5mr");
+        push.details[0].review.total_houjuu_hai_prob_now = Some(0.02);
+        push.details[1].review.total_houjuu_hai_prob_now = Some(0.1);
+        push.junme = 7;
+
+        let mut naki = entry(Acceptance::Disagree, &[("N", 1.)], "N");
+        naki.actor = 2;
+        naki.junme = 9;
+        naki.expected = json::from_value(json::json!([
+            {"type": "chi", "actor": 2, "target": 1, "pai": "3s", "consumed": ["4s", "5s"]},
+        ]))
+        .unwrap();
+        naki.actual = vec![Event::None];
+        naki.dev = 0.0123;
+
+        let kyokus = vec![
+            KyokuReview {
+                kyoku: 0,
+                entries: vec![entry(Acceptance::Agree, &[("N", 1.), ("S", 0.5)], "N")],
+                ..Default::default()
+            },
+            KyokuReview {
+                kyoku: 5,
+                honba: 2,
+                entries: vec![
+                    entry(Acceptance::Tolerable, &[("S", 1.), ("W", 0.999)], "W"),
+                    push,
+                    naki,
+                ],
+                ..Default::default()
+            },
+        ];
+
+        let mut out = vec![];
+        write_csv(&mut out, "2020010100gm-00a9-0000-x,\"1\"", &kyokus, 1000.).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            include_str!("testdata/report.csv"),
+        );
+    }
+}
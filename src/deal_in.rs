@@ -0,0 +1,174 @@
+use crate::mistake::{self, MistakeCategory};
+use crate::review::{Entry, KyokuReview};
+
+use convlog::mjai::Event;
+
+/// Returns the reviewed decision that dealt in, which is the last discard of
+/// the kyoku, or a kakan robbed by chankan.
+pub fn deal_in_entry(kyoku_review: &KyokuReview) -> Option<&Entry> {
+    kyoku_review.entries.iter().rev().find(|e| {
+        matches!(
+            e.actual.first(),
+            Some(Event::Dahai { .. } | Event::Reach { .. } | Event::Kakan { .. })
+        )
+    })
+}
+
+/// Returns the points `actor` paid for dealing in during the kyoku, or None
+/// if `actor` did not deal in. A double ron counts as one deal-in paying for
+/// both.
+pub fn deal_in_points(kyoku_review: &KyokuReview, actor: u8) -> Option<i32> {
+    let mut paid = None;
+    for ev in &kyoku_review.end_status {
+        if let Event::Hora {
+            actor: winner,
+            target,
+            deltas,
+            ..
+        } = *ev
+        {
+            if target == actor && winner != actor {
+                let delta = deltas.map_or(0, |d| d[actor as usize]);
+                *paid.get_or_insert(0) -= delta;
+            }
+        }
+    }
+    paid
+}
+
+/// Deal-ins of an actor summed over the kyokus of many games.
+#[derive(Debug, Clone, Default)]
+pub struct DealInStats {
+    pub kyokus: usize,
+    pub deal_ins: usize,
+    pub points_lost: i32,
+    /// Deal-ins where akochan would have folded.
+    pub avoidable: usize,
+    /// Deal-ins where akochan would have played differently but not folded.
+    pub disagreed: usize,
+    /// Deal-ins whose discard was not reviewed, such as on early turns.
+    pub not_reviewed: usize,
+}
+
+impl DealInStats {
+    pub fn add_game(&mut self, kyokus: &[KyokuReview], actor: u8) {
+        for kyoku_review in kyokus {
+            self.kyokus += 1;
+            let points = match deal_in_points(kyoku_review, actor) {
+                Some(points) => points,
+                None => continue,
+            };
+
+            self.deal_ins += 1;
+            self.points_lost += points;
+            match deal_in_entry(kyoku_review).map(mistake::classify) {
+                None => self.not_reviewed += 1,
+                Some(Some(MistakeCategory::OverPush)) => self.avoidable += 1,
+                Some(Some(_)) => self.disagreed += 1,
+                Some(None) => (),
+            }
+        }
+    }
+
+    /// Deal-ins per kyoku.
+    #[inline]
+    pub fn rate(&self) -> Option<f64> {
+        ratio(self.deal_ins as f64, self.kyokus)
+    }
+
+    #[inline]
+    pub fn avg_points_lost(&self) -> Option<f64> {
+        ratio(self.points_lost as f64, self.deal_ins)
+    }
+
+    /// Avoidable deal-ins per reviewed deal-in.
+    #[inline]
+    pub fn avoidable_fraction(&self) -> Option<f64> {
+        ratio(self.avoidable as f64, self.deal_ins - self.not_reviewed)
+    }
+}
+
+#[inline]
+fn ratio(n: f64, d: usize) -> Option<f64> {
+    if d == 0 {
+        None
+    } else {
+        Some(n / d as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::entry;
+    use crate::review::Acceptance;
+
+    use serde_json as json;
+
+    fn hora(actor: u8, target: u8, deltas: [i32; 4]) -> Event {
+        json::from_value(json::json!({
+            "type": "hora", "actor": actor, "target": target, "deltas": deltas,
+        }))
+        .unwrap()
+    }
+
+    fn kyoku(end_status: Vec<Event>, entries: Vec<Entry>) -> KyokuReview {
+        KyokuReview {
+            end_status,
+            entries,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn known_deal_ins() {
+        let mut push = entry(Acceptance::Disagree, &[("N", 1.), ("6p", 0.7)], "6p");
+        push.details[0].review.total_houjuu_hai_prob_now = Some(0.02);
+        push.details[1].review.total_houjuu_hai_prob_now = Some(0.1);
+        let agreed = || entry(Acceptance::Agree, &[("N", 1.), ("S", 0.5)], "N");
+        let draw = json::from_value(json::json!({"type": "ryukyoku"})).unwrap();
+
+        let kyokus = [
+            // over-pushed into a 7700
+            kyoku(vec![hora(2, 0, [-7700, 0, 7700, 0])], vec![agreed(), push]),
+            // a double ron, not reviewed
+            kyoku(
+                vec![
+                    hora(1, 0, [-2000, 2000, 0, 0]),
+                    hora(3, 0, [-3900, 0, 0, 3900]),
+                ],
+                vec![],
+            ),
+            // as akochan would
+            kyoku(vec![hora(3, 0, [-1000, 0, 0, 1000])], vec![agreed()]),
+            // not a deal-in of seat 0
+            kyoku(vec![hora(1, 1, [-1000, 2000, -500, -500])], vec![agreed()]),
+            kyoku(vec![hora(2, 3, [0, 0, 1000, -1000])], vec![agreed()]),
+            kyoku(vec![draw], vec![agreed()]),
+        ];
+
+        assert_eq!(deal_in_points(&kyokus[0], 0), Some(7700));
+        assert_eq!(deal_in_points(&kyokus[0], 2), None);
+        assert_eq!(deal_in_points(&kyokus[1], 0), Some(5900));
+        assert_eq!(deal_in_points(&kyokus[3], 0), None);
+        assert_eq!(deal_in_points(&kyokus[3], 1), None);
+        assert_eq!(deal_in_points(&kyokus[4], 3), Some(1000));
+        assert_eq!(
+            deal_in_entry(&kyokus[0]).unwrap().pai,
+            "6p".parse().unwrap()
+        );
+        assert!(deal_in_entry(&kyokus[1]).is_none());
+
+        let mut stats = DealInStats::default();
+        stats.add_game(&kyokus, 0);
+        assert_eq!(stats.kyokus, 6);
+        assert_eq!(stats.deal_ins, 3);
+        assert_eq!(stats.points_lost, 14600);
+        assert_eq!(stats.avoidable, 1);
+        assert_eq!(stats.disagreed, 0);
+        assert_eq!(stats.not_reviewed, 1);
+        assert_eq!(stats.rate(), Some(0.5));
+        assert_eq!(stats.avoidable_fraction(), Some(0.5));
+        assert_eq!(DealInStats::default().avg_points_lost(), None);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::engine::Engine;
+use crate::manifest::split_kyokus;
+use crate::review::{review_with_engine, Entry, ReviewArgs};
+
+use anyhow::{Context, Result};
+use convlog::mjai::Event;
+use convlog::DecisionId;
+use serde::Serialize;
+
+/// The review of a single decision, see [`review_single_decision`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DecisionResult {
+    pub id: DecisionId,
+    pub kyoku: u8, // in tenhou.net/6 format, counts from 0
+    pub honba: u8,
+    /// The moves akochan values about the same as its best one, within
+    /// `deviation_threshold`, so that any of them can be recommended. Empty
+    /// unless there are at least two.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tied: Vec<Vec<Event>>,
+    pub entry: Entry,
+}
+
+/// Reviews the first decision of `seat` at `junme` in the `kyoku_index`-th
+/// kyoku of the events, counting from 0. It is the decision after the draw
+/// of that junme, unless the seat called instead.
+///
+/// The whole kyoku is fed to `engine` from its StartKyoku, just like the
+/// kyoku filter does, so the engine sees the same state as in a full review.
+/// The engine is left for the caller to finish.
+pub fn review_single_decision(
+    engine: &mut impl Engine,
+    review_args: &ReviewArgs,
+    kyoku_index: usize,
+    seat: u8,
+    junme: u8,
+) -> Result<DecisionResult> {
+    let events = review_args.events;
+    let kyokus = split_kyokus(events);
+    let kyoku_events = kyokus.get(kyoku_index).with_context(|| {
+        format!(
+            "kyoku index {} is out of range, there are {} kyokus",
+            kyoku_index,
+            kyokus.len(),
+        )
+    })?;
+
+    let mut partial_events = vec![events[0].clone()];
+    partial_events.extend_from_slice(kyoku_events);
+    partial_events.push(events[events.len() - 1].clone());
+
+    let partial_args = ReviewArgs {
+        events: &partial_events,
+        target_actor: seat,
+        on_entry: None,
+        ..*review_args
+    };
+    let kyoku_review = review_with_engine(engine, &partial_args)?
+        .kyokus
+        .into_iter()
+        .next()
+        .context("akochan reviewed no kyoku")?;
+
+    let entry = kyoku_review
+        .entries
+        .into_iter()
+        .find(|entry| entry.junme == junme)
+        .with_context(|| {
+            format!(
+                "no decision of seat {} to review at junme {} in kyoku index {}",
+                seat, junme, kyoku_index,
+            )
+        })?;
+
+    let mut tied: Vec<_> = entry
+        .tied_moves(review_args.deviation_threshold)
+        .into_iter()
+        .map(<[Event]>::to_vec)
+        .collect();
+    if tied.len() < 2 {
+        tied.clear();
+    }
+
+    Ok(DecisionResult {
+        id: DecisionId::new(kyoku_review.kyoku, kyoku_review.honba, seat, entry.junme),
+        kyoku: kyoku_review.kyoku,
+        honba: kyoku_review.honba,
+        tied,
+        entry,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::{
+        discards, kyoku_events, mock_review, pass, review_args, MockEngine,
+    };
+
+    use serde_json as json;
+
+    #[test]
+    fn same_entry_as_full_review() {
+        let mut events = vec![json::from_value(json::json!({
+            "type": "start_game",
+            "names": ["A", "B", "C", "D"],
+            "kyoku_first": 0,
+            "aka_flag": true,
+        }))
+        .unwrap()];
+        events.extend(kyoku_events(
+            "1m 2m 3m 4p 5p 6p 7s 8s 9s E E S N",
+            &[("W", "N"), ("P", "S")],
+        ));
+        let answers = || {
+            vec![
+                discards(&[("N", 1.2), ("W", 1.1)]),
+                pass(),
+                discards(&[("P", 1.3), ("S", 1.)]),
+            ]
+        };
+        let args = review_args(&events);
+
+        let full = mock_review(&args, answers());
+        let mut engine = MockEngine::new(answers());
+        let decision = review_single_decision(&mut engine, &args, 0, 0, 2).unwrap();
+        engine.finish().unwrap();
+
+        let full_entry = &full.kyokus[0].entries[1];
+        assert_eq!(full_entry.junme, 2);
+        assert_eq!(
+            json::to_value(&decision.entry).unwrap(),
+            json::to_value(full_entry).unwrap(),
+        );
+        assert_eq!(decision.id.to_string(), "E1-0-s0-t2");
+        assert!(decision.tied.is_empty());
+    }
+}
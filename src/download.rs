@@ -47,7 +47,7 @@ pub fn mahjong_soul_log(log_id: &str) -> Result<String> {
 }
 
 fn proxy_from_env(req: &mut ureq::Request, url: &str) -> Result<()> {
-    if let Some(proxy_url) = env_proxy::for_url_str(&url).raw_value() {
+    if let Some(proxy_url) = env_proxy::for_url_str(url).raw_value() {
         let proxy_str: String = proxy_url.chars().skip("http://".len()).collect();
         let proxy = ureq::Proxy::new(proxy_str).context("failed to parse proxy")?;
         req.set_proxy(proxy);
@@ -0,0 +1,133 @@
+use crate::log;
+use crate::review::DetailedAction;
+use std::io::prelude::*;
+use std::io::{BufReader, Lines};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+use convlog::mjai::Event;
+use serde_json as json;
+
+/// An evaluator of the decisions of one player, which the review loop feeds
+/// with every mjai event of the game and asks for candidate actions at each
+/// decision to review.
+pub trait Engine {
+    /// Tells the engine about the next event of the game.
+    fn feed(&mut self, event: &Event) -> Result<()>;
+
+    /// Returns the candidate actions for the decision right after the last
+    /// fed event, the best one first.
+    ///
+    /// The review loop calls it after each tsumo of the target actor and each
+    /// discard or kakan of the others, as akochan answers every one of them
+    /// in order, even those that end up not reviewed.
+    fn evaluate(&mut self) -> Result<Vec<DetailedAction>>;
+
+    /// Waits for the engine to exit after the whole game has been fed.
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// The akochan subprocess in `pipe_detailed` mode.
+pub struct Akochan {
+    child: Child,
+    /// Taken and closed before waiting for the child to exit, as
+    /// [`Child::wait`] would do for its own handle.
+    stdin: Option<ChildStdin>,
+    stdout_lines: Lines<BufReader<ChildStdout>>,
+    verbose: bool,
+}
+
+impl Akochan {
+    pub fn spawn(
+        akochan_exe: &Path,
+        akochan_dir: &Path,
+        tactics_config: &Path,
+        target_actor: u8,
+        verbose: bool,
+    ) -> Result<Self> {
+        let target_actor_string = target_actor.to_string();
+        let args = &[
+            "pipe_detailed".as_ref(),
+            tactics_config,
+            target_actor_string.as_ref(),
+        ];
+
+        if verbose {
+            log!("$ cd {:?}", akochan_dir);
+            log!(
+                "$ {:?}{}",
+                akochan_exe,
+                args.iter()
+                    .fold("".to_owned(), |acc, p| format!("{} {:?}", acc, p))
+            );
+        }
+
+        let mut child = Command::new(akochan_exe)
+            .args(args)
+            .current_dir(akochan_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .context("failed to spawn akochan")?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .context("failed to get stdin of akochan")?;
+        let stdout_lines = BufReader::new(
+            child
+                .stdout
+                .take()
+                .context("failed to get stdout of akochan")?,
+        )
+        .lines();
+
+        Ok(Self {
+            child,
+            stdin: Some(stdin),
+            stdout_lines,
+            verbose,
+        })
+    }
+}
+
+impl Engine for Akochan {
+    fn feed(&mut self, event: &Event) -> Result<()> {
+        let to_write = json::to_string(event).unwrap();
+        let stdin = self.stdin.as_mut().context("akochan is already finished")?;
+        writeln!(stdin, "{}", to_write).context("failed to write to akochan")?;
+        if self.verbose {
+            log!("> {}", to_write);
+        }
+        Ok(())
+    }
+
+    fn evaluate(&mut self) -> Result<Vec<DetailedAction>> {
+        // be careful, stdout_lines.next() may block.
+        let line = self
+            .stdout_lines
+            .next()
+            .context("failed to read from akochan: unexpected EOF")?
+            .context("failed to read from akochan")?;
+        if self.verbose {
+            log!("< {}", line.trim());
+        }
+
+        json::from_str(&line).context("failed to parse JSON output of akochan")
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        drop(self.stdin.take());
+        let ecode = self.child.wait()?;
+        if !ecode.success() {
+            if let Some(code) = ecode.code() {
+                bail!("non-zero exit code: {}", code);
+            } else {
+                bail!("non-zero exit code: Process terminated by signal");
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+
+/// The unit the EVs of a report are shown in, by --ev-unit.
+///
+/// akochan values the final placement of the game only, not the score, so
+/// there is no rate to turn its EVs into game points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvUnit {
+    /// As akochan gives them, pt or the negative placement.
+    #[default]
+    Pt,
+    /// A thousandth of a pt or a placement, for small differences.
+    Permille,
+    /// A fraction of the gap between the rewards of 1st and 4th place, so
+    /// that 1 is the whole way from last to top whatever the pt are.
+    Normalized,
+}
+
+impl EvUnit {
+    /// Returns the factor to multiply the EVs of a review with, which is
+    /// under the pt `jun_pt` or under placement EV.
+    pub fn scale(self, jun_pt: &[i32; 4], use_placement_ev: bool) -> f64 {
+        match self {
+            Self::Pt => 1.,
+            Self::Permille => 1000.,
+            Self::Normalized => {
+                // the placements go from -1 to -4
+                let gap = if use_placement_ev {
+                    3.
+                } else {
+                    (jun_pt[0] - jun_pt[3]) as f64
+                };
+                if gap > 0. {
+                    gap.recip()
+                } else {
+                    1.
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for EvUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pt" => Ok(Self::Pt),
+            "permille" => Ok(Self::Permille),
+            "normalized" => Ok(Self::Normalized),
+            _ => Err(format!("unsupported EV unit {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_per_unit() {
+        let jun_pt = [90, 45, 0, -135];
+        assert_eq!(EvUnit::Pt.scale(&jun_pt, false), 1.);
+        assert_eq!(EvUnit::Pt.scale(&jun_pt, true), 1.);
+        assert_eq!(EvUnit::Permille.scale(&jun_pt, false), 1000.);
+        assert_eq!(EvUnit::Permille.scale(&jun_pt, true), 1000.);
+        assert_eq!(EvUnit::Normalized.scale(&jun_pt, false), 1. / 225.);
+        assert_eq!(EvUnit::Normalized.scale(&jun_pt, true), 1. / 3.);
+        // no gap to normalize by
+        assert_eq!(EvUnit::Normalized.scale(&[0; 4], false), 1.);
+
+        assert_eq!("permille".parse(), Ok(EvUnit::Permille));
+        assert!("percent".parse::<EvUnit>().is_err());
+    }
+}
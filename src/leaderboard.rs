@@ -0,0 +1,165 @@
+use crate::review::{Acceptance, KyokuReview};
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+/// One reviewed game of a player, as read from a JSON report.
+pub struct PlayerGame<'a> {
+    pub name: &'a str,
+    /// Tells apart players that can not be told by name, such as the file
+    /// name of the report.
+    pub source: &'a str,
+    pub kyokus: &'a [KyokuReview],
+}
+
+/// The stats of one player across all of their games.
+#[derive(Debug, Clone, Serialize)]
+pub struct Row {
+    /// The name of the player, with the source appended for anonymous ones.
+    pub name: String,
+    pub games: usize,
+    pub decisions: usize,
+    pub mistakes: usize,
+    /// Mistakes per decision.
+    pub mistake_rate: f64,
+    /// EV dropped by mistakes and tolerated moves, per decision.
+    pub avg_ev_loss: f64,
+}
+
+/// Returns true for names that do not stand for one person, namely the
+/// aliases of `--anonymous` like "Aさん", tenhou's "NoName" for guests and
+/// empty names.
+fn is_anonymous(name: &str) -> bool {
+    let mut chars = name.chars();
+    let is_alias = matches!(chars.next(), Some('A'..='D')) && chars.as_str() == "さん";
+    is_alias || name == "NoName" || name.trim().is_empty()
+}
+
+/// Merges the games by player name and ranks the players by mistake rate
+/// and then by average EV loss, the best first.
+///
+/// Anonymous names, see [`is_anonymous`], are never merged, each of them
+/// is kept apart by its source instead. Forced decisions are not counted,
+/// while trivial discards left out of the entries are.
+pub fn leaderboard<'a>(games: impl IntoIterator<Item = PlayerGame<'a>>) -> Vec<Row> {
+    #[derive(Default)]
+    struct Sum {
+        games: usize,
+        decisions: usize,
+        mistakes: usize,
+        ev_loss: f64,
+    }
+
+    let mut players: HashMap<String, Sum> = HashMap::new();
+    for game in games {
+        let key = if is_anonymous(game.name) {
+            format!("{} ({})", game.name, game.source)
+        } else {
+            game.name.to_owned()
+        };
+        let sum = players.entry(key).or_default();
+        sum.games += 1;
+        sum.decisions += game
+            .kyokus
+            .iter()
+            .map(|k| k.trivial_discards)
+            .sum::<usize>();
+        let entries = game.kyokus.iter().flat_map(|k| &k.entries);
+        for entry in entries.filter(|e| !e.forced) {
+            sum.decisions += 1;
+            match entry.acceptance {
+                Acceptance::Disagree => sum.mistakes += 1,
+                Acceptance::Tolerable => (),
+                Acceptance::Agree => continue,
+            }
+            sum.ev_loss += entry.dev;
+        }
+    }
+
+    let per_decision = |v: f64, decisions: usize| {
+        if decisions == 0 {
+            0.
+        } else {
+            v / decisions as f64
+        }
+    };
+    let mut rows: Vec<_> = players
+        .into_iter()
+        .map(|(name, sum)| Row {
+            name,
+            games: sum.games,
+            decisions: sum.decisions,
+            mistakes: sum.mistakes,
+            mistake_rate: per_decision(sum.mistakes as f64, sum.decisions),
+            avg_ev_loss: per_decision(sum.ev_loss, sum.decisions),
+        })
+        .collect();
+
+    // the name keeps the order stable among equal stats
+    rows.sort_by(|a, b| {
+        a.mistake_rate
+            .total_cmp(&b.mistake_rate)
+            .then(a.avg_ev_loss.total_cmp(&b.avg_ev_loss))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::entry;
+
+    #[test]
+    fn shared_players() {
+        let clean = vec![KyokuReview {
+            entries: vec![
+                entry(Acceptance::Agree, &[("N", 1.), ("S", 0.5)], "N"),
+                entry(Acceptance::Tolerable, &[("N", 1.), ("S", 0.98)], "S"),
+            ],
+            trivial_discards: 2,
+            ..Default::default()
+        }];
+        let sloppy = vec![KyokuReview {
+            entries: vec![entry(Acceptance::Disagree, &[("N", 1.), ("S", 0.5)], "S")],
+            ..Default::default()
+        }];
+        let game = |name, source, kyokus| PlayerGame {
+            name,
+            source,
+            kyokus,
+        };
+        let games = [
+            game("Alice", "a.json", &clean[..]),
+            game("Bob", "a.json", &sloppy),
+            game("Alice", "b.json", &sloppy),
+            game("Aさん", "a.json", &clean),
+            game("Aさん", "b.json", &clean),
+            game("NoName", "c.json", &sloppy),
+        ];
+
+        let rows = leaderboard(games);
+        let names: Vec<_> = rows.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Aさん (a.json)",
+                "Aさん (b.json)",
+                "Alice",
+                "Bob",
+                "NoName (c.json)"
+            ],
+        );
+
+        let alice = &rows[2];
+        assert_eq!(alice.games, 2);
+        assert_eq!(alice.decisions, 5);
+        assert_eq!(alice.mistakes, 1);
+        assert!((alice.mistake_rate - 0.2).abs() < 1e-9);
+        assert!((alice.avg_ev_loss - 0.52 / 5.).abs() < 1e-9);
+
+        assert_eq!(rows[0].decisions, 4);
+        assert_eq!(rows[0].mistakes, 0);
+        assert_eq!(rows[3].mistake_rate, 1.);
+    }
+}
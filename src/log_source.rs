@@ -4,6 +4,7 @@ pub enum LogSource {
     Tenhou(String),
     MahjongSoul(String),
     File(OsString),
+    MjaiFile(OsString),
     Stdin,
 }
 
@@ -18,13 +19,20 @@ impl LogSource {
         }
     }
 
-    pub fn default_output_filename(&self, actor: u8) -> OsString {
+    /// Returns the file name of the report of `actors` without the suffix,
+    /// where the seats of a combined report are joined like "0,2".
+    pub fn default_output_filename(&self, actors: &[u8]) -> OsString {
+        let seats = actors
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
         match self {
-            LogSource::Tenhou(id) => format!("{}&tw={}", id, actor).into(),
+            LogSource::Tenhou(id) => format!("{}&tw={}", id, seats).into(),
             LogSource::MahjongSoul(full_id) => {
-                format!("{}_{}", mjsoul_log_id_from_full(full_id), actor).into()
+                format!("{}_{}", mjsoul_log_id_from_full(full_id), seats).into()
             }
-            LogSource::File(filename) => filename.clone(),
+            LogSource::File(filename) | LogSource::MjaiFile(filename) => filename.clone(),
             LogSource::Stdin => "report".to_owned().into(),
         }
     }
@@ -47,9 +55,9 @@ fn mjsoul_log_id_from_full(full_id: &str) -> &str {
 fn deobfuse_mjsoul_log_id(id: &str) -> String {
     let mut ret = String::with_capacity(id.len());
     for (i, &code) in id.as_bytes().iter().enumerate() {
-        let o = if (b'0'..=b'9').contains(&code) {
+        let o = if code.is_ascii_digit() {
             code - b'0'
-        } else if (b'a'..=b'z').contains(&code) {
+        } else if code.is_ascii_lowercase() {
             code - b'a' + 10
         } else {
             ret.push(code as char);
@@ -0,0 +1,227 @@
+use crate::review::{compare_action_strict, Acceptance, Entry, KyokuReview};
+
+/// The result of a single reviewed game, in the unit of the review's EV.
+#[derive(Debug, Clone)]
+pub struct GameOutcome<'a> {
+    pub kyokus: &'a [KyokuReview],
+    pub pt: [i32; 4],
+    pub use_placement_ev: bool,
+    /// Final placement of the actor, from 1.
+    pub placement: u8,
+}
+
+impl GameOutcome<'_> {
+    /// The actual result, which is the pt of the placement, or the negative
+    /// placement under placement EV as akochan gives it.
+    pub fn actual(&self) -> f64 {
+        if self.use_placement_ev {
+            -(self.placement as f64)
+        } else {
+            self.pt[self.placement as usize - 1] as f64
+        }
+    }
+
+    /// The expected result of the game without knowing anything, where all
+    /// placements are equally likely.
+    pub fn baseline(&self) -> f64 {
+        if self.use_placement_ev {
+            -2.5
+        } else {
+            self.pt.iter().sum::<i32>() as f64 / 4.
+        }
+    }
+
+    /// Sum of the EV dropped by every decision akochan disagreed with or
+    /// tolerated.
+    pub fn mistake_loss(&self) -> f64 {
+        self.kyokus
+            .iter()
+            .flat_map(|k| &k.entries)
+            .filter(|e| !matches!(e.acceptance, Acceptance::Agree))
+            // an empty f64 sum is -0
+            .fold(0., |sum, e| sum + e.dev)
+    }
+
+    /// Sums of the EV kept and the EV in play over the decisions akochan
+    /// evaluated, see [`LuckSkill::efficiency`].
+    pub fn kept_and_in_play(&self) -> (f64, f64) {
+        self.kyokus
+            .iter()
+            .flat_map(|k| &k.entries)
+            .filter_map(decision_evs)
+            .fold((0., 0.), |(kept, in_play), (k, p)| (kept + k, in_play + p))
+    }
+}
+
+/// Returns the EV of the actual move and of the best one above the worst
+/// candidate, or None if akochan gives no EV for either of them, or the
+/// decision is forced or all candidates are worth the same.
+fn decision_evs(entry: &Entry) -> Option<(f64, f64)> {
+    if entry.forced {
+        return None;
+    }
+    let best_ev = entry.details.first()?.review.pt_exp_total?;
+    let worst_ev = entry.details.last()?.review.pt_exp_total?;
+    // an agreed move may not be akochan's first choice, but is as good
+    let actual_ev = if matches!(entry.acceptance, Acceptance::Agree) {
+        best_ev
+    } else {
+        entry
+            .details
+            .iter()
+            .find(|d| compare_action_strict(&entry.actual, &d.moves))?
+            .review
+            .pt_exp_total?
+    };
+
+    let in_play = best_ev - worst_ev;
+    (in_play > 0.).then_some((actual_ev - worst_ev, in_play))
+}
+
+/// An approximate split of the results of a session into what was lost to
+/// mistakes and what was swung by variance, see the README for the method.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LuckSkill {
+    pub games: usize,
+    /// Sum of the actual results minus the baselines.
+    pub net: f64,
+    /// Sum of the EV lost to mistakes, always non-negative.
+    pub mistake_loss: f64,
+    /// Sum over the evaluated decisions of the EV of the actual move above
+    /// the worst candidate.
+    pub kept_ev: f64,
+    /// Sum over the evaluated decisions of the EV of the best move above the
+    /// worst candidate.
+    pub ev_in_play: f64,
+}
+
+impl LuckSkill {
+    pub fn new<'a>(games: impl IntoIterator<Item = GameOutcome<'a>>) -> Self {
+        games.into_iter().fold(Self::default(), |acc, game| {
+            let (kept, in_play) = game.kept_and_in_play();
+            Self {
+                games: acc.games + 1,
+                net: acc.net + game.actual() - game.baseline(),
+                mistake_loss: acc.mistake_loss + game.mistake_loss(),
+                kept_ev: acc.kept_ev + kept,
+                ev_in_play: acc.ev_in_play + in_play,
+            }
+        })
+    }
+
+    /// The share of the EV in play that the actual moves kept, where 1 is
+    /// playing akochan's best move at every decision and 0 its worst one.
+    ///
+    /// The EVs are measured above the worst candidate of each decision, as
+    /// the EVs themselves may be negative. Decisions akochan gives no EV for
+    /// are left out. Returns None if no decision is left.
+    pub fn efficiency(&self) -> Option<f64> {
+        (self.ev_in_play > 0.).then(|| self.kept_ev / self.ev_in_play)
+    }
+
+    /// What the results would have been off the baseline with no mistakes,
+    /// attributed to luck.
+    #[inline]
+    pub fn variance(&self) -> f64 {
+        self.net + self.mistake_loss
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::entry;
+
+    const PT: [i32; 4] = [90, 45, 0, -135];
+
+    /// A kyoku where the actor lost 0.3 by a mistake and 0.05 by a tolerated
+    /// move, keeping 1 of the 1.35 EV in play.
+    fn session() -> Vec<KyokuReview> {
+        let mut forced = entry(Acceptance::Agree, &[("P", 0.8)], "P");
+        forced.forced = true;
+        let entries = vec![
+            entry(Acceptance::Agree, &[("N", 1.), ("W", 0.5)], "N"),
+            entry(
+                Acceptance::Disagree,
+                &[("S", 1.), ("P", 0.7), ("F", 0.2)],
+                "P",
+            ),
+            forced,
+            entry(Acceptance::Tolerable, &[("S", 0.5), ("P", 0.45)], "P"),
+        ];
+        vec![KyokuReview {
+            entries,
+            ..Default::default()
+        }]
+    }
+
+    #[test]
+    fn game_outcome() {
+        let kyokus = session();
+        let game = GameOutcome {
+            kyokus: &kyokus,
+            pt: PT,
+            use_placement_ev: false,
+            placement: 2,
+        };
+        assert_eq!(game.actual(), 45.);
+        assert_eq!(game.baseline(), 0.);
+        assert!((game.mistake_loss() - 0.35).abs() < 1e-9);
+        let (kept, in_play) = game.kept_and_in_play();
+        assert!((kept - 1.).abs() < 1e-9);
+        assert!((in_play - 1.35).abs() < 1e-9);
+
+        let game = GameOutcome {
+            use_placement_ev: true,
+            ..game
+        };
+        assert_eq!(game.actual(), -2.);
+        assert_eq!(game.baseline(), -2.5);
+    }
+
+    #[test]
+    fn decision_evs_of_entries() {
+        let kyokus = session();
+        let evs: Vec<_> = kyokus[0].entries.iter().map(decision_evs).collect();
+        assert_eq!(evs[0], Some((0.5, 0.5)));
+        let (kept, in_play) = evs[1].unwrap();
+        assert!((kept - 0.5).abs() < 1e-9);
+        assert!((in_play - 0.8).abs() < 1e-9);
+        // forced
+        assert_eq!(evs[2], None);
+        let (kept, in_play) = evs[3].unwrap();
+        assert!(kept.abs() < 1e-9);
+        assert!((in_play - 0.05).abs() < 1e-9);
+
+        // all the same
+        let tie = entry(Acceptance::Agree, &[("N", 1.), ("W", 1.)], "W");
+        assert_eq!(decision_evs(&tie), None);
+    }
+
+    #[test]
+    fn luck_skill_efficiency() {
+        let kyokus = session();
+        let games = [2, 4].map(|placement| GameOutcome {
+            kyokus: &kyokus,
+            pt: PT,
+            use_placement_ev: false,
+            placement,
+        });
+        let stats = LuckSkill::new(games);
+        assert_eq!(stats.games, 2);
+        assert_eq!(stats.net, -90.);
+        assert!((stats.mistake_loss - 0.7).abs() < 1e-9);
+        assert!((stats.variance() - -89.3).abs() < 1e-9);
+        assert!((stats.efficiency().unwrap() - 1. / 1.35).abs() < 1e-9);
+
+        assert_eq!(LuckSkill::new([]).efficiency(), None);
+
+        let clean = GameOutcome {
+            kyokus: &[],
+            pt: PT,
+            use_placement_ev: false,
+            placement: 1,
+        };
+        assert!(clean.mistake_loss().is_sign_positive());
+    }
+}
@@ -1,25 +1,48 @@
+mod annotation;
+mod call_value;
+mod csv;
+mod deal_in;
+mod decision;
 mod download;
+mod engine;
+mod ev_unit;
+mod leaderboard;
 mod log;
 mod log_source;
+mod luck;
+mod manifest;
 mod metadata;
+mod mistake;
+mod ndjson;
 mod raw_log_ext;
 mod render;
 mod report_output;
 mod review;
+mod riichi_value;
 mod state;
+mod summary;
 mod tactics;
 mod tehai;
+mod time_series;
 
 use crate::render::Layout;
 
+use self::decision::review_single_decision;
+use self::engine::{Akochan, Engine};
+use self::ev_unit::EvUnit;
 use self::log_source::LogSource;
-use self::metadata::Metadata;
+use self::manifest::{review_with_manifest, split_kyokus};
+use self::metadata::{KyokuTiming, Metadata, Timing};
+use self::mistake::MistakeCategory;
+use self::ndjson::NdJsonWriter;
 use self::raw_log_ext::RawLogExt;
-use self::render::{Language, View};
-use self::report_output::ReportOutput;
-use self::review::review;
+use self::render::{Language, Page, View};
+use self::report_output::{ReportFormat, ReportOutput};
+use self::review::Acceptance;
+use self::review::KyokuReview;
 use self::review::ReviewArgs;
-use self::tactics::TacticsJson;
+use self::review::{retain_involved, review};
+use std::cmp::Reverse;
 use std::env;
 use std::fs;
 use std::fs::File;
@@ -28,13 +51,18 @@ use std::io::prelude::*;
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail, ensure};
 use anyhow::{Context, Result};
 use clap::{App, Arg};
+use convlog::hand_value;
+use convlog::mjai::Event;
+use convlog::safety;
 use convlog::tenhou;
+use convlog::{LogDate, Pai};
 use dunce::canonicalize;
+use serde::Deserialize;
 use serde_json as json;
-use tempfile::NamedTempFile;
+use tempfile::TempPath;
 use url::Url;
 
 const PKG_NAME: &str = env!("CARGO_PKG_NAME");
@@ -83,6 +111,45 @@ fn main() -> Result<()> {
                     It is the number after \"&tw=\" in tenhou's log url.",
                 ),
         )
+        .arg(
+            Arg::with_name("actors")
+                .long("actors")
+                .takes_value(true)
+                .value_name("INDEXES")
+                .conflicts_with_all(&[
+                    "actor",
+                    "actor-name",
+                    "deal-ins-only",
+                    "tenpai-only",
+                    "manifest",
+                    "annotations",
+                    "decision",
+                ])
+                .validator(|v| {
+                    let mut seen = [false; 4];
+                    for p in v.split(',') {
+                        let num: u8 = p
+                            .parse()
+                            .map_err(|err| format!("INDEXES must be numbers: {}", err))?;
+                        if num > 3 {
+                            return Err(format!("INDEXES must be within 0~3, got {}", num));
+                        }
+                        if seen[num as usize] {
+                            return Err(format!("INDEXES must be distinct, got {} twice", num));
+                        }
+                        seen[num as usize] = true;
+                    }
+                    Ok(())
+                })
+                .help(
+                    "Specify multiple actors to review in one pass, such as \"0,2\". \
+                    The log is loaded and converted only once, \
+                    and all actors go into one combined report, \
+                    with a section for each actor in HTML and summary, \
+                    an array of their reports in JSON, \
+                    and the rows or lines of all of them in CSV and NDJSON.",
+                ),
+        )
         .arg(
             Arg::with_name("actor-name")
                 .long("actor-name")
@@ -104,6 +171,72 @@ fn main() -> Result<()> {
                     Format: \"E1,E4,S3.1\".",
                 ),
         )
+        .arg(
+            Arg::with_name("decision")
+                .long("decision")
+                .takes_value(true)
+                .value_name("INDEX,JUNME")
+                .conflicts_with_all(&[
+                    "csv",
+                    "ndjson",
+                    "summary",
+                    "manifest",
+                    "annotations",
+                    "deal-ins-only",
+                    "tenpai-only",
+                    "call-values",
+                    "tenpai-breaks",
+                    "riichi-decisions",
+                ])
+                .validator(|v| parse_decision(&v).map(|_| ()))
+                .help(
+                    "Only review the actor's decision at JUNME in the INDEX-th kyoku \
+                    to review, counting from 0, and output it in JSON. \
+                    The whole kyoku is still fed to akochan.",
+                ),
+        )
+        .arg(Arg::with_name("call-values").long("call-values").help(
+            "Summarize how much each chi, pon and daiminkan of the actor \
+                    gained or lost against passing it, according to akochan.",
+        ))
+        .arg(Arg::with_name("tenpai-breaks").long("tenpai-breaks").help(
+            "Summarize each turn where the actor broke tenpai, such as to fold, \
+                    and whether akochan agreed.",
+        ))
+        .arg(
+            Arg::with_name("riichi-decisions")
+                .long("riichi-decisions")
+                .help(
+                    "Summarize each turn where the actor could declare riichi, with \
+                    akochan's best EV with and without riichi, and flag the turns \
+                    where the actor chose the worse side by more than \
+                    --deviation-threshold. \
+                    Such turns are also told as a too-cheap riichi, on a hand worth \
+                    at most 1 han without riichi, or a too-slow dama, on a hand with \
+                    2 or more kinds of wait or 3 han or more.",
+                ),
+        )
+        .arg(Arg::with_name("deal-ins-only").long("deal-ins-only").help(
+            "Only review kyokus where the actor dealt in, \
+                    and summarize whether akochan would have folded on each deal-in, \
+                    with the order to fold the hand in and whether its genbutsu last \
+                    to the draw when it would. \
+                    Requires the actor to be known before review.",
+        ))
+        .arg(Arg::with_name("tenpai-only").long("tenpai-only").help(
+            "Only review kyokus where the actor reached tenpai or won, \
+                    leaving out the hands the actor folded from the start. \
+                    Requires the actor to be known before review.",
+        ))
+        .arg(Arg::with_name("skip-aborts").long("skip-aborts").help(
+            "Do not review kyokus that ended in 九種九牌 or 四風連打, \
+                    which have no meaningful decisions.",
+        ))
+        .arg(Arg::with_name("no-aka").long("no-aka").help(
+            "Review as if there were no aka, with every aka turned into its normal 5 \
+                    before review. This changes the values of hands akochan sees, \
+                    while the recorded scores and the log viewer are left as they are.",
+        ))
         .arg(
             Arg::with_name("in-file")
                 .short("i")
@@ -115,6 +248,16 @@ fn main() -> Result<()> {
                     If FILE is \"-\" or empty, read from stdin.",
                 ),
         )
+        .arg(
+            Arg::with_name("mjai-in")
+                .long("mjai-in")
+                .takes_value(true)
+                .value_name("FILE")
+                .help(
+                    "Specify a mjai format log file to review, overriding --in-file. \
+                    The log viewer is not available for mjai logs.",
+                ),
+        )
         .arg(
             Arg::with_name("out-file")
                 .short("o")
@@ -178,9 +321,103 @@ fn main() -> Result<()> {
                 .value_name("FILE")
                 .help(
                     "Specify a file of Tenhou log ID list to convert to mjai format, \
-                    implying --no-review.",
+                    implying --no-review. \
+                    An interrupted run can be resumed, as logs already converted \
+                    in the output directory are skipped.",
+                ),
+        )
+        .arg(
+            Arg::with_name("check")
+                .long("check")
+                .takes_value(true)
+                .multiple(true)
+                .value_name("FILE")
+                .help(
+                    "Check that each tenhou.net/6 log FILE can be parsed, validated \
+                    and converted into mjai format, without reviewing it. \
+                    Exit with an error if any of them fails.",
                 ),
         )
+        .arg(
+            Arg::with_name("mistake-stats")
+                .long("mistake-stats")
+                .takes_value(true)
+                .multiple(true)
+                .value_name("FILE")
+                .help(
+                    "Classify the mistakes in each JSON report FILE generated with --json, \
+                    and print the counts of each category across all of them, \
+                    from the most common one.",
+                ),
+        )
+        .arg(
+            Arg::with_name("deal-in-stats")
+                .long("deal-in-stats")
+                .takes_value(true)
+                .multiple(true)
+                .value_name("FILE")
+                .help(
+                    "Print how often the actor dealt in across the JSON reports FILE \
+                    generated with --json, the average points lost per deal-in, \
+                    and the fraction of reviewed deal-ins where akochan would have folded.",
+                ),
+        )
+        .arg(
+            Arg::with_name("luck-stats")
+                .long("luck-stats")
+                .takes_value(true)
+                .multiple(true)
+                .value_name("FILE")
+                .help(
+                    "Split the results of the JSON reports FILE of whole games, \
+                    generated with --json, into the EV lost to mistakes and the rest \
+                    swung by variance, and print the sums across all of them, along \
+                    with the share of the EV in play that the actual moves kept.",
+                ),
+        )
+        .arg(
+            Arg::with_name("time-series")
+                .long("time-series")
+                .takes_value(true)
+                .multiple(true)
+                .value_name("FILE")
+                .help(
+                    "Bucket the JSON reports FILE generated with --json by the date in \
+                    their log IDs, and print the mistake rate and average EV loss of \
+                    each bucket as a JSON time series. \
+                    Reports without a date, such as of local files, go into the last \
+                    bucket whose \"bucket\" is null.",
+                ),
+        )
+        .arg(
+            Arg::with_name("leaderboard")
+                .long("leaderboard")
+                .takes_value(true)
+                .multiple(true)
+                .value_name("FILE")
+                .help(
+                    "Merge the JSON reports FILE generated with --json by the name of \
+                    their actors, and print a JSON leaderboard of the players ranked by \
+                    mistake rate and then average EV loss. \
+                    Anonymous names, such as of --anonymous reports, are never merged \
+                    and are told apart by the file names instead.",
+                ),
+        )
+        .arg(
+            Arg::with_name("bucket")
+                .long("bucket")
+                .takes_value(true)
+                .value_name("PERIOD")
+                .help(
+                    "Set the period of each bucket of --time-series. \
+                    Default value \"month\". \
+                    Supported periods: week, month.",
+                )
+                .validator(|v| match v.as_str() {
+                    "week" | "month" => Ok(()),
+                    _ => Err(format!("unsupported period {}", v)),
+                }),
+        )
         .arg(
             Arg::with_name("out-dir")
                 .long("out-dir")
@@ -211,11 +448,44 @@ fn main() -> Result<()> {
                 .long("no-review")
                 .help("Do not review at all. Only download and save files."),
         )
+        .arg(Arg::with_name("efficiency").long("efficiency").help(
+            "Do not review with akochan. Instead, check every discard of the \
+                    target actors for the lowest shanten and then the most ukeire, \
+                    ignoring value and safety, and print the inefficient ones.",
+        ))
         .arg(
             Arg::with_name("json")
                 .long("json")
                 .help("Output review result in JSON instead of HTML."),
         )
+        .arg(
+            Arg::with_name("csv")
+                .long("csv")
+                .conflicts_with("json")
+                .help(
+                    "Output review result in CSV instead of HTML, \
+                    one row per reviewed decision.",
+                ),
+        )
+        .arg(
+            Arg::with_name("ndjson")
+                .long("ndjson")
+                .conflicts_with_all(&["json", "csv", "manifest", "annotations"])
+                .help(
+                    "Output review result in newline-delimited JSON instead of HTML, \
+                    one decision per line, written as soon as it is reviewed.",
+                ),
+        )
+        .arg(
+            Arg::with_name("summary")
+                .long("summary")
+                .conflicts_with_all(&["json", "csv", "ndjson"])
+                .help(
+                    "Output review result in plain text instead of HTML, \
+                    one line per kyoku with its result and the EV lost in it, \
+                    followed by a few sentences summarizing the game.",
+                ),
+        )
         .arg(
             Arg::with_name("akochan-dir")
                 .short("d")
@@ -236,7 +506,8 @@ fn main() -> Result<()> {
                 .value_name("FILE")
                 .help(
                     "Specify the tactics config file for akochan. \
-                    Default value \"tactics.json\".",
+                    Default value \"tactics.json\", or the tactics embedded in the reviewer \
+                    if there is no such file.",
                 ),
         )
         .arg(
@@ -270,6 +541,21 @@ fn main() -> Result<()> {
                     This will override --pt and \"jun_pt\" in --tactics-config.",
                 ),
         )
+        .arg(
+            Arg::with_name("game-length")
+                .long("game-length")
+                .takes_value(true)
+                .value_name("LENGTH")
+                .help(
+                    "Force the game length instead of the one told by the rule of the log, \
+                    which also sets the horizon of the placement EV. \
+                    Supported lengths: tonpuu, hanchan.",
+                )
+                .validator(|v| match v.as_str() {
+                    "tonpuu" | "hanchan" => Ok(()),
+                    _ => Err(format!("unsupported game length {}", v)),
+                }),
+        )
         .arg(
             Arg::with_name("deviation-threshold")
                 .short("n")
@@ -285,6 +571,8 @@ fn main() -> Result<()> {
                     "THRESHOLD is an absolute value that the reviewer will ignore all \
                     problematic moves whose EVs are within the range of \
                     [best EV - THRESHOLD, best EV]. \
+                    When the runner-up is within THRESHOLD of the best, the range \
+                    extends to THRESHOLD below the runner-up. \
                     This option is effective under both pt and placement EV mode. \
                     It is recommended to use it with --use-placement-ev where the reward \
                     distribution is fixed and even. \
@@ -292,6 +580,65 @@ fn main() -> Result<()> {
                     Default value: \"0.001\".",
                 ),
         )
+        .arg(
+            Arg::with_name("ev-precision")
+                .long("ev-precision")
+                .takes_value(true)
+                .value_name("DIGITS")
+                .validator(|v| match v.parse::<usize>() {
+                    Ok(digits) if digits <= 10 => Ok(()),
+                    _ => Err("DIGITS must be an integer from 0 to 10".to_owned()),
+                })
+                .help(
+                    "Round the EVs shown in the HTML report and by --summary to DIGITS \
+                    decimal places. Scores are always shown in whole points. \
+                    JSON output keeps the full precision. \
+                    Default value: \"3\".",
+                ),
+        )
+        .arg(
+            Arg::with_name("ev-unit")
+                .long("ev-unit")
+                .takes_value(true)
+                .value_name("UNIT")
+                .validator(|v| v.parse::<EvUnit>().map(|_| ()))
+                .help(
+                    "Show the EVs and EV losses of the HTML report, --summary and --csv \
+                    in UNIT. \"pt\" is as akochan gives them, in pt or placement. \
+                    \"permille\" is a thousandth of that. \"normalized\" is a fraction \
+                    of the gap between 1st and 4th place, so that 1 is the whole way \
+                    from last to top. There is no unit of game points, as akochan \
+                    only values the final placement. \
+                    JSON output keeps the values as akochan gives them. \
+                    Default value: \"pt\".",
+                ),
+        )
+        .arg(
+            Arg::with_name("skip-uninvolved")
+                .long("skip-uninvolved")
+                .help(
+                    "Leave out of the report the kyokus where akochan agrees with \
+                    every decision of the target actor, or where there is none. \
+                    The report tells how many kyokus are left out.",
+                ),
+        )
+        .arg(
+            Arg::with_name("skip-trivial-discards")
+                .long("skip-trivial-discards")
+                .help(
+                    "Leave out of the report the decisions akochan agrees with that \
+                    only discard a lone yaochuu pai in the first 6 turns of a closed \
+                    hand still at the shanten of its haipai. \
+                    Hands close to chiitoitsu or kokushi musou are always reviewed. \
+                    The decisions left out still count toward the score as agreed.",
+                ),
+        )
+        .arg(Arg::with_name("list-forced").long("list-forced").help(
+            "List in the report the forced decisions of the target actor, \
+                    where akochan finds a single legal action on their own turn \
+                    outside riichi. They are left out by default, and never count \
+                    toward the score either way.",
+        ))
         .arg(
             Arg::with_name("lang")
                 .long("lang")
@@ -307,6 +654,10 @@ fn main() -> Result<()> {
                     _ => Err(format!("unsupported language {}", v)),
                 }),
         )
+        .arg(Arg::with_name("timing").long("timing").help(
+            "Record the time spent by akochan on each decision and kyoku, \
+                    and include it in the JSON report.",
+        ))
         .arg(
             Arg::with_name("verbose")
                 .short("v")
@@ -328,36 +679,106 @@ fn main() -> Result<()> {
                     _ => Err(format!("unsupported layout {}", v)),
                 }),
         )
+        .arg(
+            Arg::with_name("manifest")
+                .long("manifest")
+                .takes_value(true)
+                .value_name("FILE")
+                .help(
+                    "Record reviewed kyokus in FILE, and only review kyokus that are \
+                    not recorded yet, reusing the recorded results for the others. \
+                    FILE will be created if it does not exist.",
+                ),
+        )
+        .arg(
+            Arg::with_name("annotations")
+                .long("annotations")
+                .takes_value(true)
+                .value_name("FILE")
+                .help(
+                    "Attach notes in FILE to the reviewed decisions in the report. \
+                    FILE is a JSON array of objects with fields \"kyoku\" (counts from 0), \
+                    \"honba\", \"seat\", \"turn\" and \"note\", or with \"id\" of the decision \
+                    such as \"E1-0-s2-t7\" in place of the first 4.",
+                ),
+        )
         .arg(Arg::with_name("URL").help("Tenhou or Mahjong Soul log URL."))
         .get_matches();
 
     // load options
     let arg_in_file = matches.value_of_os("in-file");
+    let arg_mjai_in = matches.value_of_os("mjai-in");
     let arg_out_file = matches.value_of_os("out-file");
     let arg_tenhou_id = matches.value_of("tenhou-id").map(String::from);
     let arg_mjsoul_id = matches.value_of("mjsoul-id").map(String::from);
     let arg_tenhou_out = matches.value_of_os("tenhou-out");
     let arg_mjai_out = matches.value_of_os("mjai-out");
     let arg_tenhou_ids_file = matches.value_of_os("tenhou-ids-file");
+    let arg_check = matches.values_of_os("check");
+    let arg_mistake_stats = matches.values_of_os("mistake-stats");
+    let arg_deal_in_stats = matches.values_of_os("deal-in-stats");
+    let arg_luck_stats = matches.values_of_os("luck-stats");
+    let arg_time_series = matches.values_of_os("time-series");
+    let arg_leaderboard = matches.values_of_os("leaderboard");
+    let arg_bucket = match matches.value_of("bucket") {
+        Some("week") => time_series::Bucket::Week,
+        _ => time_series::Bucket::Month,
+    };
     let arg_out_dir = matches.value_of_os("out-dir");
     let arg_akochan_dir = matches.value_of_os("akochan-dir");
     let arg_tactics_config = matches.value_of_os("tactics-config");
     let arg_actor: Option<u8> = matches.value_of("actor").map(|p| p.parse().unwrap());
+    let arg_actors: Option<Vec<u8>> = matches
+        .value_of("actors")
+        .map(|v| v.split(',').map(|p| p.parse().unwrap()).collect());
     let arg_actor_name: Option<String> = matches.value_of("actor-name").map(String::from);
     let arg_pt = matches.value_of("pt");
     let arg_kyokus = matches.value_of("kyokus");
+    let arg_decision = matches
+        .value_of("decision")
+        .map(|v| parse_decision(v).unwrap());
+    let arg_skip_aborts = matches.is_present("skip-aborts");
+    let arg_no_aka = matches.is_present("no-aka");
+    let arg_call_values = matches.is_present("call-values");
+    let arg_riichi_decisions = matches.is_present("riichi-decisions");
+    let arg_tenpai_breaks = matches.is_present("tenpai-breaks");
+    let arg_deal_ins_only = matches.is_present("deal-ins-only");
+    let arg_tenpai_only = matches.is_present("tenpai-only");
     let arg_use_placement_ev = matches.is_present("use-placement-ev");
     let arg_without_viewer = matches.is_present("without-viewer");
     let arg_anonymous = matches.is_present("anonymous");
     let arg_no_open = matches.is_present("no-open");
     let arg_no_review = matches.is_present("no-review");
+    let arg_efficiency = matches.is_present("efficiency");
     let arg_json = matches.is_present("json");
+    let arg_csv = matches.is_present("csv");
+    let arg_ndjson = matches.is_present("ndjson");
+    let arg_summary = matches.is_present("summary");
     let arg_deviation_threshold = matches
         .value_of("deviation-threshold")
         .map(|v| v.parse().unwrap())
         .unwrap_or(0.001);
+    let arg_ev_precision = matches
+        .value_of("ev-precision")
+        .map(|v| v.parse().unwrap())
+        .unwrap_or(3);
+    let arg_ev_unit: EvUnit = matches
+        .value_of("ev-unit")
+        .map(|v| v.parse().unwrap())
+        .unwrap_or_default();
     let arg_lang = matches.value_of("lang");
+    let arg_manifest = matches.value_of_os("manifest");
+    let arg_annotations = matches.value_of_os("annotations");
     let arg_verbose = matches.is_present("verbose");
+    let arg_timing = matches.is_present("timing");
+    let arg_skip_trivial_discards = matches.is_present("skip-trivial-discards");
+    let arg_list_forced = matches.is_present("list-forced");
+    let arg_skip_uninvolved = matches.is_present("skip-uninvolved");
+    let arg_game_length = matches.value_of("game-length").map(|v| match v {
+        "tonpuu" => tenhou::GameLength::Tonpuu,
+        "hanchan" => tenhou::GameLength::Hanchan,
+        _ => unreachable!(),
+    });
     let arg_url = matches.value_of("URL");
 
     let layout = match matches.value_of("layout") {
@@ -377,10 +798,34 @@ fn main() -> Result<()> {
         return batch_download(&out_dir_name, Path::new(tenhou_ids_file));
     }
 
+    // check logs and exit
+    if let Some(filenames) = arg_check {
+        return check_logs(filenames.map(Path::new));
+    }
+
+    // aggregate mistakes from reports and exit
+    if let Some(filenames) = arg_mistake_stats {
+        return print_mistake_stats(filenames.map(Path::new));
+    }
+    if let Some(filenames) = arg_deal_in_stats {
+        return print_deal_in_stats(filenames.map(Path::new));
+    }
+    if let Some(filenames) = arg_luck_stats {
+        return print_luck_stats(filenames.map(Path::new));
+    }
+    if let Some(filenames) = arg_time_series {
+        return print_time_series(filenames.map(Path::new), arg_bucket);
+    }
+    if let Some(filenames) = arg_leaderboard {
+        return print_leaderboard(filenames.map(Path::new));
+    }
+
     // sometimes the log URL contains the actor info
     let mut actor_opt = arg_actor;
 
-    let log_source = if let Some(filename) = arg_in_file {
+    let log_source = if let Some(filename) = arg_mjai_in {
+        LogSource::MjaiFile(filename.to_owned())
+    } else if let Some(filename) = arg_in_file {
         if filename == "-" {
             LogSource::Stdin
         } else {
@@ -467,94 +912,175 @@ fn main() -> Result<()> {
         })
         .transpose()?;
 
-    // download and parse tenhou.net/6 log
-    let mut raw_log: tenhou::RawLog = match &log_source {
-        LogSource::Tenhou(id) => {
-            let body = download::tenhou_log(id)
-                .with_context(|| format!("failed to download tenhou log {}", id))?;
-            if let Some((mut writer, filename)) = tenhou_out {
-                writer.write_all(body.as_bytes()).with_context(|| {
-                    format!("failed to write downloaded tenhou log to {:?}", filename)
-                })?;
+    // download and parse the log
+    //
+    // See https://manishearth.github.io/blog/2017/04/13/prolonging-temporaries-in-rust/
+    // for the technique of extending the lifetime of temp var here.
+    let cloned_raw_log;
+    let mut non_tenpai_kyokus = 0;
+    let (mut log, splitted_raw_logs, game_info) = if let LogSource::MjaiFile(filename) = &log_source
+    {
+        // mjai logs are parsed into tenhou::Log directly, there is no raw log
+        // for the viewer.
+        let mut log = read_mjai_log(Path::new(filename))?;
+
+        if actor_opt.is_none() {
+            if let Some(actor_name) = &arg_actor_name {
+                actor_opt = Some(find_actor_by_name(&log.names, actor_name)?);
             }
+        }
 
-            json::from_str(&body).context("failed to parse tenhou.net/6 log")?
+        if arg_anonymous {
+            log.hide_names();
         }
-        LogSource::MahjongSoul(id) => {
-            let body = download::mahjong_soul_log(id)
-                .with_context(|| format!("failed to download mahjong soul log {}", id))?;
-            if let Some((mut writer, filename)) = tenhou_out {
-                writer.write_all(body.as_bytes()).with_context(|| {
-                    format!("failed to write downloaded tenhou log to {:?}", filename)
-                })?;
+        if let Some(expr) = arg_kyokus {
+            let filter = expr.parse().context("failed to parse kyoku filter")?;
+            log.filter_kyokus(&filter);
+            if log.kyokus.is_empty() {
+                return Err(anyhow!("no kyoku to review (invalid filter?)"));
             }
-
-            let val: RawLogExt =
-                json::from_str(&body).context("failed to parse tenhou.net/6 log")?;
-
-            actor_opt = actor_opt.or(val.target_actor);
-            val.raw_log
         }
-        LogSource::File(filename) => {
-            let mut file = File::open(&filename)
-                .with_context(|| format!("failed to open tenhou.net/6 log file {:?}", filename))?;
-            let mut body = String::new();
-            file.read_to_string(&mut body)?;
-
-            json::from_str(&body).context("failed to parse tenhou.net/6 log")?
+        if arg_skip_aborts {
+            log.kyokus.retain(|k| !k.is_first_turn_abort());
+            if log.kyokus.is_empty() {
+                return Err(anyhow!("no kyoku to review (all aborted?)"));
+            }
         }
-        LogSource::Stdin => {
-            let stdin = io::stdin();
-            let handle = stdin.lock();
-            json::from_reader(handle).context("failed to parse tenhou.net/6 log")?
+        if arg_deal_ins_only {
+            let actor = actor_opt.context("--deal-ins-only requires the actor")?;
+            log.kyokus.retain(|k| k.deal_in_seat() == Some(actor));
+            if log.kyokus.is_empty() {
+                return Err(anyhow!("no kyoku to review (no deal-in)"));
+            }
+        }
+        if arg_tenpai_only {
+            let actor = actor_opt.context("--tenpai-only requires the actor")?;
+            let before = log.kyokus.len();
+            log.kyokus.retain(|k| k.reached_tenpai(actor));
+            non_tenpai_kyokus = before - log.kyokus.len();
+            if log.kyokus.is_empty() {
+                return Err(anyhow!("no kyoku to review (never tenpai)"));
+            }
         }
-    };
 
-    // Try to match the name from arg
-    if actor_opt.is_none() {
-        if let Some(actor_name) = arg_actor_name {
-            for (idx, n) in raw_log.get_names().iter().enumerate() {
-                if *n == actor_name {
-                    actor_opt = Some(idx as u8)
+        (log, None, tenhou::GameInfo::default())
+    } else {
+        // download and parse tenhou.net/6 log
+        let mut raw_log: tenhou::RawLog = match &log_source {
+            LogSource::Tenhou(id) => {
+                let body = download::tenhou_log(id)
+                    .with_context(|| format!("failed to download tenhou log {}", id))?;
+                if let Some((mut writer, filename)) = tenhou_out {
+                    writer.write_all(body.as_bytes()).with_context(|| {
+                        format!("failed to write downloaded tenhou log to {:?}", filename)
+                    })?;
                 }
+
+                json::from_str(&body).context("failed to parse tenhou.net/6 log")?
+            }
+            LogSource::MahjongSoul(id) => {
+                let body = download::mahjong_soul_log(id)
+                    .with_context(|| format!("failed to download mahjong soul log {}", id))?;
+                if let Some((mut writer, filename)) = tenhou_out {
+                    writer.write_all(body.as_bytes()).with_context(|| {
+                        format!("failed to write downloaded tenhou log to {:?}", filename)
+                    })?;
+                }
+
+                let val: RawLogExt =
+                    json::from_str(&body).context("failed to parse tenhou.net/6 log")?;
+
+                actor_opt = actor_opt.or(val.target_actor);
+                val.raw_log
+            }
+            LogSource::File(filename) => {
+                let body = read_log_file(Path::new(filename))?;
+                json::from_str(&body).context("failed to parse tenhou.net/6 log")?
+            }
+            LogSource::Stdin => {
+                let stdin = io::stdin();
+                let handle = stdin.lock();
+                json::from_reader(handle).context("failed to parse tenhou.net/6 log")?
+            }
+            LogSource::MjaiFile(_) => unreachable!(),
+        };
+
+        // Try to match the name from arg
+        if actor_opt.is_none() {
+            if let Some(actor_name) = &arg_actor_name {
+                actor_opt = Some(find_actor_by_name(raw_log.get_names(), actor_name)?);
             }
-            // can not find actor index by name, return available players name
-            anyhow::ensure!(
-                actor_opt.is_some(),
-                "there is no player named \"{}\", available players: {}",
-                actor_name,
-                raw_log.get_names().join(", "),
-            );
         }
-    }
 
-    // apply filters
-    if arg_anonymous {
-        raw_log.hide_names();
-    }
-    if let Some(expr) = arg_kyokus {
-        let filter = expr.parse().context("failed to parse kyoku filter")?;
-        raw_log.filter_kyokus(&filter);
-        if raw_log.is_empty() {
-            return Err(anyhow!("no kyoku to review (invalid filter?)"));
+        // apply filters
+        if arg_anonymous {
+            raw_log.hide_names();
+        }
+        if let Some(expr) = arg_kyokus {
+            let filter = expr.parse().context("failed to parse kyoku filter")?;
+            raw_log.filter_kyokus(&filter);
+            if raw_log.is_empty() {
+                return Err(anyhow!("no kyoku to review (invalid filter?)"));
+            }
+        }
+        if arg_skip_aborts {
+            raw_log.skip_first_turn_aborts();
+            if raw_log.is_empty() {
+                return Err(anyhow!("no kyoku to review (all aborted?)"));
+            }
+        }
+        if arg_deal_ins_only {
+            let actor = actor_opt.context("--deal-ins-only requires the actor")?;
+            raw_log.retain_deal_ins(actor);
+            if raw_log.is_empty() {
+                return Err(anyhow!("no kyoku to review (no deal-in)"));
+            }
+        }
+        if arg_tenpai_only {
+            let actor = actor_opt.context("--tenpai-only requires the actor")?;
+            let before = raw_log.len();
+            raw_log.retain_tenpai(actor);
+            non_tenpai_kyokus = before - raw_log.len();
+            if raw_log.is_empty() {
+                return Err(anyhow!("no kyoku to review (never tenpai)"));
+            }
         }
-    }
 
-    // clone the parsed raw log for possible reuse (split)
-    //
-    // See https://manishearth.github.io/blog/2017/04/13/prolonging-temporaries-in-rust/
-    // for the technique of extending the lifetime of temp var here.
-    let cloned_raw_log;
-    let splitted_raw_logs = if !arg_without_viewer {
-        cloned_raw_log = raw_log.clone();
-        Some(cloned_raw_log.split_by_kyoku())
-    } else {
-        None
-    };
+        // clone the parsed raw log for possible reuse (split)
+        let splitted_raw_logs = if !arg_without_viewer {
+            cloned_raw_log = raw_log.clone();
+            Some(cloned_raw_log.split_by_kyoku())
+        } else {
+            None
+        };
 
-    // convert from RawLog to Log.
-    // it moves raw_log.
-    let log = tenhou::Log::from(raw_log);
+        // convert from RawLog to Log.
+        // it moves raw_log.
+        let game_info = raw_log.game_info();
+        (tenhou::Log::from(raw_log), splitted_raw_logs, game_info)
+    };
+    if let Some(game_length) = arg_game_length {
+        log!("forcing the game length to be {}", game_length);
+        log.force_game_length(game_length);
+    }
+    for warning in &log.warnings {
+        log!("WARNING: {}", warning);
+    }
+    if arg_no_aka {
+        log.deaka();
+    }
+    // the placements are only known with all kyokus
+    let whole_game =
+        arg_kyokus.is_none() && !arg_deal_ins_only && !arg_tenpai_only && !arg_skip_aborts;
+    if non_tenpai_kyokus > 0 {
+        log!(
+            "omitted {} kyoku(s) where the actor never reached tenpai",
+            non_tenpai_kyokus
+        );
+    }
+    if arg_kyokus.is_none() && !arg_deal_ins_only && log.is_ended_by_tobi() {
+        log!("the game ended early by tobi, its last kyoku is the end of the game");
+    }
 
     // convert from tenhou::Log to Vec<mjai::Event>
     let begin_convert_log = chrono::Local::now();
@@ -583,13 +1109,24 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
-    // get actor
-    let actor = actor_opt.context("actor is required")?;
-    if actor > 3 {
+    // get actors
+    let actors = match arg_actors {
+        Some(actors) => actors,
+        None => vec![actor_opt.context("actor is required")?],
+    };
+    if let Some(actor) = actors.iter().find(|&&a| a > 3) {
         // just in case
         return Err(anyhow!("must be within 0~3, got {}", actor));
     }
 
+    // check efficiency and exit, which needs no akochan
+    if arg_efficiency {
+        for &actor in &actors {
+            print_efficiency(&log, &events, actor);
+        }
+        return Ok(());
+    }
+
     // get paths
     let akochan_dir = {
         let path = arg_akochan_dir
@@ -605,75 +1142,24 @@ fn main() -> Result<()> {
             .collect::<PathBuf>(),
     )
     .context("failed to canonicalize akochan_exe path")?;
-    let (tactics_file_path, tactics) = {
-        let path = arg_tactics_config
-            .map(PathBuf::from)
-            .unwrap_or_else(|| "tactics.json".into());
-
-        let canon_path = canonicalize(&path)
-            .with_context(|| format!("failed to canonicalize tactics_config path {:?}", path))?;
-
-        // load tactics_config for metadata
-        let tactics_file = File::open(&canon_path)
-            .with_context(|| format!("failed to open tactics_config {:?}", canon_path))?;
-        let tactics_file_reader = BufReader::new(tactics_file);
-
-        let mut tactics_json: TacticsJson = json::from_reader(tactics_file_reader)
-            .with_context(|| format!("failed to parse tactics_config {:?}", canon_path))?;
-
-        // opt-in pt
-        let pt_opt = if arg_use_placement_ev {
-            Some(vec![-1, -2, -3, -4])
-        } else {
-            arg_pt.map(|pt| pt.split(',').map(|p| p.parse::<i32>().unwrap()).collect())
-        };
-
-        if let Some(pt) = pt_opt {
-            tactics_json
-                .tactics
-                .jun_pt
-                .iter_mut()
-                .zip(pt)
-                .for_each(|(o, n)| *o = n);
-
-            let mut tmp = NamedTempFile::new().context("failed to create temp file")?;
-            json::to_writer(&mut tmp, &tactics_json).context("failed to write to temp file")?;
-
-            let tmp_path = tmp
-                .into_temp_path()
-                .keep()
-                .context("failed to keep temp file")?;
-            let canon_tmp_path = canonicalize(&tmp_path)
-                .with_context(|| format!("failed to canonicalize temp file path {:?}", tmp_path))?;
-
-            (canon_tmp_path, tactics_json.tactics)
-        } else {
-            (canon_path, tactics_json.tactics)
-        }
+    // opt-in pt
+    let pt_opt: Option<Vec<_>> = if arg_use_placement_ev {
+        Some(vec![-1, -2, -3, -4])
+    } else {
+        arg_pt.map(|pt| pt.split(',').map(|p| p.parse::<i32>().unwrap()).collect())
     };
+    let tactics::ResolvedTactics {
+        path: tactics_file_path,
+        tactics,
+        source: tactics_source,
+        temp: temp_tactics,
+    } = tactics::resolve(
+        arg_tactics_config.map(Path::new),
+        Path::new("tactics.json"),
+        pt_opt.as_deref(),
+    )?;
 
     log!("players: {}", log.names.join(", "));
-    log!("target: {}", log.names[actor as usize]);
-    log!("review has started, this may take several minutes...");
-
-    // do the review
-    let begin_review = chrono::Local::now();
-    let review_args = ReviewArgs {
-        akochan_exe: &akochan_exe,
-        akochan_dir: &akochan_dir,
-        tactics_config: &tactics_file_path,
-        events: &events,
-        target_actor: actor,
-        deviation_threshold: arg_deviation_threshold,
-        verbose: arg_verbose,
-    };
-    let review_result = review(&review_args).context("failed to review log")?;
-
-    // clean up temp file
-    if arg_pt.is_some() {
-        fs::remove_file(&tactics_file_path)
-            .with_context(|| format!("failed to clean up temp file {:?}", tactics_file_path))?;
-    }
 
     // determine language
     let lang = match arg_lang {
@@ -682,7 +1168,18 @@ fn main() -> Result<()> {
         _ => unreachable!(),
     };
 
-    // determine output file
+    // determine output format and file, which all actors share
+    let format = if arg_json || arg_decision.is_some() {
+        ReportFormat::Json
+    } else if arg_csv {
+        ReportFormat::Csv
+    } else if arg_ndjson {
+        ReportFormat::NdJson
+    } else if arg_summary {
+        ReportFormat::Summary
+    } else {
+        ReportFormat::Html
+    };
     let out = if let Some(filename) = arg_out_file {
         if filename == "-" {
             ReportOutput::Stdout
@@ -690,63 +1187,323 @@ fn main() -> Result<()> {
             ReportOutput::File(filename.to_owned())
         }
     } else {
-        let suffix = if arg_json { ".json" } else { ".html" };
-        let mut filename = log_source.default_output_filename(actor);
-        filename.push(suffix);
+        let mut filename = log_source.default_output_filename(&actors);
+        filename.push(format.suffix());
         ReportOutput::File(filename)
     };
 
     // prepare output, can be a file or stdout
     let mut out_write: Box<dyn Write> = match &out {
         ReportOutput::File(filename) => Box::new(
-            File::create(&filename)
+            File::create(filename)
                 .with_context(|| format!("failed to create output report file {:?}", filename))?,
         ),
         ReportOutput::Stdout => Box::new(io::stdout()),
     };
 
-    let now = chrono::Local::now();
-    let loading_time = (begin_review - begin_convert_log).to_std()?;
-    let review_time = (now - begin_review).to_std()?;
-    let meta = Metadata {
-        pt: &tactics.jun_pt,
-        game_length: &log.game_length.to_string(),
-        loading_time,
-        review_time,
-        log_id: if arg_anonymous {
-            None
+    // NDJSON lines are written during the review, each with its seat
+    let ndjson_writer = if format == ReportFormat::NdJson {
+        Some(NdJsonWriter::new(&mut out_write))
+    } else {
+        None
+    };
+    let write_ndjson_entry = |kyoku, honba, entry: &_| {
+        ndjson_writer
+            .as_ref()
+            .unwrap()
+            .write_entry(kyoku, honba, entry)
+    };
+
+    let review_args = |actor| ReviewArgs {
+        akochan_exe: &akochan_exe,
+        akochan_dir: &akochan_dir,
+        tactics_config: &tactics_file_path,
+        events: &events,
+        target_actor: actor,
+        deviation_threshold: arg_deviation_threshold,
+        verbose: arg_verbose,
+        timing: arg_timing,
+        skip_trivial_discards: arg_skip_trivial_discards,
+        list_forced: arg_list_forced,
+        on_entry: if ndjson_writer.is_some() {
+            Some(&write_ndjson_entry)
         } else {
-            log_source.log_id()
+            None
         },
-        use_placement_ev: arg_use_placement_ev,
-        deviation_threshold: arg_deviation_threshold,
-        total_reviewed: review_result.total_reviewed,
-        total_tolerated: review_result.total_tolerated,
-        total_problems: review_result.total_problems,
-        score: review_result.score,
-        version: &format!("v{} ({})", PKG_VERSION, GIT_HASH),
     };
 
-    // render the HTML report page or JSON
-    let view = View::new(
-        &review_result.kyokus,
-        actor,
-        splitted_raw_logs,
-        &meta,
-        lang,
-        layout,
-    );
-    if arg_json {
+    // review a single decision, --decision conflicts with --actors
+    if let Some((kyoku_index, junme)) = arg_decision {
+        let actor = actors[0];
+        log!("target: {}", log.names[actor as usize]);
+        log!("review has started, this may take several minutes...");
+
+        let mut akochan = Akochan::spawn(
+            &akochan_exe,
+            &akochan_dir,
+            &tactics_file_path,
+            actor,
+            arg_verbose,
+        )?;
+        let decision =
+            review_single_decision(&mut akochan, &review_args(actor), kyoku_index, actor, junme)
+                .context("failed to review decision")?;
+        akochan.finish()?;
+        if !decision.tied.is_empty() {
+            let tied: Vec<_> = decision
+                .tied
+                .iter()
+                .map(|moves| csv::action_to_string(moves))
+                .collect();
+            log!("about equally good: {}", tied.join(" or "));
+        }
         log!("writing output...");
-        json::to_writer(&mut out_write, &view).context("failed to write JSON result")?;
+        json::to_writer(&mut out_write, &decision).context("failed to write JSON result")?;
+
+        close_temp_tactics(temp_tactics)?;
+        log!("done");
+        return Ok(());
+    }
+
+    // the log is loaded and converted only once for all actors
+    let loading_time = (chrono::Local::now() - begin_convert_log).to_std()?;
+    // the id of the source comes first, the "ref" of a log file second
+    let log_id = if arg_anonymous {
+        None
     } else {
-        log!("rendering output...");
-        view.render(&mut out_write)
-            .context("failed to render HTML report")?;
+        log_source.log_id().or(game_info.log_id.as_deref())
+    };
+    let game_length = log.game_length.to_string();
+    let ev_scale = arg_ev_unit.scale(&tactics.jun_pt, arg_use_placement_ev);
+    let version = format!("v{} ({})", PKG_VERSION, GIT_HASH);
+    let mut reports = Vec::with_capacity(actors.len());
+    for &actor in &actors {
+        log!("target: {}", log.names[actor as usize]);
+        log!("review has started, this may take several minutes...");
+
+        // do the review
+        let begin_review = chrono::Local::now();
+        let review_args = review_args(actor);
+        let mut review_result = if let Some(manifest_path) = arg_manifest {
+            // everything that affects the entries besides the events, the pt
+            // of --use-placement-ev is already in the tactics file
+            let tactics_content = fs::read_to_string(&tactics_file_path).with_context(|| {
+                format!("failed to read tactics_config {:?}", tactics_file_path)
+            })?;
+            let config = format!(
+                "{}\n{}\n{}\n{}\n{}",
+                log.game_length,
+                arg_deviation_threshold,
+                arg_skip_trivial_discards,
+                arg_list_forced,
+                tactics_content,
+            );
+            review_with_manifest(&review_args, Path::new(manifest_path), &config)
+        } else {
+            review(&review_args)
+        }
+        .context("failed to review log")?;
+
+        // attach annotations
+        if let Some(annotations_path) = arg_annotations {
+            let annotations = annotation::load(Path::new(annotations_path))?;
+            let unmatched = annotation::apply(&mut review_result.kyokus, annotations);
+            for a in &unmatched {
+                log!("WARNING: annotation matches no reviewed decision: {}", a.id);
+            }
+        }
+
+        // drop the kyokus without anything to learn from, along with their
+        // viewers
+        let involved = if arg_skip_uninvolved {
+            retain_involved(&mut review_result.kyokus)
+        } else {
+            vec![true; review_result.kyokus.len()]
+        };
+        let omitted_kyokus = involved.iter().filter(|&&i| !i).count();
+        if omitted_kyokus > 0 {
+            log!(
+                "omitted {} kyoku(s) without any disagreement",
+                omitted_kyokus
+            );
+        }
+        let viewer_logs: Option<Vec<_>> = splitted_raw_logs.as_ref().map(|logs| {
+            logs.iter()
+                .zip(&involved)
+                .filter(|(_, &i)| i)
+                .map(|(&l, _)| l)
+                .collect()
+        });
+
+        if arg_deal_ins_only {
+            log_deal_ins(&review_result.kyokus, &events);
+        }
+        if arg_call_values {
+            log_call_values(&review_result.kyokus);
+        }
+        if arg_tenpai_breaks {
+            log_tenpai_breaks(&log, &review_result.kyokus, actor);
+        }
+        if arg_riichi_decisions {
+            log_riichi_decisions(
+                &review_result.kyokus,
+                &events,
+                actor,
+                log.has_aka,
+                arg_deviation_threshold,
+            );
+        }
+
+        let now = chrono::Local::now();
+        let review_time = (now - begin_review).to_std()?;
+        let timing = review_result.engine_time.map(|engine_time| {
+            let mut kyoku_timings: Vec<_> = review_result
+                .kyokus
+                .iter()
+                .map(|k| KyokuTiming {
+                    kyoku: k.kyoku,
+                    honba: k.honba,
+                    engine_time: k.engine_time.unwrap_or_default(),
+                })
+                .collect();
+            kyoku_timings.sort_by_key(|k| Reverse(k.engine_time));
+            kyoku_timings.truncate(3);
+
+            let overhead = review_time.saturating_sub(engine_time);
+            log!(
+                "akochan took {:?} of {:?} in total, overhead {:?}",
+                engine_time,
+                review_time,
+                overhead,
+            );
+            for k in &kyoku_timings {
+                log!(
+                    "slow kyoku: kyoku={} honba={} took {:?}",
+                    k.kyoku,
+                    k.honba,
+                    k.engine_time,
+                );
+            }
+
+            Timing {
+                engine_time,
+                overhead,
+                slowest_kyokus: kyoku_timings,
+            }
+        });
+        let meta = Metadata {
+            pt: &tactics.jun_pt,
+            tactics_source: &tactics_source,
+            game_length: &game_length,
+            game_length_forced: arg_game_length.is_some(),
+            loading_time,
+            review_time,
+            log_id,
+            actor_name: &log.names[actor as usize],
+            viewer_url: tenhou::GameInfo {
+                log_id: log_id.map(str::to_owned),
+            }
+            .viewer_url(actor),
+            use_placement_ev: arg_use_placement_ev,
+            placement: whole_game.then(|| log.placements()[actor as usize]),
+            oorasu_target: whole_game
+                .then(|| log.oorasu_targets())
+                .flatten()
+                .and_then(|targets| targets.get(actor as usize).copied()),
+            deviation_threshold: arg_deviation_threshold,
+            ev_precision: arg_ev_precision,
+            ev_unit: arg_ev_unit,
+            ev_scale,
+            total_reviewed: review_result.total_reviewed,
+            total_tolerated: review_result.total_tolerated,
+            total_problems: review_result.total_problems,
+            score: review_result.score,
+            omitted_kyokus,
+            non_tenpai_kyokus,
+            version: &version,
+            timing,
+        };
+
+        reports.push(HeroReport {
+            actor,
+            kyokus: review_result.kyokus,
+            viewer_logs,
+            meta,
+        });
+    }
+
+    // render the HTML report page or JSON, with a section for each actor
+    let views: Vec<_> = reports
+        .iter()
+        .map(|r| {
+            View::new(
+                &r.kyokus,
+                r.actor,
+                r.viewer_logs.as_deref(),
+                &r.meta,
+                lang,
+                layout,
+            )
+        })
+        .collect();
+    match format {
+        ReportFormat::Json => {
+            log!("writing output...");
+            // a single actor keeps the object of the plain report
+            if let [view] = &*views {
+                json::to_writer(&mut out_write, view)
+            } else {
+                json::to_writer(&mut out_write, &views)
+            }
+            .context("failed to write JSON result")?;
+        }
+        ReportFormat::Csv => {
+            log!("writing output...");
+            let game_id = log_id.unwrap_or_default();
+            let kyokus = reports.iter().flat_map(|r| &r.kyokus);
+            csv::write_csv(&mut out_write, game_id, kyokus, ev_scale)?;
+        }
+        ReportFormat::NdJson => (),
+        ReportFormat::Summary => {
+            log!("writing output...");
+            for (i, r) in reports.iter().enumerate() {
+                if i > 0 {
+                    writeln!(out_write).context("failed to write summary")?;
+                }
+                if reports.len() > 1 {
+                    writeln!(out_write, "{}", r.meta.actor_name)
+                        .context("failed to write summary")?;
+                }
+                summary::write_summary(
+                    &mut out_write,
+                    &r.kyokus,
+                    &log.names,
+                    lang,
+                    arg_ev_precision,
+                    ev_scale,
+                    r.meta.omitted_kyokus,
+                )?;
+                let blurb = summary::game_blurb(
+                    &r.kyokus,
+                    r.meta.actor_name,
+                    r.meta.placement,
+                    lang,
+                    arg_ev_precision,
+                    ev_scale,
+                );
+                writeln!(out_write, "\n{}", blurb).context("failed to write summary")?;
+            }
+        }
+        ReportFormat::Html => {
+            log!("rendering output...");
+            Page::new(&views, lang, layout)
+                .render(&mut out_write)
+                .context("failed to render HTML report")?;
+        }
     }
 
     // open the output page
-    if !arg_json && !arg_no_open {
+    if format == ReportFormat::Html && !arg_no_open {
         if let ReportOutput::File(filepath) = out {
             opener::open(&filepath).with_context(|| {
                 format!("failed to open rendered HTML report file {:?}", filepath)
@@ -754,21 +1511,637 @@ fn main() -> Result<()> {
         }
     }
 
+    close_temp_tactics(temp_tactics)?;
     log!("done");
     Ok(())
 }
 
+/// The review of one of the actors, which all go into the same report.
+struct HeroReport<'a> {
+    actor: u8,
+    kyokus: Vec<KyokuReview>,
+    viewer_logs: Option<Vec<tenhou::RawPartialLog<'a>>>,
+    meta: Metadata<'a>,
+}
+
+/// Removes the temp file holding the tactics, if there is one. It is also
+/// removed when dropped, but without telling whether that failed.
+fn close_temp_tactics(temp_tactics: Option<TempPath>) -> Result<()> {
+    if let Some(tmp_path) = temp_tactics {
+        let path = tmp_path.to_path_buf();
+        tmp_path
+            .close()
+            .with_context(|| format!("failed to clean up temp file {:?}", path))?;
+    }
+    Ok(())
+}
+
+/// Parses "INDEX,JUNME" of --decision.
+fn parse_decision(v: &str) -> Result<(usize, u8), String> {
+    let (index, junme) = v
+        .split_once(',')
+        .ok_or_else(|| format!("expected INDEX,JUNME, got {:?}", v))?;
+    let index = index
+        .parse()
+        .map_err(|err| format!("INDEX must be a number: {}", err))?;
+    let junme = junme
+        .parse()
+        .map_err(|err| format!("JUNME must be a number: {}", err))?;
+    Ok((index, junme))
+}
+
+/// Logs whether akochan would have folded on the deal-in of each kyoku, which
+/// must all be deal-ins of the actor, and how dangerous the pai was in
+/// hindsight judging from the winner's hand.
+fn log_deal_ins(kyokus: &[KyokuReview], events: &[Event]) {
+    for (kyoku_review, kyoku_events) in kyokus.iter().zip(split_kyokus(events)) {
+        let deal_in = deal_in::deal_in_entry(kyoku_review);
+
+        let category = deal_in.and_then(mistake::classify);
+        let verdict = match (deal_in, category) {
+            (None, _) => "not reviewed",
+            (Some(_), Some(MistakeCategory::OverPush)) => "akochan would have folded",
+            (Some(_), Some(_)) => "akochan would have played differently",
+            (Some(_), None) => "akochan would have played the same",
+        };
+        log!(
+            "deal-in: kyoku={} honba={} turn={}: {}",
+            kyoku_review.kyoku,
+            kyoku_review.honba,
+            deal_in.map(|e| e.junme.to_string()).unwrap_or_default(),
+            verdict,
+        );
+
+        // only the first one of a double ron
+        let danger = (0..kyoku_events.len()).find_map(|i| safety::deal_in_danger(kyoku_events, i));
+        if let Some(danger) = danger {
+            let waits: String = danger.waits.iter().map(|p| p.as_mpsz()).collect();
+            let is_suji = danger.class == safety::SafetyClass::Suji;
+            let trap = if is_suji && danger.suji_risk.is_some_and(|r| r.is_trap()) {
+                ", a suji trap"
+            } else {
+                ""
+            };
+            log!(
+                "  in hindsight: {} was {:?}{} against seat {} waiting on {} ({} left)",
+                danger.pai.as_mpsz(),
+                danger.class,
+                trap,
+                danger.winner,
+                waits,
+                danger.live_waits,
+            );
+        }
+
+        // how the hand could have folded instead, from right before the
+        // deal-in discard
+        if category != Some(MistakeCategory::OverPush) {
+            continue;
+        }
+        let fold_path = kyoku_events.iter().enumerate().find_map(|(i, ev)| match *ev {
+            Event::Hora { actor, target, .. } if actor != target => {
+                let discard_index = kyoku_events[..i].iter().rposition(|ev| {
+                    matches!(*ev, Event::Dahai { actor, .. } | Event::Kakan { actor, .. } if actor == target)
+                })?;
+                safety::fold_path_at(&kyoku_events[..discard_index], target)
+            }
+            _ => None,
+        });
+        if let Some(fold_path) = fold_path {
+            let order: String = fold_path.order.iter().map(|(p, _)| p.as_mpsz()).collect();
+            let outcome = if fold_path.survives() {
+                "enough to the draw".to_owned()
+            } else {
+                format!(
+                    "{} short of the draw",
+                    fold_path.turns_left - fold_path.safe_turns
+                )
+            };
+            log!(
+                "  fold path: {}, with {} genbutsu for {} discards left, {}",
+                order,
+                fold_path.safe_turns,
+                fold_path.turns_left,
+                outcome,
+            );
+        }
+    }
+}
+
+/// Prints each discard of `actor` that is not the most efficient one by
+/// shanten and ukeire, with a tally over the game.
+fn print_efficiency(log: &tenhou::Log, events: &[Event], actor: u8) {
+    println!("{}:", log.names[actor as usize]);
+
+    let mut total = 0;
+    let mut mistakes = 0;
+    for (kyoku, kyoku_events) in log.kyokus.iter().zip(split_kyokus(events)) {
+        let label = csv::kyoku_label(kyoku.meta.kyoku_num, kyoku.meta.honba);
+        for check in convlog::hand_value::efficiency(kyoku_events, actor) {
+            total += 1;
+            if !check.is_mistake() {
+                continue;
+            }
+            mistakes += 1;
+            let best: String = check.best.iter().map(|p| p.as_mpsz()).collect();
+            println!(
+                "  {}: discarded {} for {} shanten and {} ukeire, {} gives {} shanten and {} ukeire",
+                label,
+                check.discarded.as_mpsz(),
+                check.shanten,
+                check.ukeire,
+                best,
+                check.best_shanten,
+                check.best_ukeire,
+            );
+        }
+    }
+    println!("  {} of {} discards are inefficient", mistakes, total);
+}
+
+/// Logs each turn where `actor` broke tenpai, along with akochan's verdict on
+/// the discard.
+fn log_tenpai_breaks(log: &tenhou::Log, kyokus: &[KyokuReview], actor: u8) {
+    for kyoku in &log.kyokus {
+        let table = &kyoku.action_tables[actor as usize];
+        let kyoku_review = kyokus
+            .iter()
+            .find(|k| k.kyoku == kyoku.meta.kyoku_num && k.honba == kyoku.meta.honba);
+
+        for turn in table.tenpai_breaks() {
+            // junme counts the takes of the actor, where a daiminkan and its
+            // rinshan tsumo are one
+            let daiminkans = table.takes[..=turn]
+                .iter()
+                .filter(|t| matches!(t, tenhou::ActionItem::Naki(n) if n.contains('m')))
+                .count();
+            let junme = (turn + 1 - daiminkans) as u8;
+
+            let entry = kyoku_review.and_then(|k| {
+                k.entries.iter().find(|e| {
+                    e.junme == junme
+                        && matches!(
+                            e.actual.first(),
+                            Some(Event::Dahai { .. } | Event::Reach { .. })
+                        )
+                })
+            });
+            let verdict = match entry {
+                None => "not reviewed",
+                Some(entry) => match (entry.acceptance, mistake::classify(entry)) {
+                    (Acceptance::Agree, _) => "akochan agreed",
+                    (Acceptance::Tolerable, _) => {
+                        "akochan would have played differently, within the threshold"
+                    }
+                    (_, Some(MistakeCategory::OverFold)) => "akochan would have pushed",
+                    _ => "akochan would have played differently",
+                },
+            };
+            log!(
+                "tenpai-break: kyoku={} honba={} turn={}: {}",
+                kyoku.meta.kyoku_num,
+                kyoku.meta.honba,
+                junme,
+                verdict,
+            );
+        }
+    }
+}
+
+/// Logs how much each call in `kyokus` gained or lost against passing it.
+fn log_call_values(kyokus: &[KyokuReview]) {
+    for value in call_value::call_values(kyokus) {
+        let call = match value.entry.actual.first() {
+            Some(Event::Chi { .. }) => "chi",
+            Some(Event::Pon { .. }) => "pon",
+            _ => "daiminkan",
+        };
+        log!(
+            "call-value: kyoku={} honba={} turn={}: the {} {} {:.3} (call {:.3}, pass {:.3})",
+            value.kyoku,
+            value.honba,
+            value.entry.junme,
+            call,
+            if value.gained() { "gained" } else { "lost" },
+            value.gain().abs(),
+            value.call_ev,
+            value.pass_ev,
+        );
+    }
+}
+
+fn log_riichi_decisions(
+    kyokus: &[KyokuReview],
+    events: &[Event],
+    actor: u8,
+    has_aka: bool,
+    deviation_threshold: f64,
+) {
+    // keyed by kyoku and honba, as some kyokus may not be reviewed
+    let progressions: Vec<_> = split_kyokus(events)
+        .into_iter()
+        .filter_map(|kyoku_events| match kyoku_events.first() {
+            Some(&Event::StartKyoku {
+                bakaze,
+                kyoku,
+                honba,
+                ..
+            }) => {
+                let kyoku = (bakaze.as_u8() - Pai::East.as_u8()) * 4 + kyoku - 1;
+                let progression = hand_value::progression(kyoku_events, actor, has_aka);
+                Some(((kyoku, honba), progression))
+            }
+            _ => None,
+        })
+        .collect();
+
+    for decision in riichi_value::riichi_decisions(kyokus) {
+        let verdict = match (
+            decision.declared(),
+            decision.is_misjudged(deviation_threshold),
+        ) {
+            (true, false) => "declared riichi",
+            (false, false) => "did not declare riichi",
+            (true, true) => "declared riichi, but akochan would not",
+            (false, true) => "did not declare riichi, but akochan would",
+        };
+        log!(
+            "riichi-decision: kyoku={} honba={} turn={}: {} (riichi {:.3}, no riichi {:.3})",
+            decision.kyoku,
+            decision.honba,
+            decision.entry.junme,
+            verdict,
+            decision.riichi_ev,
+            decision.no_riichi_ev,
+        );
+
+        let value = progressions
+            .iter()
+            .find(|(key, _)| *key == (decision.kyoku, decision.honba))
+            .and_then(|(_, progression)| {
+                progression.iter().find(|v| v.junme == decision.entry.junme)
+            });
+        let coaching =
+            value.and_then(|v| Some((decision.coaching(deviation_threshold, v)?, v.han?, v)));
+        if let Some((coaching, han, value)) = coaching {
+            log!(
+                "riichi-decision: kyoku={} honba={} turn={}: a {}, {} to {} han on {} kind(s) of wait, akochan prefers {}",
+                decision.kyoku,
+                decision.honba,
+                decision.entry.junme,
+                coaching,
+                han.min,
+                han.max,
+                value.waits.len(),
+                if decision.riichi_gain() > 0. { "riichi" } else { "no riichi" },
+            );
+        }
+    }
+}
+
+fn find_actor_by_name(names: &[String; 4], actor_name: &str) -> Result<u8> {
+    let actor = names.iter().rposition(|n| n == actor_name);
+
+    // can not find actor index by name, return available players name
+    actor.map(|idx| idx as u8).with_context(|| {
+        format!(
+            "there is no player named \"{}\", available players: {}",
+            actor_name,
+            names.join(", "),
+        )
+    })
+}
+
+/// Reads a tenhou.net/6 log file into a string.
+///
+/// Gzip compressed files are recognized by their magic bytes and rejected
+/// with a dedicated error, as decompression is not built in.
+fn read_log_file(filename: &Path) -> Result<String> {
+    const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+    let body = fs::read(filename)
+        .with_context(|| format!("failed to read tenhou.net/6 log file {:?}", filename))?;
+    if body.starts_with(GZIP_MAGIC) {
+        bail!(
+            "{:?} is gzip compressed, which is not supported, decompress it first",
+            filename,
+        );
+    }
+
+    String::from_utf8(body)
+        .with_context(|| format!("tenhou.net/6 log file {:?} is not valid UTF-8", filename))
+}
+
+fn read_mjai_log(filename: &Path) -> Result<tenhou::Log> {
+    let file = File::open(filename)
+        .with_context(|| format!("failed to open mjai log file {:?}", filename))?;
+
+    let events = json::Deserializer::from_reader(BufReader::new(file))
+        .into_iter()
+        .collect::<json::Result<Vec<_>>>()
+        .context("failed to parse mjai log")?;
+
+    convlog::mjai_to_tenhou(&events).context("failed to convert mjai log into tenhou.net/6 format")
+}
+
+fn check_logs<'a>(filenames: impl Iterator<Item = &'a Path>) -> Result<()> {
+    let mut total = 0;
+    let mut failed = 0;
+
+    for filename in filenames {
+        total += 1;
+        match check_log(filename) {
+            Ok(()) => log!("{:?}: ok", filename),
+            Err(err) => {
+                failed += 1;
+                log!("{:?}: {:#}", filename, err);
+            }
+        }
+    }
+
+    log!("{} of {} logs passed the check", total - failed, total);
+    anyhow::ensure!(failed == 0, "{} logs failed the check", failed);
+
+    Ok(())
+}
+
+fn check_log(filename: &Path) -> Result<()> {
+    let body = read_log_file(filename)?;
+    let raw_log: tenhou::RawLog =
+        json::from_str(&body).context("failed to parse tenhou.net/6 log")?;
+
+    let log = tenhou::Log::from(raw_log);
+    for warning in &log.warnings {
+        log!("{:?}: WARNING: {}", filename, warning);
+    }
+    log.verify_kyotaku_flow()
+        .context("failed to validate tenhou.net/6 log")?;
+    if let Some(starting_score) = log.starting_score() {
+        log.verify_score_sum(starting_score)
+            .context("failed to validate tenhou.net/6 log")?;
+    }
+    for kyoku in &log.kyokus {
+        kyoku
+            .check_tile_conservation()
+            .context("failed to validate tenhou.net/6 log")?;
+    }
+    let options = convlog::ConvertOptions {
+        strict_tsumogiri: true,
+    };
+    convlog::tenhou_to_mjai_with_options(&log, options)
+        .context("failed to convert tenhou.net/6 log into mjai format")?;
+
+    Ok(())
+}
+
+fn print_luck_stats<'a>(filenames: impl Iterator<Item = &'a Path>) -> Result<()> {
+    #[derive(Deserialize)]
+    struct JsonReport {
+        kyokus: Vec<KyokuReview>,
+        metadata: JsonMetadata,
+    }
+    #[derive(Deserialize)]
+    struct JsonMetadata {
+        pt: [i32; 4],
+        use_placement_ev: bool,
+        placement: Option<u8>,
+    }
+
+    let mut reports = vec![];
+    for filename in filenames {
+        let file = File::open(filename)
+            .with_context(|| format!("failed to open JSON report {:?}", filename))?;
+        let report: JsonReport = json::from_reader(BufReader::new(file))
+            .with_context(|| format!("failed to parse JSON report {:?}", filename))?;
+        let placement = report.metadata.placement.with_context(|| {
+            format!(
+                "JSON report {:?} has no placement, it must review a whole game",
+                filename,
+            )
+        })?;
+        reports.push((report, placement));
+    }
+
+    let use_placement_ev = reports
+        .first()
+        .is_some_and(|(r, _)| r.metadata.use_placement_ev);
+    ensure!(
+        reports
+            .iter()
+            .all(|(r, _)| r.metadata.use_placement_ev == use_placement_ev),
+        "JSON reports of pt EV and placement EV can not be mixed",
+    );
+
+    let stats = luck::LuckSkill::new(reports.iter().map(|(report, placement)| luck::GameOutcome {
+        kyokus: &report.kyokus,
+        pt: report.metadata.pt,
+        use_placement_ev,
+        placement: *placement,
+    }));
+    let unit = if use_placement_ev { "placement" } else { "pt" };
+
+    println!("games\t{}", stats.games);
+    println!("net\t{:+.3} {}", stats.net, unit);
+    println!("mistakes\t{:+.3} {}", 0. - stats.mistake_loss, unit);
+    println!("variance\t{:+.3} {}", stats.variance(), unit);
+    match stats.efficiency() {
+        Some(efficiency) => println!("efficiency\t{:.1}% of optimal", efficiency * 100.),
+        None => println!("efficiency\tN/A"),
+    }
+
+    Ok(())
+}
+
+fn print_time_series<'a>(
+    filenames: impl Iterator<Item = &'a Path>,
+    bucket: time_series::Bucket,
+) -> Result<()> {
+    #[derive(Deserialize)]
+    struct JsonReport {
+        kyokus: Vec<KyokuReview>,
+        metadata: JsonMetadata,
+    }
+    #[derive(Deserialize)]
+    struct JsonMetadata {
+        log_id: Option<String>,
+    }
+
+    let mut reports = vec![];
+    for filename in filenames {
+        let file = File::open(filename)
+            .with_context(|| format!("failed to open JSON report {:?}", filename))?;
+        let report: JsonReport = json::from_reader(BufReader::new(file))
+            .with_context(|| format!("failed to parse JSON report {:?}", filename))?;
+        reports.push(report);
+    }
+
+    let games = reports.iter().map(|r| {
+        let date = r.metadata.log_id.as_deref().and_then(LogDate::from_log_id);
+        (date, &*r.kyokus)
+    });
+    let points = time_series::time_series(games, bucket);
+    println!("{}", json::to_string_pretty(&points)?);
+
+    Ok(())
+}
+
+fn print_leaderboard<'a>(filenames: impl Iterator<Item = &'a Path>) -> Result<()> {
+    #[derive(Deserialize)]
+    struct JsonReport {
+        kyokus: Vec<KyokuReview>,
+        target_actor: u8,
+        splited_logs: Option<Vec<JsonLog>>,
+        metadata: JsonMetadata,
+    }
+    #[derive(Deserialize)]
+    struct JsonLog {
+        name: [String; 4],
+    }
+    #[derive(Deserialize)]
+    struct JsonMetadata {
+        // older reports only have the names in the viewer logs
+        actor_name: Option<String>,
+        use_placement_ev: bool,
+    }
+
+    let mut reports = vec![];
+    for filename in filenames {
+        let file = File::open(filename)
+            .with_context(|| format!("failed to open JSON report {:?}", filename))?;
+        let report: JsonReport = json::from_reader(BufReader::new(file))
+            .with_context(|| format!("failed to parse JSON report {:?}", filename))?;
+        let name = match (&report.metadata.actor_name, &report.splited_logs) {
+            (Some(name), _) => name.clone(),
+            (None, Some(logs)) if !logs.is_empty() => {
+                logs[0].name[report.target_actor as usize % 4].clone()
+            }
+            _ => bail!(
+                "JSON report {:?} has no name of its actor, generate it again",
+                filename,
+            ),
+        };
+        reports.push((filename.to_string_lossy(), name, report));
+    }
+
+    let use_placement_ev = reports
+        .first()
+        .is_some_and(|(_, _, r)| r.metadata.use_placement_ev);
+    ensure!(
+        reports
+            .iter()
+            .all(|(_, _, r)| r.metadata.use_placement_ev == use_placement_ev),
+        "JSON reports of pt EV and placement EV can not be mixed",
+    );
+
+    let rows = leaderboard::leaderboard(reports.iter().map(|(source, name, report)| {
+        leaderboard::PlayerGame {
+            name,
+            source,
+            kyokus: &report.kyokus,
+        }
+    }));
+    println!("{}", json::to_string_pretty(&rows)?);
+
+    Ok(())
+}
+
+fn print_deal_in_stats<'a>(filenames: impl Iterator<Item = &'a Path>) -> Result<()> {
+    #[derive(Deserialize)]
+    struct JsonReport {
+        kyokus: Vec<KyokuReview>,
+        target_actor: u8,
+    }
+
+    let mut stats = deal_in::DealInStats::default();
+    let mut games = 0;
+    for filename in filenames {
+        let file = File::open(filename)
+            .with_context(|| format!("failed to open JSON report {:?}", filename))?;
+        let report: JsonReport = json::from_reader(BufReader::new(file))
+            .with_context(|| format!("failed to parse JSON report {:?}", filename))?;
+        stats.add_game(&report.kyokus, report.target_actor);
+        games += 1;
+    }
+
+    let percent = |v: Option<f64>| {
+        v.map(|v| format!("{:.1}%", v * 100.))
+            .unwrap_or_else(|| "n/a".to_owned())
+    };
+    println!("games\t{}", games);
+    println!("kyokus\t{}", stats.kyokus);
+    println!("deal-ins\t{} ({})", stats.deal_ins, percent(stats.rate()));
+    println!(
+        "average points lost\t{}",
+        stats
+            .avg_points_lost()
+            .map(|v| format!("{:.0}", v))
+            .unwrap_or_else(|| "n/a".to_owned()),
+    );
+    println!(
+        "akochan would have folded\t{} ({} of reviewed)",
+        stats.avoidable,
+        percent(stats.avoidable_fraction()),
+    );
+    println!("akochan would have played differently\t{}", stats.disagreed);
+    println!("not reviewed\t{}", stats.not_reviewed);
+
+    Ok(())
+}
+
+fn print_mistake_stats<'a>(filenames: impl Iterator<Item = &'a Path>) -> Result<()> {
+    #[derive(Deserialize)]
+    struct JsonReport {
+        kyokus: Vec<KyokuReview>,
+    }
+
+    let mut kyokus = vec![];
+    for filename in filenames {
+        let file = File::open(filename)
+            .with_context(|| format!("failed to open JSON report {:?}", filename))?;
+        let report: JsonReport = json::from_reader(BufReader::new(file))
+            .with_context(|| format!("failed to parse JSON report {:?}", filename))?;
+        kyokus.extend(report.kyokus);
+    }
+
+    let entries = kyokus.iter().flat_map(|k| &k.entries);
+    for (category, count) in mistake::rank(entries) {
+        println!("{}\t{}", count, category);
+    }
+
+    Ok(())
+}
+
 fn batch_download(out_dir_name: &Path, tenhou_ids_file: &Path) -> Result<()> {
-    fs::create_dir_all(&out_dir_name)
+    fs::create_dir_all(out_dir_name)
         .with_context(|| format!("failed to create {:?}", out_dir_name))?;
 
     log!("tenhou_ids_file: {:?}", tenhou_ids_file);
 
-    for line in BufReader::new(File::open(tenhou_ids_file)?).lines() {
-        let tenhou_id = line?;
+    let tenhou_ids = BufReader::new(File::open(tenhou_ids_file)?).lines();
+    batch_convert(out_dir_name, tenhou_ids, download::tenhou_log)
+}
+
+/// Converts the tenhou log of each of `tenhou_ids`, got by `fetch`, into an
+/// mjai log in `out_dir_name`, skipping those a previous run has finished.
+fn batch_convert(
+    out_dir_name: &Path,
+    tenhou_ids: impl IntoIterator<Item = io::Result<String>>,
+    mut fetch: impl FnMut(&str) -> Result<String>,
+) -> Result<()> {
+    for tenhou_id in tenhou_ids {
+        let tenhou_id = tenhou_id?;
+
+        // the marker is only created once the mjai log is fully written, so a
+        // log left half-written by an interrupted run is converted again
+        let mjai_out = out_dir_name.join(format!("{}.json", tenhou_id));
+        let done_marker = out_dir_name.join(format!("{}.done", tenhou_id));
+        if done_marker.exists() && mjai_out.exists() {
+            log!("skipping tenhou log {}, already converted", tenhou_id);
+            continue;
+        }
 
         log!("downloading tenhou log {} ...", tenhou_id);
-        let body = download::tenhou_log(&tenhou_id)
+        let body = fetch(&tenhou_id)
             .with_context(|| format!("failed to download tenhou log ID={:?}", tenhou_id))?;
 
         log!("parsing tenhou log {} ...", tenhou_id);
@@ -780,11 +2153,6 @@ fn batch_download(out_dir_name: &Path, tenhou_ids_file: &Path) -> Result<()> {
         let events = convlog::tenhou_to_mjai(&log)
             .context("failed to convert tenhou log into mjai format")?;
 
-        let mjai_out = {
-            let mut p = out_dir_name.to_owned();
-            p.push(tenhou_id + ".json");
-            p
-        };
         let mut mjai_out_file = File::create(&mjai_out)
             .with_context(|| format!("failed to create mjai out file {:?}", mjai_out))?;
 
@@ -793,7 +2161,110 @@ fn batch_download(out_dir_name: &Path, tenhou_ids_file: &Path) -> Result<()> {
             writeln!(mjai_out_file, "{}", to_write)
                 .with_context(|| format!("failed to write to mjai out file {:?}", mjai_out))?;
         }
+        mjai_out_file
+            .sync_all()
+            .with_context(|| format!("failed to write to mjai out file {:?}", mjai_out))?;
+
+        File::create(&done_marker)
+            .with_context(|| format!("failed to create marker file {:?}", done_marker))?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const GOOD_LOG: &str = include_str!("../convlog/tests/testdata/double_ron.json");
+
+    /// Writes `body` into `dir` as `name` and returns its path.
+    fn write_log(dir: &TempDir, name: &str, body: &[u8]) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::write(&path, body).unwrap();
+        path
+    }
+
+    #[test]
+    fn check_good_and_bad_logs() {
+        let dir = TempDir::new().unwrap();
+        let good = write_log(&dir, "good.json", GOOD_LOG.as_bytes());
+        // five 1m in the haipai of the first seat
+        let bad_tiles = GOOD_LOG.replacen("[14,51,16,17,19,", "[11,11,11,11,11,", 1);
+        let bad_tiles = write_log(&dir, "bad_tiles.json", bad_tiles.as_bytes());
+        let truncated = write_log(&dir, "truncated.json", &GOOD_LOG.as_bytes()[..100]);
+
+        check_log(&good).unwrap();
+        let err = check_log(&bad_tiles).unwrap_err();
+        assert!(format!("{:#}", err).starts_with("failed to validate tenhou.net/6 log"));
+        let err = check_log(&truncated).unwrap_err();
+        assert!(format!("{:#}", err).starts_with("failed to parse tenhou.net/6 log"));
+
+        check_logs(vec![good.as_path(), good.as_path()].into_iter()).unwrap();
+        let err =
+            check_logs(vec![good.as_path(), bad_tiles.as_path(), truncated.as_path()].into_iter())
+                .unwrap_err();
+        assert_eq!(err.to_string(), "2 logs failed the check");
+    }
+
+    #[test]
+    fn gzip_or_unparsable_log_file() {
+        let dir = TempDir::new().unwrap();
+        // the magic bytes, method deflate and no flags
+        let gzip = write_log(&dir, "log.json.gz", &[0x1f, 0x8b, 0x08, 0x00, 0x00]);
+        let garbage = write_log(&dir, "log.json", b"not a log");
+
+        let err = read_log_file(&gzip).unwrap_err();
+        assert!(err.to_string().contains("is gzip compressed"));
+        let err = check_log(&gzip).unwrap_err();
+        assert!(err.to_string().contains("is gzip compressed"));
+
+        // anything else is read as is and left to the JSON parser
+        assert_eq!(read_log_file(&garbage).unwrap(), "not a log");
+        let err = check_log(&garbage).unwrap_err();
+        assert_eq!(err.to_string(), "failed to parse tenhou.net/6 log");
+
+        let err = read_log_file(&write_log(&dir, "latin1.json", &[0xe9])).unwrap_err();
+        assert!(err.to_string().contains("is not valid UTF-8"));
+    }
+
+    #[test]
+    fn resume_interrupted_batch() {
+        let dir = TempDir::new().unwrap();
+        let ids = || vec!["a", "b", "c"].into_iter().map(|id| Ok(id.to_owned()));
+        let mut fetched = vec![];
+
+        // the run is interrupted while fetching the second game
+        let err = batch_convert(dir.path(), ids(), |id| {
+            fetched.push(id.to_owned());
+            ensure!(id != "b", "connection reset");
+            Ok(GOOD_LOG.to_owned())
+        })
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("connection reset"));
+        assert_eq!(fetched, ["a", "b"]);
+        assert!(dir.path().join("a.done").exists());
+        assert!(!dir.path().join("b.done").exists());
+        // as if it were killed while writing the second game
+        let half_written = write_log(&dir, "b.json", b"{\"type\":\"start_game\"");
+        let finished = fs::read_to_string(dir.path().join("a.json")).unwrap();
+
+        fetched.clear();
+        batch_convert(dir.path(), ids(), |id| {
+            fetched.push(id.to_owned());
+            Ok(GOOD_LOG.to_owned())
+        })
+        .unwrap();
+        // the finished game is skipped, the half-written one converted again
+        assert_eq!(fetched, ["b", "c"]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.json")).unwrap(),
+            finished
+        );
+        assert_eq!(fs::read_to_string(&half_written).unwrap(), finished);
+        for id in &["a", "b", "c"] {
+            assert!(dir.path().join(format!("{}.done", id)).exists());
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use crate::log;
+use crate::review::{review, KyokuReview, Review, ReviewArgs};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+
+use anyhow::{ensure, Context, Result};
+use convlog::mjai::Event;
+use serde::{Deserialize, Serialize};
+use serde_json as json;
+
+/// A record of already reviewed kyokus, keyed by a hash of everything that
+/// can affect the review result of a kyoku.
+///
+/// The hash is FNV-1a of 64 bits, which stays the same across builds and
+/// platforms, so a manifest can be shared between them.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    kyokus: HashMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestEntry {
+    raw_score: f64,
+    review: KyokuReview,
+}
+
+impl Manifest {
+    /// Loads the manifest from `path`, an absent file is an empty manifest.
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to open manifest {:?}", path))
+            }
+        };
+
+        json::from_reader(BufReader::new(file))
+            .with_context(|| format!("failed to parse manifest {:?}", path))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file =
+            File::create(path).with_context(|| format!("failed to create manifest {:?}", path))?;
+
+        json::to_writer(BufWriter::new(file), self)
+            .with_context(|| format!("failed to write manifest {:?}", path))
+    }
+
+    pub fn get(&self, key: &str) -> Option<KyokuReview> {
+        self.kyokus.get(key).map(|entry| KyokuReview {
+            raw_score: entry.raw_score,
+            ..entry.review.clone()
+        })
+    }
+
+    pub fn insert(&mut self, key: String, review: KyokuReview) {
+        let entry = ManifestEntry {
+            raw_score: review.raw_score,
+            review,
+        };
+        self.kyokus.insert(key, entry);
+    }
+}
+
+/// Reviews only the kyokus that are not recorded in the manifest at
+/// `manifest_path`, reusing the recorded results for the rest, then records
+/// the newly reviewed kyokus in the manifest.
+///
+/// `config` must cover every review setting that is not part of the events,
+/// such as the content of tactics config.
+pub fn review_with_manifest(
+    review_args: &ReviewArgs,
+    manifest_path: &Path,
+    config: &str,
+) -> Result<Review> {
+    let mut manifest = Manifest::load(manifest_path)?;
+
+    let events = review_args.events;
+    let kyokus = split_kyokus(events);
+    let keys: Vec<_> = kyokus
+        .iter()
+        .map(|kyoku_events| kyoku_key(kyoku_events, review_args.target_actor, config))
+        .collect();
+
+    let missing: Vec<_> = keys
+        .iter()
+        .enumerate()
+        .filter(|(_, key)| !manifest.kyokus.contains_key(*key))
+        .map(|(idx, _)| idx)
+        .collect();
+    log!(
+        "{} of {} kyokus are found in manifest",
+        kyokus.len() - missing.len(),
+        kyokus.len(),
+    );
+
+    let mut fresh_reviews = if missing.is_empty() {
+        vec![]
+    } else {
+        // review the missing kyokus only, just like the kyoku filter does
+        let mut partial_events = vec![events[0].clone()];
+        for &idx in &missing {
+            partial_events.extend_from_slice(kyokus[idx]);
+        }
+        partial_events.push(events[events.len() - 1].clone());
+
+        let partial_args = ReviewArgs {
+            events: &partial_events,
+            ..*review_args
+        };
+        review(&partial_args)?.kyokus
+    }
+    .into_iter();
+    ensure!(
+        fresh_reviews.len() == missing.len(),
+        "expected {} kyoku reviews, got {}",
+        missing.len(),
+        fresh_reviews.len(),
+    );
+
+    let mut kyoku_reviews = Vec::with_capacity(kyokus.len());
+    for (idx, key) in keys.into_iter().enumerate() {
+        let kyoku_review = if missing.contains(&idx) {
+            // fresh_reviews has the same length as missing
+            let kyoku_review = fresh_reviews.next().unwrap();
+            manifest.insert(key, kyoku_review.clone());
+            kyoku_review
+        } else {
+            // checked in the filter of missing above
+            manifest.get(&key).unwrap()
+        };
+        kyoku_reviews.push(kyoku_review);
+    }
+
+    manifest.save(manifest_path)?;
+
+    Ok(Review::from_kyokus(kyoku_reviews))
+}
+
+/// Splits the events into kyokus, each of which starts with StartKyoku and
+/// ends with EndKyoku.
+pub(crate) fn split_kyokus(events: &[Event]) -> Vec<&[Event]> {
+    let mut kyokus = vec![];
+    let mut start = None;
+
+    for (i, event) in events.iter().enumerate() {
+        match event {
+            Event::StartKyoku { .. } => start = Some(i),
+            Event::EndKyoku => {
+                if let Some(start) = start.take() {
+                    kyokus.push(&events[start..=i]);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    kyokus
+}
+
+fn kyoku_key(kyoku_events: &[Event], target_actor: u8, config: &str) -> String {
+    let mut hasher = Fnv1a::default();
+
+    hasher.write(&[target_actor]);
+    // the length keeps the config apart from the events
+    hasher.write(&(config.len() as u64).to_le_bytes());
+    hasher.write(config.as_bytes());
+    for event in kyoku_events {
+        // Event does not implement Hash, its JSON form is used instead, which
+        // never contains a raw newline.
+        hasher.write(json::to_string(event).unwrap().as_bytes());
+        hasher.write(b"\n");
+    }
+
+    format!("{:016x}", hasher.0)
+}
+
+/// The 64-bit FNV-1a hash, see <http://www.isthe.com/chongo/tech/comp/fnv/>.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::{entry, kyoku_events};
+    use crate::review::Acceptance;
+    use std::time::Duration;
+
+    const HAIPAI: &str = "1m 2m 3m 4p 5p 6p 7s 8s 9s E E S N";
+
+    #[test]
+    fn fnv1a_reference_values() {
+        let hash = |s: &[u8]| {
+            let mut hasher = Fnv1a::default();
+            hasher.write(s);
+            hasher.0
+        };
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn kyoku_key_is_stable() {
+        let events = kyoku_events(HAIPAI, &[("W", "N")]);
+        let kyoku = split_kyokus(&events)[0];
+        let key = kyoku_key(kyoku, 0, "{}");
+        assert_eq!(key.len(), 16);
+        assert_eq!(key, kyoku_key(kyoku, 0, "{}"));
+        assert_ne!(key, kyoku_key(kyoku, 1, "{}"));
+        assert_ne!(key, kyoku_key(kyoku, 0, "{ }"));
+        assert_ne!(key, kyoku_key(&kyoku[..kyoku.len() - 2], 0, "{}"));
+    }
+
+    #[test]
+    fn split_and_combine() {
+        let first = kyoku_events(HAIPAI, &[("W", "N")]);
+        let second = kyoku_events(HAIPAI, &[("P", "P"), ("F", "F")]);
+        // the kyoku of `first`, the kyoku of `second`, end_game
+        let mut events = first[..first.len() - 1].to_vec();
+        events.extend_from_slice(&second);
+
+        let kyokus = split_kyokus(&events);
+        assert_eq!(kyokus.len(), 2);
+        assert_eq!(kyokus[0], &first[..first.len() - 1]);
+        assert_eq!(kyokus[1], &second[..second.len() - 1]);
+        for kyoku in &kyokus {
+            assert!(matches!(kyoku[0], Event::StartKyoku { .. }));
+            assert!(matches!(kyoku[kyoku.len() - 1], Event::EndKyoku));
+        }
+
+        let agreed = || entry(Acceptance::Agree, &[("N", 1.), ("S", 0.5)], "N");
+        let kyoku_reviews = vec![
+            KyokuReview {
+                entries: vec![
+                    agreed(),
+                    entry(Acceptance::Disagree, &[("N", 1.), ("S", 0.5)], "S"),
+                ],
+                raw_score: 1.5,
+                ..Default::default()
+            },
+            KyokuReview {
+                kyoku: 1,
+                entries: vec![
+                    agreed(),
+                    entry(Acceptance::Tolerable, &[("N", 1.), ("S", 0.99)], "S"),
+                ],
+                trivial_discards: 1,
+                raw_score: 2.5,
+                engine_time: Some(Duration::from_secs(1)),
+                ..Default::default()
+            },
+        ];
+        let review = Review::from_kyokus(kyoku_reviews);
+        assert_eq!(review.total_reviewed, 5);
+        assert_eq!(review.total_problems, 1);
+        assert_eq!(review.total_tolerated, 1);
+        assert!((review.score - 0.64).abs() < 1e-9);
+        assert_eq!(review.engine_time, Some(Duration::from_secs(1)));
+    }
+}
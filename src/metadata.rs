@@ -1,13 +1,31 @@
+use crate::ev_unit::EvUnit;
 use std::time::Duration;
 
+use convlog::tenhou::PlacementTarget;
 use serde::Serialize;
 
 #[derive(Serialize)]
 pub struct Metadata<'a> {
     pub pt: &'a [i32; 4],
+    /// Path of the tactics config, or "embedded".
+    pub tactics_source: &'a str,
     pub game_length: &'a str,
+    /// Whether `game_length` is given by --game-length.
+    pub game_length_forced: bool,
     pub log_id: Option<&'a str>,
+    /// Name of the actor, already hidden by --anonymous.
+    pub actor_name: &'a str,
+    /// Link to the game on tenhou.net from the actor, only for tenhou logs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub viewer_url: Option<String>,
     pub use_placement_ev: bool,
+    /// Final placement of the actor, only when the whole game is reviewed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placement: Option<u8>,
+    /// What the actor needs to rise one place in the oorasu, only when the
+    /// whole game is reviewed and it ends in the oorasu.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oorasu_target: Option<PlacementTarget>,
 
     #[serde(with = "humantime_serde")]
     pub loading_time: Duration,
@@ -15,10 +33,43 @@ pub struct Metadata<'a> {
     pub review_time: Duration,
 
     pub deviation_threshold: f64,
+    /// Decimal places of the EVs shown in the HTML report, by --ev-precision.
+    pub ev_precision: usize,
+    /// The unit of the EVs shown in the HTML report, by --ev-unit.
+    pub ev_unit: EvUnit,
+    /// The factor from the EVs of the review to those shown in `ev_unit`.
+    pub ev_scale: f64,
     pub total_reviewed: usize,
     pub total_tolerated: usize,
     pub total_problems: usize,
     pub score: f64,
+    /// Kyokus left out of the report by --skip-uninvolved.
+    pub omitted_kyokus: usize,
+    /// Kyokus left out before review by --tenpai-only, where the actor never
+    /// reached tenpai.
+    pub non_tenpai_kyokus: usize,
 
     pub version: &'a str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<Timing>,
+}
+
+/// Time spent by akochan, enabled by --timing.
+#[derive(Serialize)]
+pub struct Timing {
+    #[serde(with = "humantime_serde")]
+    pub engine_time: Duration,
+    /// Time of review minus time spent by akochan.
+    #[serde(with = "humantime_serde")]
+    pub overhead: Duration,
+    pub slowest_kyokus: Vec<KyokuTiming>,
+}
+
+#[derive(Serialize)]
+pub struct KyokuTiming {
+    pub kyoku: u8,
+    pub honba: u8,
+    #[serde(with = "humantime_serde")]
+    pub engine_time: Duration,
 }
@@ -0,0 +1,185 @@
+use crate::review::{compare_action_strict, Acceptance, Entry};
+use std::collections::HashMap;
+use std::fmt;
+
+use convlog::mjai::Event;
+use serde::{Deserialize, Serialize};
+
+/// Differences of deal-in probability within this margin are not considered
+/// as a matter of pushing or folding.
+const DEAL_IN_PROB_MARGIN: f64 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MistakeCategory {
+    /// Discarded a safer pai than akochan's choice.
+    OverFold,
+    /// Discarded a more dangerous pai than akochan's choice.
+    OverPush,
+    /// Declared riichi when akochan would not, or the other way around.
+    RiichiTiming,
+    /// Called when akochan would not, or the other way around.
+    Naki,
+    /// Discarded a different pai of similar danger.
+    Efficiency,
+    /// Anything else, such as a missed hora.
+    Other,
+}
+
+impl fmt::Display for MistakeCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::OverFold => "over-folding",
+            Self::OverPush => "over-pushing",
+            Self::RiichiTiming => "riichi timing",
+            Self::Naki => "naki",
+            Self::Efficiency => "inefficient discard",
+            Self::Other => "other",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Classifies a review entry, returns None if it is not a mistake.
+pub fn classify(entry: &Entry) -> Option<MistakeCategory> {
+    if !matches!(entry.acceptance, Acceptance::Disagree) {
+        return None;
+    }
+
+    let is_reach = |moves: &[Event]| moves.iter().any(|ev| matches!(ev, Event::Reach { .. }));
+    if is_reach(&entry.expected) != is_reach(&entry.actual) {
+        return Some(MistakeCategory::RiichiTiming);
+    }
+
+    let category = match (entry.expected.first(), entry.actual.first()) {
+        (Some(Event::Dahai { .. }), Some(Event::Dahai { .. }))
+        | (Some(Event::Reach { .. }), Some(Event::Reach { .. })) => {
+            classify_discard(entry).unwrap_or(MistakeCategory::Efficiency)
+        }
+        (Some(ee), Some(aa)) if is_naki(ee) || is_naki(aa) => MistakeCategory::Naki,
+        _ => MistakeCategory::Other,
+    };
+    Some(category)
+}
+
+/// Counts the mistakes of each category, from the most to the least common.
+pub fn rank<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Vec<(MistakeCategory, usize)> {
+    let mut counts = HashMap::new();
+    for category in entries.into_iter().filter_map(classify) {
+        *counts.entry(category).or_insert(0) += 1;
+    }
+
+    let mut ranked: Vec<_> = counts.into_iter().collect();
+    ranked.sort_by(|(ca, na), (cb, nb)| nb.cmp(na).then(ca.cmp(cb)));
+    ranked
+}
+
+/// Compares the deal-in probability of the actual discard against the
+/// expected one.
+///
+/// Returns None if akochan does not give the probabilities, which happens on
+/// early turns or high shanten.
+fn classify_discard(entry: &Entry) -> Option<MistakeCategory> {
+    let expected_prob = entry.details.first()?.review.total_houjuu_hai_prob_now?;
+    let actual_prob = entry
+        .details
+        .iter()
+        .find(|d| compare_action_strict(&entry.actual, &d.moves))?
+        .review
+        .total_houjuu_hai_prob_now?;
+
+    let category = if actual_prob + DEAL_IN_PROB_MARGIN < expected_prob {
+        MistakeCategory::OverFold
+    } else if actual_prob > expected_prob + DEAL_IN_PROB_MARGIN {
+        MistakeCategory::OverPush
+    } else {
+        MistakeCategory::Efficiency
+    };
+    Some(category)
+}
+
+fn is_naki(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Chi { .. }
+            | Event::Pon { .. }
+            | Event::Daiminkan { .. }
+            | Event::Kakan { .. }
+            | Event::Ankan { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::entry;
+
+    use serde_json as json;
+
+    fn events(value: json::Value) -> Vec<Event> {
+        json::from_value(value).unwrap()
+    }
+
+    /// A mistake discarding S instead of N, with the deal-in probabilities of
+    /// both.
+    fn discard(expected_prob: f64, actual_prob: f64) -> Entry {
+        let mut entry = entry(Acceptance::Disagree, &[("N", 1.), ("S", 0.5)], "S");
+        entry.details[0].review.total_houjuu_hai_prob_now = Some(expected_prob);
+        entry.details[1].review.total_houjuu_hai_prob_now = Some(actual_prob);
+        entry
+    }
+
+    #[test]
+    fn classify_categories() {
+        let agreed = entry(Acceptance::Agree, &[("N", 1.), ("S", 0.5)], "N");
+        assert_eq!(classify(&agreed), None);
+        let tolerated = entry(Acceptance::Tolerable, &[("N", 1.), ("S", 0.99)], "S");
+        assert_eq!(classify(&tolerated), None);
+
+        assert_eq!(
+            classify(&discard(0.1, 0.02)),
+            Some(MistakeCategory::OverFold)
+        );
+        assert_eq!(
+            classify(&discard(0.02, 0.1)),
+            Some(MistakeCategory::OverPush)
+        );
+        assert_eq!(
+            classify(&discard(0.1, 0.105)),
+            Some(MistakeCategory::Efficiency)
+        );
+        // no probabilities
+        let early = entry(Acceptance::Disagree, &[("N", 1.), ("S", 0.5)], "S");
+        assert_eq!(classify(&early), Some(MistakeCategory::Efficiency));
+
+        let mut riichi = discard(0.1, 0.02);
+        riichi.expected = events(json::json!([
+            {"type": "reach", "actor": 0},
+            {"type": "dahai", "actor": 0, "pai": "N", "tsumogiri": false},
+        ]));
+        assert_eq!(classify(&riichi), Some(MistakeCategory::RiichiTiming));
+
+        let mut naki = entry(Acceptance::Disagree, &[("N", 1.)], "N");
+        naki.expected = events(json::json!([
+            {"type": "pon", "actor": 0, "target": 3, "pai": "E", "consumed": ["E", "E"]},
+        ]));
+        naki.actual = events(json::json!([{"type": "none"}]));
+        assert_eq!(classify(&naki), Some(MistakeCategory::Naki));
+
+        let mut hora = naki.clone();
+        hora.expected = events(json::json!([
+            {"type": "hora", "actor": 0, "target": 3, "deltas": [0, 0, 0, 0]},
+        ]));
+        assert_eq!(classify(&hora), Some(MistakeCategory::Other));
+
+        let entries = [discard(0.02, 0.1), naki, discard(0.02, 0.1), hora, agreed];
+        assert_eq!(
+            rank(&entries),
+            [
+                (MistakeCategory::OverPush, 2),
+                (MistakeCategory::Naki, 1),
+                (MistakeCategory::Other, 1),
+            ],
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use crate::csv::kyoku_label;
+use crate::review::Entry;
+use std::cell::RefCell;
+use std::io::prelude::*;
+
+use anyhow::{Context, Result};
+use convlog::DecisionId;
+use serde::Serialize;
+use serde_json as json;
+
+#[derive(Serialize)]
+struct Line<'a> {
+    id: DecisionId,
+    kyoku: u8,
+    honba: u8,
+    label: String,
+    seat: u8,
+    #[serde(flatten)]
+    entry: &'a Entry,
+}
+
+/// Streams reviewed decisions as newline-delimited JSON, one self-contained
+/// object per line, flushed as soon as each decision is reviewed.
+pub struct NdJsonWriter<W> {
+    w: RefCell<W>,
+}
+
+impl<W: Write> NdJsonWriter<W> {
+    pub fn new(w: W) -> Self {
+        Self { w: RefCell::new(w) }
+    }
+
+    pub fn write_entry(&self, kyoku: u8, honba: u8, entry: &Entry) -> Result<()> {
+        let line = Line {
+            id: DecisionId::new(kyoku, honba, entry.actor, entry.junme),
+            kyoku,
+            honba,
+            label: kyoku_label(kyoku, honba),
+            seat: entry.actor,
+            entry,
+        };
+        // the compact form never contains a raw newline
+        let s = json::to_string(&line).context("failed to serialize NDJSON line")?;
+
+        let mut w = self.w.borrow_mut();
+        writeln!(w, "{}", s).context("failed to write NDJSON line")?;
+        w.flush().context("failed to flush NDJSON output")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::entry;
+    use crate::review::Acceptance;
+
+    #[test]
+    fn standalone_lines() {
+        let first = entry(Acceptance::Disagree, &[("N", 1.2), ("S", 1.1)], "S");
+        let mut second = entry(Acceptance::Agree, &[("N", 1.2), ("S", 1.1)], "N");
+        second.actor = 2;
+        second.junme = 7;
+
+        let mut out = vec![];
+        {
+            let writer = NdJsonWriter::new(&mut out);
+            writer.write_entry(5, 1, &first).unwrap();
+            writer.write_entry(5, 1, &second).unwrap();
+        }
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with('\n'));
+
+        // each line parses on its own
+        let lines: Vec<json::Value> = out
+            .lines()
+            .map(|line| json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["id"], "S2-1-s0-t1");
+        assert_eq!(lines[0]["label"], "S2-1");
+        assert_eq!(lines[0]["acceptance"], "disagree");
+        assert_eq!(lines[1]["id"], "S2-1-s2-t7");
+        assert_eq!(lines[1]["seat"], 2);
+        assert_eq!(lines[1]["acceptance"], "agree");
+    }
+}
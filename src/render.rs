@@ -15,7 +15,6 @@ static TEMPLATES: Lazy<Tera> = Lazy::new(|| {
     tera.register_function("kyoku_to_string_ja", kyoku_to_string_ja);
     tera.register_function("kyoku_to_string_en", kyoku_to_string_en);
     tera.register_function("pretty_round", pretty_round);
-	tera.register_function("pretty_round_two", pretty_round_two);
 
     tera.add_raw_templates(vec![
         ("macros.html", include_str!("../templates/macros.html")),
@@ -29,7 +28,7 @@ static TEMPLATES: Lazy<Tera> = Lazy::new(|| {
     tera
 });
 
-#[derive(Serialize)]
+#[derive(Clone, Copy, Serialize)]
 pub enum Language {
     // The string is used in html lang attribute, as per BCP47.
     #[serde(rename = "ja")]
@@ -38,7 +37,7 @@ pub enum Language {
     English,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Copy, Serialize)]
 pub enum Layout {
     #[serde(rename = "horizontal")]
     Horizontal,
@@ -46,61 +45,63 @@ pub enum Layout {
     Vertical,
 }
 
-#[allow(clippy::unnecessary_wraps)]
-fn kyoku_to_string_ja(args: &HashMap<String, Value>) -> tera::Result<Value> {
+/// Returns names like "東一局 1 本場" and "East 1-1" for a kyoku in tenhou.net/6
+/// format, which counts from 0.
+pub fn kyoku_to_string(kyoku: u8, honba: u8, lang: Language) -> String {
     const BAKAZE_KANJI: &[&str] = &["東", "南", "西", "北"];
     const NUM_KANJI: &[&str] = &["一", "二", "三", "四"];
+    const BAKAZE_ENG: &[&str] = &["East", "South", "West", "North"];
+    const NUM_ENG: &[&str] = &["1", "2", "3", "4"];
 
-    let kyoku = args.get("kyoku").and_then(|p| p.as_u64()).unwrap_or(0) as usize;
-    let honba = args.get("honba").and_then(|p| p.as_u64()).unwrap_or(0) as usize;
-
-    let s = if honba == 0 {
-        format!("{}{}局", BAKAZE_KANJI[kyoku / 4], NUM_KANJI[kyoku % 4])
-    } else {
-        format!(
+    let (kyoku, honba) = (kyoku as usize % 16, honba as usize);
+    match lang {
+        Language::Japanese if honba == 0 => {
+            format!("{}{}局", BAKAZE_KANJI[kyoku / 4], NUM_KANJI[kyoku % 4])
+        }
+        Language::Japanese => format!(
             "{}{}局 {} 本場",
             BAKAZE_KANJI[kyoku / 4],
             NUM_KANJI[kyoku % 4],
             honba,
-        )
-    };
-    Ok(Value::String(s))
+        ),
+        Language::English if honba == 0 => {
+            format!("{} {}", BAKAZE_ENG[kyoku / 4], NUM_ENG[kyoku % 4])
+        }
+        Language::English => {
+            format!("{} {}-{}", BAKAZE_ENG[kyoku / 4], NUM_ENG[kyoku % 4], honba)
+        }
+    }
 }
 
-#[allow(clippy::unnecessary_wraps)]
-fn kyoku_to_string_en(args: &HashMap<String, Value>) -> tera::Result<Value> {
-    const BAKAZE_ENG: &[&str] = &["East", "South", "West", "North"];
-    const NUM_ENG: &[&str] = &["1", "2", "3", "4"];
-
-    let kyoku = args.get("kyoku").and_then(|p| p.as_u64()).unwrap_or(0) as usize;
-    let honba = args.get("honba").and_then(|p| p.as_u64()).unwrap_or(0) as usize;
-
-    let s = if honba == 0 {
-        format!("{} {}", BAKAZE_ENG[kyoku / 4], NUM_ENG[kyoku % 4])
-    } else {
-        format!("{} {}-{}", BAKAZE_ENG[kyoku / 4], NUM_ENG[kyoku % 4], honba)
-    };
-    Ok(Value::String(s))
+fn kyoku_args(args: &HashMap<String, Value>) -> (u8, u8) {
+    let kyoku = args.get("kyoku").and_then(|p| p.as_u64()).unwrap_or(0) as u8;
+    let honba = args.get("honba").and_then(|p| p.as_u64()).unwrap_or(0) as u8;
+    (kyoku, honba)
 }
 
 #[allow(clippy::unnecessary_wraps)]
-fn pretty_round(args: &HashMap<String, Value>) -> tera::Result<Value> {
-    let prec = args.get("prec").and_then(|p| p.as_u64()).unwrap_or(5);
-
-    if let Some(num) = args.get("num").and_then(|n| n.as_f64()) {
-        let pow = (10usize).pow(prec as u32) as f64;
-        let f = (num * pow).round() / pow;
-        let s = format!("{:.1$}", f, prec as usize);
-
-        return Ok(Value::String(s));
-    }
+fn kyoku_to_string_ja(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let (kyoku, honba) = kyoku_args(args);
+    Ok(Value::String(kyoku_to_string(
+        kyoku,
+        honba,
+        Language::Japanese,
+    )))
+}
 
-    Ok(Value::Null)
+#[allow(clippy::unnecessary_wraps)]
+fn kyoku_to_string_en(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let (kyoku, honba) = kyoku_args(args);
+    Ok(Value::String(kyoku_to_string(
+        kyoku,
+        honba,
+        Language::English,
+    )))
 }
 
 #[allow(clippy::unnecessary_wraps)]
-fn pretty_round_two(args: &HashMap<String, Value>) -> tera::Result<Value> {
-    let prec = args.get("prec").and_then(|p| p.as_u64()).unwrap_or(2);
+fn pretty_round(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let prec = args.get("prec").and_then(|p| p.as_u64()).unwrap_or(5);
 
     if let Some(num) = args.get("num").and_then(|n| n.as_f64()) {
         let pow = (10usize).pow(prec as u32) as f64;
@@ -149,12 +150,38 @@ where
             layout,
         }
     }
+}
+
+/// The HTML report page, with a section for each of the heroes reviewed by
+/// --actors, in their order.
+#[derive(Serialize)]
+pub struct Page<'p, 'a, L>
+where
+    L: AsRef<[RawPartialLog<'a>]> + Serialize,
+{
+    reports: &'p [View<'a, L>],
+    lang: Language,
+    layout: Layout,
+}
+
+impl<'p, 'a, L> Page<'p, 'a, L>
+where
+    L: AsRef<[RawPartialLog<'a>]> + Serialize,
+{
+    #[inline]
+    pub fn new(views: &'p [View<'a, L>], lang: Language, layout: Layout) -> Self {
+        Self {
+            reports: views,
+            lang,
+            layout,
+        }
+    }
 
     pub fn render<W>(&self, w: &mut W) -> Result<()>
     where
         W: Write,
     {
-        let ctx = tera::Context::from_serialize(&self)?;
+        let ctx = tera::Context::from_serialize(self)?;
         let result =
             TEMPLATES.render("report.html", &ctx).with_context(|| {
                 match json::to_string(&self) {
@@ -167,3 +194,191 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ev_unit::EvUnit;
+    use crate::review::tests::entry;
+    use crate::review::Acceptance;
+    use std::time::Duration;
+
+    fn metadata(ev_precision: usize) -> Metadata<'static> {
+        Metadata {
+            pt: &[90, 45, 0, -135],
+            tactics_source: "embedded",
+            game_length: "hanchan",
+            game_length_forced: false,
+            log_id: None,
+            actor_name: "Alice",
+            viewer_url: None,
+            use_placement_ev: false,
+            placement: None,
+            oorasu_target: None,
+            loading_time: Duration::default(),
+            review_time: Duration::default(),
+            deviation_threshold: 0.05,
+            ev_precision,
+            ev_unit: EvUnit::Pt,
+            ev_scale: 1.,
+            total_reviewed: 1,
+            total_tolerated: 0,
+            total_problems: 1,
+            score: 0.,
+            omitted_kyokus: 0,
+            non_tenpai_kyokus: 0,
+            version: "test",
+            timing: None,
+        }
+    }
+
+    fn kyokus() -> Vec<KyokuReview> {
+        vec![KyokuReview {
+            entries: vec![entry(
+                Acceptance::Disagree,
+                &[("N", 1.234567), ("S", 1.111111)],
+                "S",
+            )],
+            ..Default::default()
+        }]
+    }
+
+    #[test]
+    fn pretty_round_digits() {
+        let round = |num: f64, prec: u64| {
+            let args = vec![
+                ("num".to_owned(), json::json!(num)),
+                ("prec".to_owned(), json::json!(prec)),
+            ]
+            .into_iter()
+            .collect();
+            pretty_round(&args).unwrap()
+        };
+        assert_eq!(round(0.123456, 3), "0.123");
+        assert_eq!(round(0.1235, 2), "0.12");
+        assert_eq!(round(1.5, 0), "2");
+        assert_eq!(round(2., 3), "2.000");
+    }
+
+    #[test]
+    fn rounded_html_and_full_json() {
+        let kyokus = kyokus();
+        let metadata = metadata(2);
+        let view = View::new(
+            &kyokus,
+            0,
+            None::<Vec<RawPartialLog<'_>>>,
+            &metadata,
+            Language::English,
+            Layout::Vertical,
+        );
+
+        let mut html = vec![];
+        Page::new(
+            std::slice::from_ref(&view),
+            Language::English,
+            Layout::Vertical,
+        )
+        .render(&mut html)
+        .unwrap();
+        let html = String::from_utf8(html).unwrap();
+        // the deviation and the EVs of the details
+        assert!(html.contains("-0.12&nbsp;EV"));
+        assert!(html.contains(">1.23</span>"));
+        assert!(html.contains(">1.11</span>"));
+        // the full value stays in the title
+        assert!(html.contains(r#"title="1.234567""#));
+
+        let view = json::to_value(&view).unwrap();
+        let entry = &view["kyokus"][0]["entries"][0];
+        assert_eq!(entry["details"][0]["review"]["pt_exp_total"], 1.234567);
+        assert!((entry["dev"].as_f64().unwrap() - 0.123456).abs() < 1e-12);
+    }
+
+    #[test]
+    fn kyoku_sections_and_anchors() {
+        let mut kyokus = kyokus();
+        kyokus.push(KyokuReview {
+            kyoku: 5,
+            honba: 1,
+            ..Default::default()
+        });
+        let metadata = metadata(2);
+        let views = vec![View::new(
+            &kyokus,
+            0,
+            None::<Vec<RawPartialLog<'_>>>,
+            &metadata,
+            Language::English,
+            Layout::Vertical,
+        )];
+
+        let mut html = vec![];
+        Page::new(&views, Language::English, Layout::Vertical)
+            .render(&mut html)
+            .unwrap();
+        let html = String::from_utf8(html).unwrap();
+        // each kyoku is its own collapsed section
+        assert_eq!(
+            html.matches(r#"<details class="kyoku-section">"#).count(),
+            2
+        );
+        for anchor in &["kyoku-0-0", "kyoku-5-1"] {
+            // the heading is the target, linked from the table of contents
+            // and from itself
+            assert_eq!(html.matches(&format!(r#"<h1 id="{}""#, anchor)).count(), 1);
+            assert_eq!(html.matches(&format!(r##"href="#{}""##, anchor)).count(), 2);
+        }
+        // only the kyoku with reviewed decisions shows the EV loss
+        assert_eq!(html.matches(r#"<span class="ev-loss">"#).count(), 1);
+        assert!(html.contains("EV loss: 0.12"));
+        assert!(!html.contains("hero-heading"));
+    }
+
+    #[test]
+    fn sections_of_two_heroes() {
+        let kyokus = kyokus();
+        let alice = metadata(2);
+        let bob = Metadata {
+            actor_name: "Bob",
+            ..metadata(2)
+        };
+        let views = vec![
+            View::new(
+                &kyokus,
+                0,
+                None::<Vec<RawPartialLog<'_>>>,
+                &alice,
+                Language::English,
+                Layout::Vertical,
+            ),
+            View::new(
+                &kyokus,
+                2,
+                None::<Vec<RawPartialLog<'_>>>,
+                &bob,
+                Language::English,
+                Layout::Vertical,
+            ),
+        ];
+
+        let mut html = vec![];
+        Page::new(&views, Language::English, Layout::Vertical)
+            .render(&mut html)
+            .unwrap();
+        let html = String::from_utf8(html).unwrap();
+        assert_eq!(html.matches(r#"class="hero-report""#).count(), 2);
+        assert_eq!(html.matches(r#"class="kyoku-section""#).count(), 2);
+        // the toggles are shared by both sections
+        assert_eq!(html.matches(r#"id="buttonViewers""#).count(), 1);
+
+        let alice_at = html.find(r#"<h2 id="actor-0" class="hero-heading">Alice</h2>"#);
+        let bob_at = html.find(r#"<h2 id="actor-2" class="hero-heading">Bob</h2>"#);
+        assert!(alice_at.unwrap() < bob_at.unwrap());
+        // the anchors of the same kyoku do not collide
+        assert!(html.contains(r#"<h1 id="actor-0-kyoku-0-0""#));
+        assert!(html.contains(r#"<h1 id="actor-2-kyoku-0-0""#));
+        assert!(html.contains(r##"<a href="#actor-2-kyoku-0-0">"##));
+        assert!(!html.contains(r#"id="kyoku-0-0""#));
+    }
+}
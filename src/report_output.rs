@@ -4,3 +4,25 @@ pub enum ReportOutput {
     File(OsString),
     Stdout,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Html,
+    Json,
+    Csv,
+    NdJson,
+    Summary,
+}
+
+impl ReportFormat {
+    #[inline]
+    pub const fn suffix(self) -> &'static str {
+        match self {
+            Self::Html => ".html",
+            Self::Json => ".json",
+            Self::Csv => ".csv",
+            Self::NdJson => ".ndjson",
+            Self::Summary => ".txt",
+        }
+    }
+}
@@ -1,15 +1,13 @@
+use crate::engine::{Akochan, Engine};
 use crate::log;
 use crate::state::State;
-use std::io::prelude::*;
-use std::io::BufReader;
 use std::path::Path;
-use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context, Result};
 use convlog::mjai::Event;
-use convlog::Pai;
+use convlog::{shanten, Pai};
 use serde::{Deserialize, Serialize};
-use serde_json as json;
 use serde_with::{serde_as, DisplayFromStr};
 
 pub struct Review {
@@ -18,23 +16,44 @@ pub struct Review {
     pub total_problems: usize,
     pub score: f64,
     pub kyokus: Vec<KyokuReview>,
+    pub engine_time: Option<Duration>, // Some iff timing is enabled
 }
 
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct KyokuReview {
     pub kyoku: u8, // in tenhou.net/6 format, counts from 0
     pub honba: u8,
-	pub hand_score: f64,
+    pub hand_score: f64,
+    #[serde(skip)]
+    pub raw_score: f64, // sum of move scores, used to combine reviews
     pub end_status: Vec<Event>, // must be either multiple Horas or one Ryukyoku
     pub entries: Vec<Entry>,
+    /// Number of agreed trivial discards left out of `entries`, see
+    /// [`ReviewArgs::skip_trivial_discards`]. They still count toward the
+    /// totals and the scores.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub trivial_discards: usize,
+
+    #[serde(default, with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine_time: Option<Duration>,
 }
 
 #[serde_as]
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entry {
     pub acceptance: Acceptance,
     pub junme: u8,
-	pub dev: f64,
+    pub dev: f64,
+    /// EV gap between the best action and the runner-up one, telling how
+    /// clearly akochan prefers its choice. None if akochan gives no EVs or
+    /// only one action.
+    ///
+    /// A gap within `deviation_threshold` is likely no more than the noise of
+    /// the estimates, so the runner-up is then as good as the best, and a
+    /// move within the threshold of the runner-up is only tolerable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margin: Option<f64>,
     pub actor: u8,
     #[serde_as(as = "DisplayFromStr")]
     pub pai: Pai,
@@ -45,9 +64,22 @@ pub struct Entry {
     pub actual: Vec<Event>,   // at most 2 events
 
     pub details: Vec<DetailedAction>,
+
+    #[serde(default, with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine_time: Option<Duration>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>, // given by --annotations
+
+    /// Whether the decision is forced, see [`ReviewArgs::list_forced`]. A
+    /// forced entry is always agreed and never counts toward the totals or
+    /// the scores.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub forced: bool,
 }
 
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Acceptance {
     Disagree,
@@ -58,10 +90,10 @@ pub enum Acceptance {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Stat {
     // these Options are None iff `rule_base_flag && !ori_flag` is true in akochan
-    total_houjuu_hai_prob_now: Option<f64>,
-    total_houjuu_hai_value_now: Option<f64>,
-    pt_exp_after: Option<f64>,
-    pt_exp_total: Option<f64>,
+    pub total_houjuu_hai_prob_now: Option<f64>,
+    pub total_houjuu_hai_value_now: Option<f64>,
+    pub pt_exp_after: Option<f64>,
+    pub pt_exp_total: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,6 +102,92 @@ pub struct DetailedAction {
     pub review: Stat,
 }
 
+impl Entry {
+    /// Returns the moves of akochan's top candidates whose EVs are within
+    /// `margin` of the best one, including the best one itself, in akochan's
+    /// order. Only the best one is returned if akochan gives no EVs.
+    pub fn tied_moves(&self, margin: f64) -> Vec<&[Event]> {
+        let best_ev = match self.details.first() {
+            Some(best) => best.review.pt_exp_total,
+            None => return vec![],
+        };
+
+        self.details
+            .iter()
+            .enumerate()
+            .take_while(|(i, action)| {
+                *i == 0
+                    || matches!(
+                        (best_ev, action.review.pt_exp_total),
+                        (Some(best), Some(ev)) if best - ev <= margin,
+                    )
+            })
+            .map(|(_, action)| action.moves.as_slice())
+            .collect()
+    }
+}
+
+impl KyokuReview {
+    /// Returns true if the hero agreed with akochan on every reviewed decision
+    /// of the kyoku, or had no meaningful choice at all, such as when dealt
+    /// out early or when every decision was forced.
+    pub fn is_uninvolved(&self) -> bool {
+        self.entries
+            .iter()
+            .filter(|e| !e.forced)
+            .all(|e| matches!(e.acceptance, Acceptance::Agree))
+    }
+}
+
+/// Drops the kyokus the hero is uninvolved in, see
+/// [`KyokuReview::is_uninvolved`]. Returns whether each of the kyokus given is
+/// kept, in order, so that anything alongside them can be dropped too.
+pub fn retain_involved(kyokus: &mut Vec<KyokuReview>) -> Vec<bool> {
+    let involved: Vec<_> = kyokus.iter().map(|k| !k.is_uninvolved()).collect();
+    let mut flags = involved.iter();
+    kyokus.retain(|_| *flags.next().unwrap());
+    involved
+}
+
+impl Review {
+    /// Combines kyoku reviews, possibly from different runs, into one review.
+    pub fn from_kyokus(kyokus: Vec<KyokuReview>) -> Self {
+        let mut total_reviewed = 0;
+        let mut total_tolerated = 0;
+        let mut total_problems = 0;
+        let mut raw_score = 0.;
+        let mut engine_time = None;
+
+        for kyoku_review in &kyokus {
+            for entry in kyoku_review.entries.iter().filter(|e| !e.forced) {
+                match entry.acceptance {
+                    Acceptance::Disagree => total_problems += 1,
+                    Acceptance::Tolerable => total_tolerated += 1,
+                    Acceptance::Agree => (),
+                };
+            }
+            total_reviewed += kyoku_review.entries.iter().filter(|e| !e.forced).count()
+                + kyoku_review.trivial_discards;
+            raw_score += kyoku_review.raw_score;
+            if let Some(t) = kyoku_review.engine_time {
+                *engine_time.get_or_insert_with(Duration::default) += t;
+            }
+        }
+
+        Self {
+            total_reviewed,
+            total_tolerated,
+            total_problems,
+            score: (raw_score / total_reviewed as f64).powf(2.),
+            kyokus,
+            engine_time,
+        }
+    }
+}
+
+pub type OnEntry<'a> = &'a dyn Fn(u8, u8, &Entry) -> Result<()>;
+
+#[derive(Clone, Copy)]
 pub struct ReviewArgs<'a> {
     pub akochan_exe: &'a Path,
     pub akochan_dir: &'a Path,
@@ -78,80 +196,84 @@ pub struct ReviewArgs<'a> {
     pub target_actor: u8,
     pub deviation_threshold: f64,
     pub verbose: bool,
+    pub timing: bool,
+    /// Leaves out of the entries routine discards akochan agrees with, see
+    /// [`shanten::is_trivial_discard`]. They are still counted as agreed
+    /// decisions.
+    pub skip_trivial_discards: bool,
+    /// Keeps forced decisions in the review instead of leaving them out.
+    ///
+    /// A decision is forced when it is the target actor's own turn, not in
+    /// riichi, and akochan lists a single candidate, which is then the only
+    /// legal action, such as the only kind of pai the hand can discard.
+    /// Either way it is excluded from the totals and the scores, so the
+    /// accuracy is not inflated by decisions without any choice.
+    pub list_forced: bool,
+    /// Called with the kyoku and honba of each entry as soon as it is created.
+    pub on_entry: Option<OnEntry<'a>>,
 }
 
+/// Reviews `review_args.events` with akochan, see [`review_with_engine`].
 pub fn review(review_args: &ReviewArgs) -> Result<Review> {
     let &ReviewArgs {
         akochan_exe,
         akochan_dir,
         tactics_config,
-        events,
         target_actor,
-        deviation_threshold,
         verbose,
+        ..
     } = review_args;
 
-    let mut kyoku_reviews = vec![];
-
-    let target_actor_string = target_actor.to_string();
-    let args = &[
-        "pipe_detailed".as_ref(),
+    let mut akochan = Akochan::spawn(
+        akochan_exe,
+        akochan_dir,
         tactics_config,
-        target_actor_string.as_ref(),
-    ];
+        target_actor,
+        verbose,
+    )?;
+    let review = review_with_engine(&mut akochan, review_args)?;
+    akochan.finish()?;
+    Ok(review)
+}
 
-    if verbose {
-        log!("$ cd {:?}", akochan_dir);
-        log!(
-            "$ {:?}{}",
-            akochan_exe,
-            args.iter()
-                .fold("".to_owned(), |acc, p| format!("{} {:?}", acc, p))
-        );
-    }
+/// Reviews `review_args.events` with any [`Engine`], ignoring the akochan
+/// paths in `review_args`.
+pub fn review_with_engine(engine: &mut impl Engine, review_args: &ReviewArgs) -> Result<Review> {
+    let &ReviewArgs {
+        events,
+        target_actor,
+        deviation_threshold,
+        verbose,
+        timing,
+        skip_trivial_discards,
+        list_forced,
+        on_entry,
+        ..
+    } = review_args;
 
-    let mut akochan = Command::new(akochan_exe)
-        .args(args)
-        .current_dir(Path::new(akochan_dir))
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::inherit())
-        .spawn()
-        .context("failed to spawn akochan")?;
-
-    let stdin = akochan
-        .stdin
-        .as_mut()
-        .context("failed to get stdin of akochan")?;
-    let mut stdout_lines = BufReader::new(
-        akochan
-            .stdout
-            .as_mut()
-            .context("failed to get stdout of akochan")?,
-    )
-    .lines();
+    let mut kyoku_reviews = vec![];
 
     let events_len = events.len();
     let mut total_reviewed = 0;
     let mut total_tolerated = 0;
     let mut total_problems = 0;
     let mut raw_score = 0.;
-	
-	let mut kyoku_total_reviewed = 0;
-	let mut kyoku_total_score = 0.;
+
+    let mut kyoku_total_reviewed = 0;
+    let mut kyoku_total_score = 0.;
+    let mut kyoku_trivial_discards = 0;
+    let mut total_engine_time = Duration::default();
+    let mut kyoku_engine_time = Duration::default();
 
     let mut kyoku_review = KyokuReview::default();
     let mut state = State::new(target_actor);
     let mut junme = 0;
     let mut entries = vec![];
     let mut is_reached = false;
+    let mut starting_shanten = 8;
 
     for (i, event) in events.iter().enumerate() {
-        let to_write = json::to_string(event).unwrap();
-        writeln!(stdin, "{}", to_write).context("failed to write to akochan")?;
-        if verbose {
-            log!("> {}", to_write);
-        }
+        engine.feed(event)?;
 
         // update the state
         state.update(event).context("failed to update state")?;
@@ -169,26 +291,33 @@ pub fn review(review_args: &ReviewArgs) -> Result<Review> {
                 let kyoku = (bakaze.as_u8() - Pai::East.as_u8()) * 4 + kk - 1;
                 kyoku_review.kyoku = kyoku;
                 kyoku_review.honba = honba;
-				kyoku_review.hand_score = 0.0;
+                kyoku_review.hand_score = 0.0;
                 is_reached = false;
-				
-				kyoku_total_reviewed = 0;
-				kyoku_total_score = 0.;
-				
+                starting_shanten = shanten::calc(&tehai34(state.tehai.view()), 0);
+
+                kyoku_total_reviewed = 0;
+                kyoku_total_score = 0.;
+                kyoku_trivial_discards = 0;
+                kyoku_engine_time = Duration::default();
+
                 continue;
             }
 
             Event::EndKyoku => {
                 kyoku_review.entries = entries.clone();
 
-				if kyoku_total_reviewed==0 
-				{
-						kyoku_review.hand_score=0.0;
-				}
-				else 
-				{
-					kyoku_review.hand_score = (kyoku_total_score / kyoku_total_reviewed as f64).powf(2.); // anon edit
-				}
+                if kyoku_total_reviewed == 0 {
+                    kyoku_review.hand_score = 0.0;
+                } else {
+                    // anon edit
+                    kyoku_review.hand_score =
+                        (kyoku_total_score / kyoku_total_reviewed as f64).powf(2.);
+                }
+                kyoku_review.raw_score = kyoku_total_score;
+                kyoku_review.trivial_discards = kyoku_trivial_discards;
+                if timing {
+                    kyoku_review.engine_time = Some(kyoku_engine_time);
+                }
                 entries.clear();
 
                 kyoku_reviews.push(kyoku_review.clone());
@@ -199,7 +328,7 @@ pub fn review(review_args: &ReviewArgs) -> Result<Review> {
             }
 
             Event::Hora { .. } | Event::Ryukyoku { .. } => {
-				kyoku_review.hand_score=0.0;
+                kyoku_review.hand_score = 0.0;
                 kyoku_review.end_status.push(event.clone());
                 continue;
             }
@@ -247,17 +376,11 @@ pub fn review(review_args: &ReviewArgs) -> Result<Review> {
             bail!("wrong size of input events, expected to have 4 more");
         }
 
-        // be careful, stdout_lines.next() may block.
-        let line = stdout_lines
-            .next()
-            .context("failed to read from akochan: unexpected EOF")?
-            .context("failed to read from akochan")?;
-        if verbose {
-            log!("< {}", line.trim());
-        }
-
-        let actions: Vec<DetailedAction> =
-            json::from_str(&line).context("failed to parse JSON output of akochan")?;
+        let begin_engine = Instant::now();
+        let actions = engine.evaluate()?;
+        let engine_time = begin_engine.elapsed();
+        total_engine_time += engine_time;
+        kyoku_engine_time += engine_time;
 
         if actions.is_empty() || actions.iter().any(|a| a.moves.is_empty()) {
             log!("WARNING: actions or some moves in actions is empty");
@@ -282,12 +405,27 @@ pub fn review(review_args: &ReviewArgs) -> Result<Review> {
             }
         }
 
-		let mut dev = 0.0;
+        let forced = actions.len() == 1 && matches!(*event, Event::Tsumo { .. });
+        if forced && !list_forced {
+            if verbose {
+                log!("skipping forced decision");
+            }
+            continue;
+        }
+
+        let mut dev = 0.0;
         let expected_action = &actions[0].moves; // best move
         let is_equal_or_innocent = compare_action(actual_action, expected_action, target_actor)
             .context("invalid state in event")?;
         let actual_action_strict = next_action_strict(actual_action, target_actor);
 
+        let margin = match (actions[0].review.pt_exp_total, actions.get(1)) {
+            (Some(best_ev), Some(runner_up)) => {
+                runner_up.review.pt_exp_total.map(|ev| best_ev - ev)
+            }
+            _ => None,
+        };
+
         let (move_score, acceptance) = if is_equal_or_innocent {
             (1., Acceptance::Agree) // it is an acceptable move
         } else if deviation_threshold <= 0. {
@@ -327,14 +465,22 @@ pub fn review(review_args: &ReviewArgs) -> Result<Review> {
                     let move_score = if range > 0. { 1. - error / range } else { 1. };
 
                     dev = expected_ev - actual_ev;
-                    if dev <= deviation_threshold {
+                    // the best is no clearer than the noise over the
+                    // runner-up, which is then as good a reference
+                    let tolerance = match margin {
+                        Some(margin) if margin <= deviation_threshold => {
+                            deviation_threshold + margin
+                        }
+                        _ => deviation_threshold,
+                    };
+                    if dev <= tolerance {
                         if verbose {
                             log!(
-                                "expected_ev - actual_ev <= deviation_threshold ({} - {} = {} < {})",
+                                "expected_ev - actual_ev <= tolerance ({} - {} = {} < {})",
                                 expected_ev,
                                 actual_ev,
                                 dev,
-                                deviation_threshold,
+                                tolerance,
                             );
                         }
                         (move_score, Acceptance::Tolerable) // not acceptable but tolerable
@@ -362,6 +508,21 @@ pub fn review(review_args: &ReviewArgs) -> Result<Review> {
             }
         };
 
+        let trivial_pai = match actual_action_strict.first() {
+            Some(&Event::Dahai { pai, .. })
+                if skip_trivial_discards
+                    && matches!(acceptance, Acceptance::Agree)
+                    && junme <= 6
+                    && state.fuuros.is_empty() =>
+            {
+                let tehai = tehai34(state.tehai.view());
+                pai.as_index34()
+                    .is_some_and(|kind| shanten::is_trivial_discard(&tehai, kind, starting_shanten))
+                    .then_some(pai)
+            }
+            _ => None,
+        };
+
         // handle kakan
         let (actor, pai, is_kakan) = match *event {
             Event::Dahai { actor, pai, .. } | Event::Tsumo { actor, pai, .. } => {
@@ -373,20 +534,33 @@ pub fn review(review_args: &ReviewArgs) -> Result<Review> {
             }
         };
 
-        match acceptance {
-            Acceptance::Disagree => total_problems += 1,
-            Acceptance::Tolerable => total_tolerated += 1,
-            Acceptance::Agree => (),
-        };
-        total_reviewed += 1;
-        raw_score += move_score;
-		kyoku_total_reviewed += 1;
-		kyoku_total_score += move_score;
+        if !forced {
+            match acceptance {
+                Acceptance::Disagree => total_problems += 1,
+                Acceptance::Tolerable => total_tolerated += 1,
+                Acceptance::Agree => (),
+            };
+            total_reviewed += 1;
+            raw_score += move_score;
+            kyoku_total_reviewed += 1;
+            kyoku_total_score += move_score;
+        }
 
-       let entry = Entry {
+        if let Some(pai) = trivial_pai {
+            if !forced {
+                kyoku_trivial_discards += 1;
+            }
+            if verbose {
+                log!("leaving out trivial discard of {}", pai);
+            }
+            continue;
+        }
+
+        let entry = Entry {
             acceptance,
-			junme,
-			dev,
+            junme,
+            dev,
+            margin,
             actor,
             pai,
             is_kakan,
@@ -394,6 +568,9 @@ pub fn review(review_args: &ReviewArgs) -> Result<Review> {
             expected: expected_action.to_vec(),
             actual: actual_action_strict,
             details: actions,
+            engine_time: if timing { Some(engine_time) } else { None },
+            note: None,
+            forced,
         };
 
         log!(
@@ -407,28 +584,40 @@ pub fn review(review_args: &ReviewArgs) -> Result<Review> {
         if verbose {
             log!("{:?}", entry);
         }
+        if let Some(on_entry) = on_entry {
+            on_entry(kyoku_review.kyoku, kyoku_review.honba, &entry)?;
+        }
 
         entries.push(entry);
     }
 
-    let ecode = akochan.wait()?;
-    if !ecode.success() {
-        if let Some(code) = ecode.code() {
-            bail!("non-zero exit code: {}", code);
-        } else {
-            bail!("non-zero exit code: Process terminated by signal");
-        }
-    }
-
     Ok(Review {
         total_problems,
         total_tolerated,
         total_reviewed,
         score: (raw_score / total_reviewed as f64).powf(2.),
         kyokus: kyoku_reviews,
+        engine_time: if timing {
+            Some(total_engine_time)
+        } else {
+            None
+        },
     })
 }
 
+/// Counts the pais of `tehai` by the 34 kinds.
+fn tehai34(tehai: &[Pai]) -> [u8; 34] {
+    let mut ret = [0; 34];
+    for kind in tehai.iter().filter_map(|p| p.as_index34()) {
+        ret[kind] += 1;
+    }
+    ret
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
 fn next_action_for_compare(events: &[Event]) -> &[Event] {
     match events[0] {
         Event::Dora { .. } | Event::ReachAccepted { .. } => next_action_for_compare(&events[1..]),
@@ -466,7 +655,7 @@ fn next_action_strict(rough_action: &[Event], target_actor: u8) -> Vec<Event> {
 }
 
 /// Returns true if actual_action is the same as expected_action.
-fn compare_action_strict(actual_action: &[Event], expected_action: &[Event]) -> bool {
+pub fn compare_action_strict(actual_action: &[Event], expected_action: &[Event]) -> bool {
     expected_action
         .iter()
         .zip(actual_action)
@@ -666,3 +855,347 @@ fn compare_action(
         _ => bail!("unexpected event: {:?}", actual),
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    use serde_json as json;
+
+    /// An [`Engine`] that answers each evaluation with the next scripted
+    /// candidates, in place of akochan.
+    pub(crate) struct MockEngine {
+        answers: VecDeque<Vec<DetailedAction>>,
+    }
+
+    impl MockEngine {
+        pub(crate) fn new(answers: Vec<Vec<DetailedAction>>) -> Self {
+            Self {
+                answers: answers.into(),
+            }
+        }
+    }
+
+    impl Engine for MockEngine {
+        fn feed(&mut self, _event: &Event) -> Result<()> {
+            Ok(())
+        }
+
+        fn evaluate(&mut self) -> Result<Vec<DetailedAction>> {
+            self.answers.pop_front().context("no more scripted answers")
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            if !self.answers.is_empty() {
+                bail!("{} scripted answers are left", self.answers.len());
+            }
+            Ok(())
+        }
+    }
+
+    /// Candidate discards of seat 0 with their EVs, the best first.
+    pub(crate) fn discards(candidates: &[(&str, f64)]) -> Vec<DetailedAction> {
+        candidates
+            .iter()
+            .map(|&(pai, ev)| {
+                json::from_value(json::json!({
+                    "moves": [{"type": "dahai", "actor": 0, "pai": pai, "tsumogiri": false}],
+                    "review": {
+                        "total_houjuu_hai_prob_now": 0.,
+                        "total_houjuu_hai_value_now": 0.,
+                        "pt_exp_after": ev,
+                        "pt_exp_total": ev,
+                    },
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    /// The only candidate on a discard of the others that seat 0 can not call.
+    pub(crate) fn pass() -> Vec<DetailedAction> {
+        vec![json::from_value(json::json!({
+            "moves": [{"type": "none"}],
+            "review": {
+                "total_houjuu_hai_prob_now": null,
+                "total_houjuu_hai_value_now": null,
+                "pt_exp_after": null,
+                "pt_exp_total": null,
+            },
+        }))
+        .unwrap()]
+    }
+
+    /// A hand-made entry of seat 0 at junme 1, discarding `actual` among
+    /// `candidates` as in [`discards`], with the EV gap to the best.
+    pub(crate) fn entry(acceptance: Acceptance, candidates: &[(&str, f64)], actual: &str) -> Entry {
+        let details = discards(candidates);
+        let actual: Vec<Event> = vec![json::from_value(
+            json::json!({"type": "dahai", "actor": 0, "pai": actual, "tsumogiri": false}),
+        )
+        .unwrap()];
+        let actual_ev = details
+            .iter()
+            .find(|d| compare_action_strict(&actual, &d.moves))
+            .and_then(|d| d.review.pt_exp_total);
+        let dev = match (details[0].review.pt_exp_total, actual_ev) {
+            (Some(best), Some(ev)) => best - ev,
+            _ => 0.,
+        };
+        let pai = match actual[0] {
+            Event::Dahai { pai, .. } => pai,
+            _ => unreachable!(),
+        };
+
+        Entry {
+            acceptance,
+            junme: 1,
+            dev,
+            margin: None,
+            actor: 0,
+            pai,
+            is_kakan: false,
+            state: State::new(0),
+            expected: details[0].moves.clone(),
+            actual,
+            details,
+            engine_time: None,
+            note: None,
+            forced: false,
+        }
+    }
+
+    /// Returns the events of East 1 ending in ryukyoku, where seat 0 draws
+    /// and discards each of `turns`, and seat 1 draws and discards 9p in
+    /// between, which seat 0 can not call.
+    pub(crate) fn kyoku_events(haipai: &str, turns: &[(&str, &str)]) -> Vec<Event> {
+        let haipai: Vec<_> = haipai.split(' ').collect();
+        let others = vec!["?"; 13];
+        let mut lines = vec![json::json!({
+            "type": "start_kyoku",
+            "bakaze": "E",
+            "dora_marker": "1p",
+            "kyoku": 1,
+            "honba": 0,
+            "kyotaku": 0,
+            "oya": 0,
+            "scores": [25000, 25000, 25000, 25000],
+            "tehais": [haipai, others, others, others],
+        })];
+        for (i, &(tsumo, dahai)) in turns.iter().enumerate() {
+            if i > 0 {
+                lines.push(json::json!({"type": "tsumo", "actor": 1, "pai": "9p"}));
+                lines.push(
+                    json::json!({"type": "dahai", "actor": 1, "pai": "9p", "tsumogiri": true}),
+                );
+            }
+            lines.push(json::json!({"type": "tsumo", "actor": 0, "pai": tsumo}));
+            lines.push(json::json!({
+                "type": "dahai",
+                "actor": 0,
+                "pai": dahai,
+                "tsumogiri": tsumo == dahai,
+            }));
+        }
+        lines.push(json::json!({"type": "ryukyoku"}));
+        lines.push(json::json!({"type": "end_kyoku"}));
+        lines.push(json::json!({"type": "end_game"}));
+
+        lines
+            .into_iter()
+            .map(|line| json::from_value(line).unwrap())
+            .collect()
+    }
+
+    pub(crate) fn review_args(events: &[Event]) -> ReviewArgs<'_> {
+        ReviewArgs {
+            akochan_exe: Path::new(""),
+            akochan_dir: Path::new(""),
+            tactics_config: Path::new(""),
+            events,
+            target_actor: 0,
+            deviation_threshold: 0.05,
+            verbose: false,
+            timing: false,
+            skip_trivial_discards: false,
+            list_forced: false,
+            on_entry: None,
+        }
+    }
+
+    /// Reviews with the answers scripted for each evaluation in order,
+    /// checking that all of them are used.
+    pub(crate) fn mock_review(
+        review_args: &ReviewArgs,
+        answers: Vec<Vec<DetailedAction>>,
+    ) -> Review {
+        let mut engine = MockEngine::new(answers);
+        let review = review_with_engine(&mut engine, review_args).unwrap();
+        engine.finish().unwrap();
+        review
+    }
+
+    const HAIPAI: &str = "1m 2m 3m 4p 5p 6p 7s 8s 9s E E S N";
+
+    #[test]
+    fn review_with_engine_totals() {
+        // the first discard agrees, the second is worse by a tolerable gap
+        // and the third by far more than the threshold
+        let events = kyoku_events(HAIPAI, &[("W", "N"), ("P", "P"), ("F", "F")]);
+        let answers = vec![
+            discards(&[("N", 1.), ("W", 0.9)]),
+            pass(),
+            discards(&[("S", 1.), ("P", 0.99)]),
+            pass(),
+            discards(&[("S", 1.), ("F", 0.5)]),
+        ];
+        let review = mock_review(&review_args(&events), answers);
+
+        assert_eq!(review.total_reviewed, 3);
+        assert_eq!(review.total_tolerated, 1);
+        assert_eq!(review.total_problems, 1);
+        assert_eq!(review.kyokus.len(), 1);
+        let acceptances: Vec<_> = review.kyokus[0]
+            .entries
+            .iter()
+            .map(|e| e.acceptance)
+            .collect();
+        assert_eq!(
+            acceptances,
+            [
+                Acceptance::Agree,
+                Acceptance::Tolerable,
+                Acceptance::Disagree
+            ],
+        );
+        assert!((review.kyokus[0].entries[2].dev - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tiny_margin_is_not_a_mistake() {
+        // the best leads the runner-up by less than the threshold, and the
+        // actual discard is within the threshold of the runner-up, though
+        // not of the best
+        let events = kyoku_events(HAIPAI, &[("W", "W")]);
+        let answers = vec![discards(&[("N", 1.), ("S", 0.99), ("W", 0.945)])];
+        let review = mock_review(&review_args(&events), answers);
+        let entry = &review.kyokus[0].entries[0];
+        assert_eq!(entry.acceptance, Acceptance::Tolerable);
+        assert!((entry.margin.unwrap() - 0.01).abs() < 1e-9);
+        assert_eq!(review.total_problems, 0);
+
+        // a clear best keeps it a mistake
+        let answers = vec![discards(&[("N", 1.), ("W", 0.945), ("S", 0.9)])];
+        let review = mock_review(&review_args(&events), answers);
+        assert_eq!(review.kyokus[0].entries[0].acceptance, Acceptance::Disagree);
+    }
+
+    #[test]
+    fn trivial_discards_count_but_are_left_out() {
+        // cutting N from the iishanten haipai is trivial, 5m is not even a
+        // yaochuu pai
+        let events = kyoku_events(HAIPAI, &[("W", "N"), ("5m", "5m")]);
+        let answers = vec![
+            discards(&[("N", 1.), ("W", 0.9)]),
+            pass(),
+            discards(&[("5m", 1.), ("S", 0.9)]),
+        ];
+        let args = ReviewArgs {
+            skip_trivial_discards: true,
+            ..review_args(&events)
+        };
+        let review = mock_review(&args, answers);
+        assert_eq!(review.total_reviewed, 2);
+        assert_eq!(review.total_problems, 0);
+
+        let kyoku = &review.kyokus[0];
+        assert_eq!(kyoku.trivial_discards, 1);
+        assert_eq!(kyoku.entries.len(), 1);
+        assert_eq!(kyoku.entries[0].pai, "5m".parse().unwrap());
+        assert!((kyoku.hand_score - 1.).abs() < 1e-9);
+
+        let combined = Review::from_kyokus(review.kyokus);
+        assert_eq!(combined.total_reviewed, 2);
+    }
+
+    #[test]
+    fn tied_moves_within_margin() {
+        let events = kyoku_events(HAIPAI, &[("W", "S")]);
+        let answers = vec![discards(&[("N", 1.), ("S", 0.99), ("W", 0.9)])];
+        let review = mock_review(&review_args(&events), answers);
+        let entry = &review.kyokus[0].entries[0];
+        assert_eq!(entry.acceptance, Acceptance::Tolerable);
+
+        let tied_pais = |margin| -> Vec<_> {
+            entry
+                .tied_moves(margin)
+                .into_iter()
+                .map(|moves| match moves[0] {
+                    Event::Dahai { pai, .. } => pai.to_string(),
+                    _ => unreachable!(),
+                })
+                .collect()
+        };
+        assert_eq!(tied_pais(0.05), ["N", "S"]);
+        assert_eq!(tied_pais(0.2), ["N", "S", "W"]);
+        assert_eq!(tied_pais(0.), ["N"]);
+    }
+
+    #[test]
+    fn forced_decisions_are_not_counted() {
+        let events = kyoku_events(HAIPAI, &[("W", "N"), ("P", "P")]);
+        let answers = || {
+            vec![
+                discards(&[("N", 1.), ("W", 0.9)]),
+                pass(),
+                // the only candidate
+                discards(&[("P", 1.)]),
+            ]
+        };
+
+        let review = mock_review(&review_args(&events), answers());
+        assert_eq!(review.total_reviewed, 1);
+        assert_eq!(review.kyokus[0].entries.len(), 1);
+
+        let args = ReviewArgs {
+            list_forced: true,
+            ..review_args(&events)
+        };
+        let review = mock_review(&args, answers());
+        assert_eq!(review.total_reviewed, 1);
+        let entries = &review.kyokus[0].entries;
+        assert_eq!(entries.len(), 2);
+        assert!(!entries[0].forced);
+        assert!(entries[1].forced);
+        assert_eq!(entries[1].acceptance, Acceptance::Agree);
+        assert_eq!(Review::from_kyokus(review.kyokus).total_reviewed, 1);
+    }
+
+    #[test]
+    fn drop_uninvolved_kyokus() {
+        // agreed, disagreed, and dealt out before the first draw
+        let mut events = kyoku_events(HAIPAI, &[("W", "N")]);
+        events.pop();
+        events.extend(kyoku_events(HAIPAI, &[("W", "W")]));
+        events.pop();
+        events.extend(kyoku_events(HAIPAI, &[]));
+        let answers = vec![
+            discards(&[("N", 1.), ("W", 0.9)]),
+            discards(&[("N", 1.), ("W", 0.9)]),
+        ];
+
+        let review = mock_review(&review_args(&events), answers);
+        let mut kyokus = review.kyokus;
+        assert_eq!(kyokus.len(), 3);
+        assert!(kyokus[0].is_uninvolved());
+        assert!(!kyokus[1].is_uninvolved());
+        assert!(kyokus[2].entries.is_empty());
+        assert!(kyokus[2].is_uninvolved());
+
+        let involved = retain_involved(&mut kyokus);
+        assert_eq!(involved, [false, true, false]);
+        assert_eq!(kyokus.len(), 1);
+        assert_eq!(kyokus[0].entries[0].acceptance, Acceptance::Disagree);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::review::{Entry, KyokuReview};
+use std::fmt;
+
+use convlog::hand_value::HandValue;
+use convlog::mjai::Event;
+
+/// akochan's best EV with and without riichi at a decision of the actor
+/// where riichi could be declared.
+#[derive(Debug, Clone)]
+pub struct RiichiDecision<'a> {
+    pub kyoku: u8,
+    pub honba: u8,
+    pub entry: &'a Entry,
+    pub riichi_ev: f64,
+    /// The best of dama and folding, which are both discards without
+    /// riichi.
+    pub no_riichi_ev: f64,
+}
+
+impl RiichiDecision<'_> {
+    /// Whether the actor declared riichi.
+    #[inline]
+    pub fn declared(&self) -> bool {
+        matches!(self.entry.actual.first(), Some(Event::Reach { .. }))
+    }
+
+    /// How much riichi is worth over the best choice without it, negative if
+    /// riichi is worse.
+    #[inline]
+    pub fn riichi_gain(&self) -> f64 {
+        self.riichi_ev - self.no_riichi_ev
+    }
+
+    /// Returns true if the actor chose the worse side by more than
+    /// `deviation_threshold`.
+    pub fn is_misjudged(&self, deviation_threshold: f64) -> bool {
+        let gain = self.riichi_gain();
+        if self.declared() {
+            gain < -deviation_threshold
+        } else {
+            gain > deviation_threshold
+        }
+    }
+
+    /// Puts a misjudged decision into a [`RiichiCoaching`] category, judging
+    /// the hand by `value`, the [`HandValue`] of the actual discard of the
+    /// decision. Returns None if the decision is not misjudged, see
+    /// [`is_misjudged`](Self::is_misjudged), or fits neither category.
+    ///
+    /// The han counted are those of [`HandValue::han`], so the cheapest
+    /// wait by ron without riichi.
+    pub fn coaching(&self, deviation_threshold: f64, value: &HandValue) -> Option<RiichiCoaching> {
+        if !self.is_misjudged(deviation_threshold) {
+            return None;
+        }
+        let han = value.han?;
+
+        if self.declared() {
+            (han.min <= 1).then_some(RiichiCoaching::TooCheapRiichi)
+        } else {
+            (value.waits.len() >= 2 || han.min >= 3).then_some(RiichiCoaching::TooSlowDama)
+        }
+    }
+}
+
+/// A common riichi misjudgment of beginners, see [`RiichiDecision::coaching`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiichiCoaching {
+    /// Declared riichi on a hand worth at most 1 han without it, where akochan
+    /// would rather stay dama or fold.
+    TooCheapRiichi,
+    /// Stayed dama on a hand waiting on 2 or more kinds of pai, or worth 3
+    /// han or more without riichi, where akochan would rather declare.
+    TooSlowDama,
+}
+
+impl fmt::Display for RiichiCoaching {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::TooCheapRiichi => "too-cheap riichi",
+            Self::TooSlowDama => "too-slow dama",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Returns every decision in `kyokus` where akochan evaluated both riichi and
+/// a discard without riichi.
+///
+/// Decisions are skipped when akochan gives no EV for either side, which
+/// happens on early turns or high shanten.
+pub fn riichi_decisions(kyokus: &[KyokuReview]) -> Vec<RiichiDecision<'_>> {
+    kyokus
+        .iter()
+        .flat_map(|kyoku_review| {
+            kyoku_review.entries.iter().filter_map(move |entry| {
+                let best_ev = |is_riichi: bool| {
+                    entry
+                        .details
+                        .iter()
+                        .filter(|d| match d.moves.first() {
+                            Some(Event::Reach { .. }) => is_riichi,
+                            Some(Event::Dahai { .. }) => !is_riichi,
+                            _ => false,
+                        })
+                        .filter_map(|d| d.review.pt_exp_total)
+                        .reduce(f64::max)
+                };
+                let riichi_ev = best_ev(true)?;
+                let no_riichi_ev = best_ev(false)?;
+
+                Some(RiichiDecision {
+                    kyoku: kyoku_review.kyoku,
+                    honba: kyoku_review.honba,
+                    entry,
+                    riichi_ev,
+                    no_riichi_ev,
+                })
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::entry;
+    use crate::review::{Acceptance, DetailedAction};
+    use convlog::hand_value::HanRange;
+
+    use serde_json as json;
+
+    /// A hand-made entry of seat 0 that can declare riichi discarding N,
+    /// where akochan values riichi at `riichi_ev`, dama at `dama_ev` and
+    /// folding with 1m at 0.5. The actor declared riichi if `declared`, or
+    /// stayed dama otherwise.
+    fn riichi_entry(riichi_ev: f64, dama_ev: f64, declared: bool) -> Entry {
+        let candidate = |moves: &json::Value, ev| -> DetailedAction {
+            json::from_value(json::json!({
+                "moves": moves,
+                "review": {
+                    "total_houjuu_hai_prob_now": 0.,
+                    "total_houjuu_hai_value_now": 0.,
+                    "pt_exp_after": ev,
+                    "pt_exp_total": ev,
+                },
+            }))
+            .unwrap()
+        };
+        let dahai =
+            |pai| json::json!({"type": "dahai", "actor": 0, "pai": pai, "tsumogiri": false});
+        let riichi = json::json!([{"type": "reach", "actor": 0}, dahai("N")]);
+        let dama = json::json!([dahai("N")]);
+        let fold = json::json!([dahai("1m")]);
+
+        let mut details = vec![
+            candidate(&riichi, riichi_ev),
+            candidate(&dama, dama_ev),
+            candidate(&fold, 0.5),
+        ];
+        details.sort_by(|a, b| {
+            b.review
+                .pt_exp_total
+                .partial_cmp(&a.review.pt_exp_total)
+                .unwrap()
+        });
+        let actual = if declared { riichi } else { dama };
+        Entry {
+            actual: json::from_value(actual).unwrap(),
+            expected: details[0].moves.clone(),
+            details,
+            ..entry(Acceptance::Agree, &[("N", 1.)], "N")
+        }
+    }
+
+    fn kyokus(entries: Vec<Entry>) -> Vec<KyokuReview> {
+        vec![KyokuReview {
+            entries,
+            ..Default::default()
+        }]
+    }
+
+    #[test]
+    fn should_have_riichied() {
+        let kyokus = kyokus(vec![
+            riichi_entry(1.5, 1., false),
+            // within the threshold either way
+            riichi_entry(1.02, 1., false),
+            riichi_entry(1.5, 1., true),
+        ]);
+        let decisions = riichi_decisions(&kyokus);
+        assert_eq!(decisions.len(), 3);
+
+        assert!(!decisions[0].declared());
+        assert!((decisions[0].riichi_gain() - 0.5).abs() < 1e-9);
+        assert!(decisions[0].is_misjudged(0.05));
+
+        assert!(!decisions[1].is_misjudged(0.05));
+        assert!(decisions[2].declared());
+        assert!(!decisions[2].is_misjudged(0.05));
+    }
+
+    fn tenpai(waits: Vec<usize>, min_han: u8) -> HandValue {
+        HandValue {
+            turn: 0,
+            junme: 1,
+            shanten: 0,
+            waits,
+            han: Some(HanRange {
+                min: min_han,
+                max: min_han + 2,
+            }),
+        }
+    }
+
+    #[test]
+    fn too_cheap_riichi() {
+        let kyokus = kyokus(vec![riichi_entry(1., 1.5, true)]);
+        let decision = &riichi_decisions(&kyokus)[0];
+
+        let coaching = |value| decision.coaching(0.05, &value);
+        assert_eq!(
+            coaching(tenpai(vec![0], 1)),
+            Some(RiichiCoaching::TooCheapRiichi)
+        );
+        // no yaku without riichi is still cheap
+        assert_eq!(
+            coaching(tenpai(vec![0], 0)),
+            Some(RiichiCoaching::TooCheapRiichi)
+        );
+        // misjudged, but not for being cheap
+        assert_eq!(coaching(tenpai(vec![0], 2)), None);
+        assert_eq!(
+            decision.coaching(
+                0.05,
+                &HandValue {
+                    han: None,
+                    ..tenpai(vec![], 0)
+                }
+            ),
+            None,
+        );
+
+        // riichi was the better side
+        let kyokus = self::kyokus(vec![riichi_entry(1.5, 1., true)]);
+        let decision = &riichi_decisions(&kyokus)[0];
+        assert_eq!(decision.coaching(0.05, &tenpai(vec![0], 1)), None);
+    }
+
+    #[test]
+    fn too_slow_dama() {
+        let kyokus = kyokus(vec![riichi_entry(1.5, 1., false)]);
+        let decision = &riichi_decisions(&kyokus)[0];
+
+        let coaching = |value| decision.coaching(0.05, &value);
+        // a wide wait or a valuable hand
+        assert_eq!(
+            coaching(tenpai(vec![0, 3], 1)),
+            Some(RiichiCoaching::TooSlowDama)
+        );
+        assert_eq!(
+            coaching(tenpai(vec![0], 3)),
+            Some(RiichiCoaching::TooSlowDama)
+        );
+        // a single wait on a hand of 2 han fits neither category
+        assert_eq!(coaching(tenpai(vec![0], 2)), None);
+
+        // dama was the better side
+        let kyokus = self::kyokus(vec![riichi_entry(1., 1.5, false)]);
+        let decision = &riichi_decisions(&kyokus)[0];
+        assert_eq!(decision.coaching(0.05, &tenpai(vec![0, 3], 3)), None);
+    }
+}
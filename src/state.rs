@@ -4,10 +4,10 @@ use anyhow::anyhow;
 use anyhow::{Context, Result};
 use convlog::mjai::{Consumed2, Consumed3, Consumed4, Event};
 use convlog::Pai;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct State {
     #[serde(skip)]
     actor: u8,
@@ -166,7 +166,7 @@ impl State {
 }
 
 #[serde_as]
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum Fuuro {
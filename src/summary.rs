@@ -0,0 +1,301 @@
+use crate::csv::action_to_string;
+use crate::mistake::{self, MistakeCategory};
+use crate::render::{kyoku_to_string, Language};
+use crate::review::{Acceptance, Entry, KyokuReview};
+use std::io::prelude::*;
+
+use anyhow::{Context, Result};
+use convlog::mjai::Event;
+
+/// Writes one line per kyoku with its name, how it ended and the EV the hero
+/// lost in it, which is the sum of the deviations of the reviewed decisions,
+/// multiplied by `ev_scale` and rounded to `ev_precision` decimal places.
+///
+/// A last line tells how many kyokus --skip-uninvolved left out of `kyokus`,
+/// if any.
+pub fn write_summary(
+    mut w: impl Write,
+    kyokus: &[KyokuReview],
+    names: &[String; 4],
+    lang: Language,
+    ev_precision: usize,
+    ev_scale: f64,
+    omitted_kyokus: usize,
+) -> Result<()> {
+    for kyoku_review in kyokus {
+        let label = kyoku_to_string(kyoku_review.kyoku, kyoku_review.honba, lang);
+        let result = end_status_to_string(&kyoku_review.end_status, names, lang);
+        // an empty f64 sum is -0
+        let ev_loss = kyoku_review.entries.iter().fold(0., |sum, e| sum + e.dev) * ev_scale;
+        let ev_loss_label = match lang {
+            Language::Japanese => "EV損失",
+            Language::English => "EV loss",
+        };
+
+        writeln!(
+            w,
+            "{}\t{}\t{} {:.*}",
+            label, result, ev_loss_label, ev_precision, ev_loss,
+        )
+        .context("failed to write summary line")?;
+    }
+
+    if omitted_kyokus > 0 {
+        match lang {
+            Language::Japanese => writeln!(w, "不一致のない{}局を省略", omitted_kyokus),
+            Language::English => writeln!(
+                w,
+                "{} kyoku(s) without any disagreement omitted",
+                omitted_kyokus,
+            ),
+        }
+        .context("failed to write summary line")?;
+    }
+
+    Ok(())
+}
+
+/// Returns a few sentences to share about the reviewed game of `name`, such
+/// as "Alice finished 2nd. Biggest mistake: over-pushing on East 3, 6p
+/// instead of 3m (EV loss 0.123). Mistake rate 3.2% (2 of 62 decisions)."
+///
+/// The placement is left out if it is None, which is the case when only
+/// some kyokus are reviewed. Forced decisions are not counted, while trivial
+/// discards left out of the entries are.
+pub fn game_blurb(
+    kyokus: &[KyokuReview],
+    name: &str,
+    placement: Option<u8>,
+    lang: Language,
+    ev_precision: usize,
+    ev_scale: f64,
+) -> String {
+    let entries = || kyokus.iter().flat_map(|k| &k.entries).filter(|e| !e.forced);
+    let decisions = entries().count() + kyokus.iter().map(|k| k.trivial_discards).sum::<usize>();
+    let mistakes = entries()
+        .filter(|e| matches!(e.acceptance, Acceptance::Disagree))
+        .count();
+    let rate = if decisions == 0 {
+        0.
+    } else {
+        mistakes as f64 / decisions as f64 * 100.
+    };
+    // the first of equal ones is the earliest
+    let biggest = kyokus
+        .iter()
+        .flat_map(|k| k.entries.iter().map(move |e| (k, e)))
+        .filter(|(_, e)| !e.forced && matches!(e.acceptance, Acceptance::Disagree))
+        .reduce(|a, b| if b.1.dev > a.1.dev { b } else { a });
+
+    let mut sentences = vec![];
+    if let Some(placement) = placement {
+        sentences.push(match lang {
+            Language::Japanese => format!("{}は{}位。", name, placement),
+            Language::English => format!("{} finished {}.", name, ordinal(placement)),
+        });
+    }
+    sentences.push(match biggest {
+        Some((kyoku_review, entry)) => {
+            let label = kyoku_to_string(kyoku_review.kyoku, kyoku_review.honba, lang);
+            describe_mistake(entry, &label, lang, ev_precision, ev_scale)
+        }
+        None => match lang {
+            Language::Japanese => "ミスなし。".to_owned(),
+            Language::English => "No mistakes.".to_owned(),
+        },
+    });
+    sentences.push(match lang {
+        Language::Japanese => format!(
+            "ミス率 {:.1}%（{} 手中 {} 手）。",
+            rate, decisions, mistakes
+        ),
+        Language::English => format!(
+            "Mistake rate {:.1}% ({} of {} decisions).",
+            rate, mistakes, decisions,
+        ),
+    });
+
+    let separator = match lang {
+        Language::Japanese => "",
+        Language::English => " ",
+    };
+    sentences.join(separator)
+}
+
+fn describe_mistake(
+    entry: &Entry,
+    label: &str,
+    lang: Language,
+    ev_precision: usize,
+    ev_scale: f64,
+) -> String {
+    let ev_loss = entry.dev * ev_scale;
+    let category = mistake::classify(entry).unwrap_or(MistakeCategory::Other);
+    let actual = action_to_string(&entry.actual);
+    let expected = action_to_string(&entry.expected);
+    match lang {
+        Language::Japanese => format!(
+            "最大のミス: {}の{}、{}ではなく{}（EV損失 {:.*}）。",
+            label,
+            category_to_japanese(category),
+            expected,
+            actual,
+            ev_precision,
+            ev_loss,
+        ),
+        Language::English => format!(
+            "Biggest mistake: {} on {}, {} instead of {} (EV loss {:.*}).",
+            category, label, actual, expected, ev_precision, ev_loss,
+        ),
+    }
+}
+
+fn category_to_japanese(category: MistakeCategory) -> &'static str {
+    match category {
+        MistakeCategory::OverFold => "降りすぎ",
+        MistakeCategory::OverPush => "押しすぎ",
+        MistakeCategory::RiichiTiming => "リーチ判断",
+        MistakeCategory::Naki => "鳴き判断",
+        MistakeCategory::Efficiency => "牌効率",
+        MistakeCategory::Other => "判断",
+    }
+}
+
+/// Returns "1st" to "4th".
+fn ordinal(placement: u8) -> String {
+    let suffix = match placement {
+        1 => "st",
+        2 => "nd",
+        3 => "rd",
+        _ => "th",
+    };
+    format!("{}{}", placement, suffix)
+}
+
+/// Describes the end of a kyoku such as "Alice ron Bob 7700" and "draw",
+/// joining multiple horas with " / ".
+fn end_status_to_string(end_status: &[Event], names: &[String; 4], lang: Language) -> String {
+    let parts: Vec<_> = end_status
+        .iter()
+        .filter_map(|ev| match *ev {
+            Event::Hora {
+                actor,
+                target,
+                deltas,
+                ..
+            } => {
+                let points = deltas.map_or(0, |d| d[actor as usize]);
+                let winner = &names[actor as usize];
+                let s = match (lang, actor == target) {
+                    (Language::Japanese, true) => format!("{} ツモ {}", winner, points),
+                    (Language::Japanese, false) => {
+                        format!("{} ロン {} {}", winner, names[target as usize], points)
+                    }
+                    (Language::English, true) => format!("{} tsumo {}", winner, points),
+                    (Language::English, false) => {
+                        format!("{} ron {} {}", winner, names[target as usize], points)
+                    }
+                };
+                Some(s)
+            }
+            Event::Ryukyoku { .. } => Some(
+                match lang {
+                    Language::Japanese => "流局",
+                    Language::English => "draw",
+                }
+                .to_owned(),
+            ),
+            _ => None,
+        })
+        .collect();
+
+    parts.join(" / ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::entry;
+
+    use serde_json as json;
+
+    fn names() -> [String; 4] {
+        ["Alice", "Bob", "Carol", "Dave"].map(|s| s.to_owned())
+    }
+
+    fn kyokus() -> Vec<KyokuReview> {
+        let ron: Event = json::from_value(json::json!({
+            "type": "hora", "actor": 2, "target": 0, "deltas": [-7700, 0, 7700, 0],
+        }))
+        .unwrap();
+        let draw: Event = json::from_value(json::json!({"type": "ryukyoku"})).unwrap();
+        vec![
+            KyokuReview {
+                kyoku: 0,
+                end_status: vec![ron],
+                entries: vec![
+                    entry(Acceptance::Agree, &[("N", 1.), ("S", 0.5)], "N"),
+                    entry(Acceptance::Disagree, &[("N", 1.), ("S", 0.9)], "S"),
+                ],
+                ..Default::default()
+            },
+            KyokuReview {
+                kyoku: 2,
+                honba: 1,
+                end_status: vec![draw],
+                entries: vec![],
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn write_summary_lines() {
+        let kyokus = kyokus();
+        let mut out = vec![];
+        write_summary(&mut out, &kyokus, &names(), Language::English, 2, 100., 0).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines.len(), kyokus.len());
+        assert!(lines[0].contains("Carol ron Alice 7700"), "{}", lines[0]);
+        assert!(lines[0].ends_with("EV loss 10.00"), "{}", lines[0]);
+        assert!(lines[1].contains("draw"), "{}", lines[1]);
+        assert!(lines[1].ends_with("EV loss 0.00"), "{}", lines[1]);
+
+        let mut out = vec![];
+        write_summary(&mut out, &kyokus, &names(), Language::English, 2, 100., 3).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines.len(), kyokus.len() + 1);
+        assert_eq!(lines[2], "3 kyoku(s) without any disagreement omitted");
+    }
+
+    #[test]
+    fn game_blurb_top_mistake() {
+        let mut kyokus = kyokus();
+        let mut big = entry(Acceptance::Disagree, &[("N", 1.), ("6p", 0.7)], "6p");
+        big.details[0].review.total_houjuu_hai_prob_now = Some(0.02);
+        big.details[1].review.total_houjuu_hai_prob_now = Some(0.1);
+        kyokus[1].entries.push(big);
+        kyokus[1].trivial_discards = 1;
+
+        let blurb = game_blurb(&kyokus, "Alice", Some(2), Language::English, 3, 1.);
+        assert_eq!(
+            blurb,
+            "Alice finished 2nd. \
+            Biggest mistake: over-pushing on East 3-1, 6p instead of 4z (EV loss 0.300). \
+            Mistake rate 50.0% (2 of 4 decisions).",
+        );
+
+        let blurb = game_blurb(&kyokus[..1], "Alice", None, Language::Japanese, 1, 1.);
+        assert!(
+            blurb.starts_with("最大のミス: 東一局の牌効率、4zではなく2z"),
+            "{}",
+            blurb
+        );
+
+        let clean = &kyokus[1..1];
+        let blurb = game_blurb(clean, "Alice", Some(1), Language::English, 3, 1.);
+        assert!(blurb.contains("No mistakes."), "{}", blurb);
+    }
+}
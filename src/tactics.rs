@@ -1,7 +1,17 @@
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
 
+use anyhow::{Context, Result};
+use dunce::canonicalize;
 use serde::{Deserialize, Serialize};
+use serde_json as json;
 use serde_json::Value;
+use tempfile::{NamedTempFile, TempPath};
+
+/// The tactics used when there is no tactics.json to be found.
+pub const DEFAULT_TACTICS: &str = include_str!("../tactics.json");
 
 #[derive(Serialize, Deserialize)]
 pub struct TacticsJson {
@@ -15,3 +25,139 @@ pub struct Tactics {
     #[serde(flatten)]
     pub other_fields: HashMap<String, Value>,
 }
+
+/// The tactics to review with, see [`resolve`].
+pub struct ResolvedTactics {
+    /// The file akochan reads the tactics from.
+    pub path: PathBuf,
+    pub tactics: Tactics,
+    /// Path of the tactics config, or "embedded".
+    pub source: String,
+    /// The temp file at `path` when the tactics are not read from the config
+    /// as is, which is removed when dropped, even on errors.
+    pub temp: Option<TempPath>,
+}
+
+/// Finds the tactics config and overrides its pt with `pt`.
+///
+/// An explicit `path` must exist. Without one, `default_path` is used if it
+/// exists, or else [`DEFAULT_TACTICS`]. akochan only reads tactics from a
+/// file, so the embedded or overridden tactics are written to a temp file.
+pub fn resolve(
+    path: Option<&Path>,
+    default_path: &Path,
+    pt: Option<&[i32]>,
+) -> Result<ResolvedTactics> {
+    let path = path
+        .map(Path::to_path_buf)
+        .or_else(|| default_path.exists().then(|| default_path.to_path_buf()));
+
+    let (canon_path, mut tactics_json): (_, TacticsJson) = if let Some(path) = path {
+        let canon_path = canonicalize(&path)
+            .with_context(|| format!("failed to canonicalize tactics_config path {:?}", path))?;
+
+        // load tactics_config for metadata
+        let tactics_file = File::open(&canon_path)
+            .with_context(|| format!("failed to open tactics_config {:?}", canon_path))?;
+        let tactics_file_reader = BufReader::new(tactics_file);
+        let tactics_json = json::from_reader(tactics_file_reader)
+            .with_context(|| format!("failed to parse tactics_config {:?}", canon_path))?;
+
+        (Some(canon_path), tactics_json)
+    } else {
+        let tactics_json =
+            json::from_str(DEFAULT_TACTICS).context("failed to parse the embedded tactics")?;
+        (None, tactics_json)
+    };
+    let source = match &canon_path {
+        Some(path) => path.display().to_string(),
+        None => "embedded".to_owned(),
+    };
+
+    if let Some(pt) = pt {
+        tactics_json
+            .tactics
+            .jun_pt
+            .iter_mut()
+            .zip(pt)
+            .for_each(|(o, &n)| *o = n);
+    }
+
+    match canon_path {
+        Some(canon_path) if pt.is_none() => Ok(ResolvedTactics {
+            path: canon_path,
+            tactics: tactics_json.tactics,
+            source,
+            temp: None,
+        }),
+        _ => {
+            let mut tmp = NamedTempFile::new().context("failed to create temp file")?;
+            json::to_writer(&mut tmp, &tactics_json).context("failed to write to temp file")?;
+
+            let tmp_path = tmp.into_temp_path();
+            let canon_tmp_path = canonicalize(&tmp_path)
+                .with_context(|| format!("failed to canonicalize temp file path {:?}", tmp_path))?;
+
+            Ok(ResolvedTactics {
+                path: canon_tmp_path,
+                tactics: tactics_json.tactics,
+                source,
+                temp: Some(tmp_path),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn embedded_fallback() {
+        let dir = TempDir::new().unwrap();
+        let missing = dir.path().join("tactics.json");
+
+        let resolved = resolve(None, &missing, None).unwrap();
+        assert_eq!(resolved.source, "embedded");
+        let embedded: TacticsJson = json::from_str(DEFAULT_TACTICS).unwrap();
+        assert_eq!(resolved.tactics.jun_pt, embedded.tactics.jun_pt);
+        // written out for akochan, and gone once dropped
+        let written: TacticsJson =
+            json::from_str(&fs::read_to_string(&resolved.path).unwrap()).unwrap();
+        assert_eq!(written.tactics.jun_pt, embedded.tactics.jun_pt);
+        let path = resolved.path.clone();
+        drop(resolved);
+        assert!(!path.exists());
+
+        let resolved = resolve(None, &missing, Some(&[-1, -2, -3, -4])).unwrap();
+        assert_eq!(resolved.source, "embedded");
+        assert_eq!(resolved.tactics.jun_pt, [-1, -2, -3, -4]);
+    }
+
+    #[test]
+    fn explicit_or_default_path() {
+        let dir = TempDir::new().unwrap();
+        let missing = dir.path().join("missing.json");
+        let err = resolve(Some(&missing), &missing, None).err().unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("failed to canonicalize tactics_config path"));
+
+        // the default path is used as is when it exists
+        let default_path = dir.path().join("tactics.json");
+        fs::write(&default_path, DEFAULT_TACTICS).unwrap();
+        let resolved = resolve(None, &default_path, None).unwrap();
+        assert!(resolved.temp.is_none());
+        assert_eq!(resolved.path, canonicalize(&default_path).unwrap());
+        assert_eq!(resolved.source, resolved.path.display().to_string());
+
+        // an explicit path does not fall back
+        let err = resolve(Some(&missing), &default_path, None).err().unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("failed to canonicalize tactics_config path"));
+    }
+}
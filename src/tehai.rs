@@ -1,5 +1,6 @@
 use convlog::Pai;
 
+use serde::de::{self, Deserialize, Deserializer};
 use serde::ser::{Serialize, SerializeSeq, Serializer};
 
 #[derive(Debug, Clone, Default)]
@@ -40,6 +41,19 @@ impl Serialize for Tehai {
     }
 }
 
+impl<'de> Deserialize<'de> for Tehai {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let tehai = Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| s.parse().map_err(de::Error::custom))
+            .collect::<Result<Vec<Pai>, _>>()?;
+        Ok(Self::from(tehai))
+    }
+}
+
 impl Tehai {
     /// Resets current tehai.
     #[inline]
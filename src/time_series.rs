@@ -0,0 +1,156 @@
+use crate::review::{Acceptance, KyokuReview};
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate};
+use convlog::LogDate;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bucket {
+    Week,
+    Month,
+}
+
+/// The stats of the games in one bucket of dates.
+#[derive(Debug, Clone, Serialize)]
+pub struct Point {
+    /// Such as "2019-W26" for weeks in ISO 8601 and "2019-06" for months.
+    /// None for games without a date, which are always the last point.
+    pub bucket: Option<String>,
+    pub games: usize,
+    pub decisions: usize,
+    pub mistakes: usize,
+    /// Mistakes per decision.
+    pub mistake_rate: f64,
+    /// EV dropped by mistakes and tolerated moves, per decision.
+    pub avg_ev_loss: f64,
+}
+
+impl Bucket {
+    fn label(self, date: LogDate) -> Option<String> {
+        let date = NaiveDate::from_ymd_opt(date.year as i32, date.month as u32, date.day as u32)?;
+        Some(match self {
+            Self::Week => {
+                let week = date.iso_week();
+                format!("{:04}-W{:02}", week.year(), week.week())
+            }
+            Self::Month => format!("{:04}-{:02}", date.year(), date.month()),
+        })
+    }
+}
+
+/// Buckets the reviewed games by their dates and returns the stats of each
+/// bucket in the order of dates.
+///
+/// Forced decisions are not counted, while trivial discards left out of the
+/// entries are.
+pub fn time_series<'a>(
+    games: impl IntoIterator<Item = (Option<LogDate>, &'a [KyokuReview])>,
+    bucket: Bucket,
+) -> Vec<Point> {
+    #[derive(Default)]
+    struct Sum {
+        games: usize,
+        decisions: usize,
+        mistakes: usize,
+        ev_loss: f64,
+    }
+
+    let mut buckets: BTreeMap<Option<String>, Sum> = BTreeMap::new();
+    for (date, kyokus) in games {
+        let label = date.and_then(|d| bucket.label(d));
+        let sum = buckets.entry(label).or_default();
+        sum.games += 1;
+        sum.decisions += kyokus.iter().map(|k| k.trivial_discards).sum::<usize>();
+        let entries = kyokus.iter().flat_map(|k| &k.entries);
+        for entry in entries.filter(|e| !e.forced) {
+            sum.decisions += 1;
+            match entry.acceptance {
+                Acceptance::Disagree => sum.mistakes += 1,
+                Acceptance::Tolerable => (),
+                Acceptance::Agree => continue,
+            }
+            sum.ev_loss += entry.dev;
+        }
+    }
+
+    let per_decision = |v: f64, decisions: usize| {
+        if decisions == 0 {
+            0.
+        } else {
+            v / decisions as f64
+        }
+    };
+    let mut points: Vec<_> = buckets
+        .into_iter()
+        .map(|(bucket, sum)| Point {
+            bucket,
+            games: sum.games,
+            decisions: sum.decisions,
+            mistakes: sum.mistakes,
+            mistake_rate: per_decision(sum.mistakes as f64, sum.decisions),
+            avg_ev_loss: per_decision(sum.ev_loss, sum.decisions),
+        })
+        .collect();
+
+    // None sorts first in the map
+    if points.first().is_some_and(|p| p.bucket.is_none()) {
+        points.rotate_left(1);
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::tests::entry;
+
+    #[test]
+    fn dated_logs() {
+        let mut forced = entry(Acceptance::Agree, &[("N", 1.)], "N");
+        forced.forced = true;
+        let clean = vec![KyokuReview {
+            entries: vec![
+                entry(Acceptance::Agree, &[("N", 1.), ("S", 0.5)], "N"),
+                forced,
+            ],
+            trivial_discards: 1,
+            ..Default::default()
+        }];
+        let sloppy = vec![KyokuReview {
+            entries: vec![entry(Acceptance::Disagree, &[("N", 1.), ("S", 0.5)], "S")],
+            ..Default::default()
+        }];
+        let date = LogDate::from_log_id;
+        let games = || {
+            [
+                (date("2019062701gm-00a9-0000-0a1b2c3d"), &clean[..]),
+                (None, &sloppy),
+                (date("190630-0a1b2c3d-0000-0000-0000-000000000000"), &sloppy),
+                (date("2019070100gm-00a9-0000-0a1b2c3d"), &clean),
+                (date("2019123000gm-00a9-0000-0a1b2c3d"), &sloppy),
+            ]
+        };
+
+        let weeks = time_series(games(), Bucket::Week);
+        let labels: Vec<_> = weeks.iter().map(|p| p.bucket.as_deref()).collect();
+        assert_eq!(
+            labels,
+            [Some("2019-W26"), Some("2019-W27"), Some("2020-W01"), None],
+        );
+        let w26 = &weeks[0];
+        assert_eq!(w26.games, 2);
+        assert_eq!(w26.decisions, 3);
+        assert_eq!(w26.mistakes, 1);
+        assert!((w26.avg_ev_loss - 0.5 / 3.).abs() < 1e-9);
+        assert_eq!(weeks[1].mistake_rate, 0.);
+        assert_eq!(weeks[3].games, 1);
+
+        let months = time_series(games(), Bucket::Month);
+        let labels: Vec<_> = months.iter().map(|p| p.bucket.as_deref()).collect();
+        assert_eq!(
+            labels,
+            [Some("2019-06"), Some("2019-07"), Some("2019-12"), None]
+        );
+    }
+}
@@ -21,3 +21,15 @@ function toggleStyle() {
         }
     }
 }
+
+// kyoku sections are collapsed by default, open the one linked from the
+// table of contents
+function openLinkedKyoku() {
+    var target = document.getElementById(decodeURIComponent(location.hash.slice(1)))
+    var section = target && target.closest("details.kyoku-section")
+    if (section) {
+        section.open = true
+    }
+}
+window.addEventListener("hashchange", openLinkedKyoku)
+window.addEventListener("DOMContentLoaded", openLinkedKyoku)